@@ -0,0 +1,157 @@
+use std::path::Path;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::{resolve_assets_path, GameFonts};
+
+// 阅读障碍友好字体（如OpenDyslexic），需要自行放到assets目录中
+const DYSLEXIA_FONT_FILE: &str = "fonts/dyslexic.ttf";
+// 可以选择的字符放大倍数
+const LETTER_SCALES: [f32; 3] = [1., 1.25, 1.5];
+
+/// 颜色方案，色盲配色避免使用红绿两种颜色区分状态
+#[derive(Deserialize, Serialize, Clone, Copy, Default, PartialEq, Debug)]
+pub enum ColorPalette {
+    #[default]
+    Standard,
+    Deuteranopia, // 绿色盲
+    Protanopia,   // 红色盲
+}
+
+impl ColorPalette {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorPalette::Standard => "标准",
+            ColorPalette::Deuteranopia => "绿色盲",
+            ColorPalette::Protanopia => "红色盲",
+        }
+    }
+
+    pub fn next(&self) -> ColorPalette {
+        match self {
+            ColorPalette::Standard => ColorPalette::Deuteranopia,
+            ColorPalette::Deuteranopia => ColorPalette::Protanopia,
+            ColorPalette::Protanopia => ColorPalette::Standard,
+        }
+    }
+}
+
+/// 玩家的无障碍设置，开始游戏时作为资源插入
+#[derive(Resource, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Accessibility {
+    pub palette: ColorPalette,
+    // 高对比度模式，目标字符使用白色并加上黑色底板
+    pub high_contrast: bool,
+    // 目标字符的放大倍数
+    pub letter_scale: f32,
+    // 减少动画：星星不闪烁，装备效果不旋转，得分不飘动
+    pub reduced_motion: bool,
+    // 目标字符使用阅读障碍友好字体
+    pub dyslexia_font: bool,
+}
+
+impl Default for Accessibility {
+    fn default() -> Self {
+        Accessibility {
+            palette: ColorPalette::Standard,
+            high_contrast: false,
+            letter_scale: 1.,
+            reduced_motion: false,
+            dyslexia_font: false,
+        }
+    }
+}
+
+impl Accessibility {
+    pub fn target_letter_color(&self) -> Color {
+        if self.high_contrast { Color::WHITE } else { Color::srgb_u8(88, 251, 254) }
+    }
+
+    /// 目标字符的底板颜色，只有高对比度模式才显示
+    pub fn letter_backplate(&self) -> Color {
+        if self.high_contrast { Color::BLACK } else { Color::NONE }
+    }
+
+    pub fn letter_size(&self, size: f32) -> f32 {
+        size * self.letter_scale
+    }
+
+    pub fn next_letter_scale(&self) -> f32 {
+        let index = LETTER_SCALES.iter().position(|s| *s == self.letter_scale).unwrap_or(0);
+        LETTER_SCALES[(index + 1) % LETTER_SCALES.len()]
+    }
+
+    /// Boss战句子中等待击毁的字符
+    pub fn checkpoint_waiting(&self) -> Color {
+        if self.high_contrast {
+            return Color::WHITE;
+        }
+        match self.palette {
+            ColorPalette::Standard => Color::srgb_u8(245, 53, 53),
+            ColorPalette::Deuteranopia | ColorPalette::Protanopia => Color::srgb_u8(86, 180, 233),
+        }
+    }
+
+    /// Boss战句子中当前要输入的字符
+    pub fn checkpoint_target(&self) -> Color {
+        match (self.high_contrast, self.palette) {
+            (true, _) => Color::srgb_u8(255, 255, 0),
+            (false, ColorPalette::Standard) => Color::srgb_u8(245, 245, 53),
+            (false, _) => Color::srgb_u8(240, 228, 66),
+        }
+    }
+
+    /// Boss战句子中已经击毁的字符
+    pub fn checkpoint_destroyed(&self) -> Color {
+        if self.high_contrast { Color::srgb_u8(60, 60, 60) } else { Color::srgb_u8(97, 97, 97) }
+    }
+
+    /// 血条从低到高的三种颜色
+    pub fn health_colors(&self) -> [Color; 3] {
+        match self.palette {
+            ColorPalette::Standard => [Color::srgb_u8(234, 67, 53), Color::srgb_u8(251, 210, 8), Color::srgb_u8(52, 168, 82)],
+            ColorPalette::Deuteranopia => [Color::srgb_u8(213, 94, 0), Color::srgb_u8(240, 228, 66), Color::srgb_u8(0, 114, 178)],
+            ColorPalette::Protanopia => [Color::srgb_u8(230, 159, 0), Color::srgb_u8(204, 204, 204), Color::srgb_u8(0, 114, 178)],
+        }
+    }
+}
+
+/// 飞行单元上目标字符的字体及显示样式
+#[derive(SystemParam)]
+pub struct LetterStyle<'w> {
+    pub fonts: Res<'w, GameFonts>,
+    pub accessibility: Res<'w, Accessibility>,
+}
+
+impl LetterStyle<'_> {
+    /// 目标字符的文字，size为没有放大时的字体大小
+    pub fn target_letter(&self, text: String, size: f32) -> impl Bundle {
+        (
+            Text2d::new(text),
+            TextFont {
+                font: self.fonts.letter_font.clone(),
+                font_size: self.accessibility.letter_size(size),
+                ..default()
+            },
+            TextColor(self.accessibility.target_letter_color()),
+            TextBackgroundColor(self.accessibility.letter_backplate()),
+        )
+    }
+}
+
+/// 选择了阅读障碍友好字体并且字体文件存在时，替换目标字符的字体
+pub fn apply_accessibility(
+    mut fonts: ResMut<GameFonts>,
+    accessibility: Res<Accessibility>,
+    asset_server: Res<AssetServer>,
+) {
+    if !accessibility.dyslexia_font {
+        return;
+    }
+    if Path::new(&resolve_assets_path()).join(DYSLEXIA_FONT_FILE).exists() {
+        fonts.letter_font = asset_server.load(DYSLEXIA_FONT_FILE);
+    } else {
+        warn!("Dyslexia-friendly font {} not found", DYSLEXIA_FONT_FILE);
+    }
+}
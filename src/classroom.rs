@@ -0,0 +1,257 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use bevy::prelude::*;
+use crate::{app_data_dir_path, save_game_users, Player, Players, GAME_APP_NAME, MAX_PLAYERS_COUNT, PLAYER_AVATARS};
+
+const CLASSROOM_DIR: &str = "classroom";
+const ROSTER_FILE: &str = "roster.csv";
+const STUDENTS_REPORT_FILE: &str = "students.csv";
+const CLASSES_REPORT_FILE: &str = "classes.csv";
+const HTML_REPORT_FILE: &str = "report.html";
+pub const NO_CLASS_NAME: &str = "未分班";
+// 带BOM的UTF-8文件才能被Excel正确识别中文
+const UTF8_BOM: &str = "\u{feff}";
+
+/// 班级名单和成绩报告所在的目录，只在导出报告时创建
+fn classroom_dir() -> PathBuf {
+    app_data_dir_path(GAME_APP_NAME).join(CLASSROOM_DIR)
+}
+
+/// 玩家所在的班级，没有班级的玩家归入“未分班”
+pub fn class_name(player: &Player) -> &str {
+    if player.class.is_empty() { NO_CLASS_NAME } else { &player.class }
+}
+
+/// 导入班级名单的结果
+pub struct ImportSummary {
+    pub added: usize,
+    pub updated: usize,
+    // 超过玩家数量上限而没有导入的学生
+    pub skipped: usize,
+}
+
+/// 解析CSV文本，支持双引号包含的逗号、换行和转义的双引号
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.trim_start_matches(UTF8_BOM).chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => (),
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+fn html_escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// 从班级名单目录中的roster.csv导入学生，每行依次为姓名、班级、头像，
+/// 已经存在的玩家只更新班级和头像，头像为空或者不存在时自动分配
+pub fn import_roster(players: &mut Players) -> Result<ImportSummary, String> {
+    let path = classroom_dir().join(ROSTER_FILE);
+    let text = fs::read_to_string(&path)
+        .map_err(|e| format!("无法读取班级名单{}：{}", path.display(), e))?;
+    let mut summary = ImportSummary { added: 0, updated: 0, skipped: 0 };
+    for (index, row) in parse_csv(&text).into_iter().enumerate() {
+        let name = row.first().map(|s| s.trim()).unwrap_or_default();
+        // 跳过空行和标题行
+        if name.is_empty() || (index == 0 && ["name", "姓名"].contains(&name.to_lowercase().as_str())) {
+            continue;
+        }
+        let class = row.get(1).map(|s| s.trim().to_owned()).unwrap_or_default();
+        let avatar = row.get(2).map(|s| s.trim())
+            .filter(|a| PLAYER_AVATARS.contains(a))
+            .map(|a| a.to_owned());
+        if let Some(player) = players.0.iter_mut().find(|p| p.name == name) {
+            player.class = class;
+            if let Some(avatar) = avatar {
+                player.avatar = avatar;
+            }
+            summary.updated += 1;
+        } else if players.0.len() >= MAX_PLAYERS_COUNT {
+            summary.skipped += 1;
+        } else {
+            let avatar = avatar.unwrap_or_else(|| PLAYER_AVATARS[players.0.len() % PLAYER_AVATARS.len()].to_owned());
+            players.0.push(Player {
+                name: name.to_owned(),
+                avatar,
+                level: 1,
+                class,
+                ..default()
+            });
+            summary.added += 1;
+        }
+    }
+    save_game_users(players);
+    Ok(summary)
+}
+
+/// 一个班级的汇总数据
+#[derive(Default)]
+struct ClassSummary<'a> {
+    students: Vec<&'a Player>,
+    level_total: u32,
+    // 准确率和每分钟字符数只统计有练习数据的学生
+    accuracy_total: f32,
+    accuracy_count: u32,
+    cpm_total: f32,
+    cpm_count: u32,
+    practice_secs: f32,
+}
+
+fn group_by_class(players: &Players) -> BTreeMap<&str, ClassSummary<'_>> {
+    let mut classes: BTreeMap<&str, ClassSummary> = BTreeMap::new();
+    for player in &players.0 {
+        let summary = classes.entry(class_name(player)).or_default();
+        summary.students.push(player);
+        summary.level_total += player.level;
+        if let Some(accuracy) = player.stats.accuracy() {
+            summary.accuracy_total += accuracy;
+            summary.accuracy_count += 1;
+        }
+        if let Some(cpm) = player.stats.cpm() {
+            summary.cpm_total += cpm;
+            summary.cpm_count += 1;
+        }
+        summary.practice_secs += player.stats.practice_secs;
+    }
+    for summary in classes.values_mut() {
+        summary.students.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+    classes
+}
+
+/// 没有练习数据时显示“-”
+fn stat_text(value: Option<f32>, percent: bool) -> String {
+    match value {
+        Some(v) if percent => format!("{:.1}%", v * 100.),
+        Some(v) => format!("{:.1}", v),
+        None => "-".to_owned(),
+    }
+}
+
+fn average(total: f32, count: u32) -> Option<f32> {
+    (count > 0).then(|| total / count as f32)
+}
+
+fn student_columns(player: &Player) -> [String; 8] {
+    [
+        class_name(player).to_owned(),
+        player.name.clone(),
+        player.level.to_string(),
+        player.lesson.to_string(),
+        player.score.to_string(),
+        stat_text(player.stats.accuracy(), true),
+        stat_text(player.stats.cpm(), false),
+        format!("{:.1}", player.stats.practice_secs / 60.),
+    ]
+}
+
+fn class_columns(class: &str, summary: &ClassSummary) -> [String; 6] {
+    let count = summary.students.len().max(1) as f32;
+    [
+        class.to_owned(),
+        summary.students.len().to_string(),
+        format!("{:.1}", summary.level_total as f32 / count),
+        stat_text(average(summary.accuracy_total, summary.accuracy_count), true),
+        stat_text(average(summary.cpm_total, summary.cpm_count), false),
+        format!("{:.1}", summary.practice_secs / 60.),
+    ]
+}
+
+const STUDENT_HEADERS: [&str; 8] = ["班级", "姓名", "级别", "课程", "分数", "准确率", "每分钟字符数", "练习时间（分钟）"];
+const CLASS_HEADERS: [&str; 6] = ["班级", "人数", "平均级别", "平均准确率", "平均每分钟字符数", "总练习时间（分钟）"];
+
+fn csv_report<const N: usize>(headers: [&str; N], rows: impl Iterator<Item = [String; N]>) -> String {
+    let mut text = UTF8_BOM.to_owned();
+    text += &headers.join(",");
+    text.push('\n');
+    for row in rows {
+        text += &row.iter().map(|v| csv_field(v)).collect::<Vec<_>>().join(",");
+        text.push('\n');
+    }
+    text
+}
+
+fn html_table<const N: usize>(headers: [&str; N], rows: impl Iterator<Item = [String; N]>) -> String {
+    let mut html = String::from("<table>\n<tr>");
+    for header in headers {
+        html += &format!("<th>{}</th>", header);
+    }
+    html += "</tr>\n";
+    for row in rows {
+        html += "<tr>";
+        for value in row {
+            html += &format!("<td>{}</td>", html_escape(&value));
+        }
+        html += "</tr>\n";
+    }
+    html + "</table>\n"
+}
+
+/// 所有学生的成绩，按班级和姓名排列的CSV文本
+pub fn students_csv(players: &Players) -> String {
+    let classes = group_by_class(players);
+    csv_report(STUDENT_HEADERS, classes.values().flat_map(|c| c.students.iter().map(|p| student_columns(p))))
+}
+
+fn classes_csv(players: &Players) -> String {
+    let classes = group_by_class(players);
+    csv_report(CLASS_HEADERS, classes.iter().map(|(class, summary)| class_columns(class, summary)))
+}
+
+/// 包含班级汇总和每个班级学生成绩的网页报告
+pub fn report_html(players: &Players) -> String {
+    let classes = group_by_class(players);
+    let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>打字练习成绩报告</title>\n\
+        <style>body{font-family:sans-serif}table{border-collapse:collapse;margin-bottom:24px}\
+        th,td{border:1px solid #999;padding:4px 12px;text-align:center}th{background:#eee}</style>\n</head>\n<body>\n");
+    html += &format!("<h1>打字练习成绩报告</h1>\n<p>生成时间：{}</p>\n", chrono::Local::now().format("%Y-%m-%d %H:%M"));
+    html += "<h2>班级汇总</h2>\n";
+    html += &html_table(CLASS_HEADERS, classes.iter().map(|(class, summary)| class_columns(class, summary)));
+    for (class, summary) in &classes {
+        html += &format!("<h2>{}</h2>\n", html_escape(class));
+        html += &html_table(STUDENT_HEADERS, summary.students.iter().map(|p| student_columns(p)));
+    }
+    html + "</body>\n</html>\n"
+}
+
+/// 导出每个学生和每个班级的成绩报告，返回报告所在的目录
+pub fn export_reports(players: &Players) -> Result<PathBuf, String> {
+    let dir = classroom_dir();
+    fs::create_dir_all(&dir).map_err(|e| format!("无法创建目录{}：{}", dir.display(), e))?;
+    for (file, content) in [(STUDENTS_REPORT_FILE, students_csv(players)),
+                            (CLASSES_REPORT_FILE, classes_csv(players)),
+                            (HTML_REPORT_FILE, report_html(players))] {
+        fs::write(dir.join(file), content).map_err(|e| format!("无法保存成绩报告{}：{}", file, e))?;
+    }
+    Ok(dir)
+}
@@ -0,0 +1,258 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use bevy::prelude::*;
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use clap::error::ErrorKind;
+use crate::{app_data_dir_path, builtin_warship_sentences, campaign_file_path, classroom, default_levels,
+            fill_level_sentences, load_game_users, set_app_data_dir, validate_levels, GameMode, LevelSettings, Players,
+            GAME_APP_NAME, GAME_APP_TITLE, WARSHIP_SENTENCES_FILE};
+
+/// 命令行指定了玩家时跳过开始界面，直接用这个玩家开始游戏
+#[derive(Resource)]
+pub struct QuickStart {
+    pub player: String,
+    // 闯关模式的级别或者课程模式的第几课，从1开始
+    pub level: Option<u32>,
+    pub mode: GameMode,
+}
+
+/// 启动游戏窗口时使用的命令行选项
+pub struct LaunchOptions {
+    pub quick_start: Option<QuickStart>,
+    // 只在本次运行中覆盖设置中的窗口模式
+    pub fullscreen: Option<bool>,
+}
+
+fn output_arg() -> Arg {
+    Arg::new("output")
+        .long("output")
+        .short('o')
+        .value_name("FILE")
+        .value_parser(value_parser!(PathBuf))
+        .help("保存到文件，默认输出到终端")
+}
+
+fn command() -> Command {
+    Command::new(GAME_APP_NAME)
+        .about(GAME_APP_TITLE)
+        .version(env!("CARGO_PKG_VERSION"))
+        .arg(Arg::new("data-dir")
+            .long("data-dir")
+            .value_name("DIR")
+            .value_parser(value_parser!(PathBuf))
+            .global(true)
+            .help("保存玩家、设置和成绩的数据目录，默认使用系统的用户数据目录"))
+        .arg(Arg::new("player")
+            .long("player")
+            .value_name("NAME")
+            .help("跳过开始界面，直接以这个玩家开始游戏"))
+        .arg(Arg::new("level")
+            .long("level")
+            .value_name("N")
+            .value_parser(value_parser!(u32).range(1..))
+            .requires("player")
+            .help("从第N级开始闯关，课程模式下从第N课开始"))
+        .arg(Arg::new("mode")
+            .long("mode")
+            .value_parser(["arcade", "lesson"])
+            .default_value("arcade")
+            .requires("player")
+            .help("游戏模式：arcade为闯关游戏，lesson为打字课程"))
+        .arg(Arg::new("fullscreen")
+            .long("fullscreen")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("windowed")
+            .help("全屏显示"))
+        .arg(Arg::new("windowed")
+            .long("windowed")
+            .action(ArgAction::SetTrue)
+            .help("窗口显示"))
+        .subcommand(Command::new("players")
+            .about("管理玩家数据")
+            .subcommand_required(true)
+            .subcommand(Command::new("list").about("列出所有玩家"))
+            .subcommand(Command::new("export").about("以JSON格式导出所有玩家的数据").arg(output_arg())))
+        .subcommand(Command::new("stats")
+            .about("练习成绩")
+            .subcommand_required(true)
+            .subcommand(Command::new("export")
+                .about("导出所有玩家的练习成绩")
+                .arg(Arg::new("format")
+                    .long("format")
+                    .value_parser(["csv", "html"])
+                    .default_value("csv")
+                    .help("导出格式"))
+                .arg(output_arg())))
+        .subcommand(Command::new("sentences")
+            .about("战舰句子和关卡文件")
+            .subcommand_required(true)
+            .subcommand(Command::new("validate").about("检查句子文件和关卡文件，找出无法用键盘输入的句子")))
+}
+
+/// Windows的发布版本没有控制台窗口，从命令提示符运行时连接到它的控制台，
+/// 这样帮助、错误信息和子命令的输出才能显示出来
+#[cfg(windows)]
+fn attach_parent_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // 从资源管理器启动时没有父控制台，调用失败也不影响游戏
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
+
+/// 解析命令行参数。指定了子命令时执行后直接退出进程，否则返回启动游戏窗口的选项
+pub fn parse_args() -> LaunchOptions {
+    if std::env::args_os().len() > 1 {
+        attach_parent_console();
+    }
+    let matches = command().get_matches();
+    if let Some(dir) = matches.get_one::<PathBuf>("data-dir") {
+        set_app_data_dir(dir.clone());
+    }
+    if let Some(subcommand) = matches.subcommand() {
+        let result = match subcommand {
+            ("players", sub) => match sub.subcommand() {
+                Some(("list", _)) => list_players(),
+                Some(("export", sub)) => export_players(sub),
+                _ => unreachable!(),
+            },
+            ("stats", sub) => match sub.subcommand() {
+                Some(("export", sub)) => export_stats(sub),
+                _ => unreachable!(),
+            },
+            ("sentences", _) => validate_sentences(),
+            _ => unreachable!(),
+        };
+        if let Err(err) = result {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+        process::exit(0);
+    }
+
+    let quick_start = matches.get_one::<String>("player").map(|name| {
+        let exists = load_game_users().is_ok_and(|players| players.iter().any(|p| &p.name == name));
+        if !exists {
+            command().error(ErrorKind::InvalidValue, format!("找不到玩家“{}”", name)).exit();
+        }
+        QuickStart {
+            player: name.clone(),
+            level: matches.get_one::<u32>("level").copied(),
+            mode: match matches.get_one::<String>("mode").map(|m| m.as_str()) {
+                Some("lesson") => GameMode::Lesson,
+                _ => GameMode::Arcade,
+            },
+        }
+    });
+    let fullscreen = if matches.get_flag("fullscreen") {
+        Some(true)
+    } else if matches.get_flag("windowed") {
+        Some(false)
+    } else {
+        None
+    };
+    LaunchOptions { quick_start, fullscreen }
+}
+
+fn load_players() -> Result<Players, String> {
+    load_game_users()
+        .map(Players)
+        .map_err(|err| format!("无法读取玩家数据：{}", err))
+}
+
+/// 输出到--output指定的文件，没有指定时输出到终端
+fn write_output(matches: &ArgMatches, content: &str) -> Result<(), String> {
+    match matches.get_one::<PathBuf>("output") {
+        Some(path) => fs::write(path, content).map_err(|err| format!("无法保存{}：{}", path.display(), err)),
+        None => {
+            print!("{}", content);
+            Ok(())
+        }
+    }
+}
+
+fn list_players() -> Result<(), String> {
+    let players = load_players()?;
+    if players.0.is_empty() {
+        println!("还没有玩家");
+        return Ok(());
+    }
+    println!("名称\t班级\t级别\t课程\t分数");
+    for player in &players.0 {
+        println!("{}\t{}\t{}\t{}\t{}", player.name, classroom::class_name(player), player.level, player.lesson, player.score);
+    }
+    Ok(())
+}
+
+fn export_players(matches: &ArgMatches) -> Result<(), String> {
+    let players = load_players()?;
+    let json = serde_json::to_string_pretty(&players.0).map_err(|err| err.to_string())?;
+    write_output(matches, &(json + "\n"))
+}
+
+fn export_stats(matches: &ArgMatches) -> Result<(), String> {
+    let players = load_players()?;
+    let content = match matches.get_one::<String>("format").map(|f| f.as_str()) {
+        Some("html") => classroom::report_html(&players),
+        _ => classroom::students_csv(&players),
+    };
+    write_output(matches, &content)
+}
+
+/// 只读取句子文件和关卡文件进行检查，不会创建或修改数据目录中的文件
+fn validate_sentences() -> Result<(), String> {
+    let mut problems = Vec::new();
+    // 文件格式错误时游戏会使用内置的句子和关卡，这里单独报告出来
+    let sentence_file = app_data_dir_path(GAME_APP_NAME).join(WARSHIP_SENTENCES_FILE);
+    let sentences = match fs::read_to_string(&sentence_file) {
+        Ok(content) => serde_json::from_str::<Vec<Vec<String>>>(&content).unwrap_or_else(|err| {
+            problems.push(format!("{}格式错误：{}", sentence_file.display(), err));
+            builtin_warship_sentences()
+        }),
+        Err(_) => builtin_warship_sentences(),
+    };
+    let mut campaign = None;
+    if let Some(path) = campaign_file_path()
+        && let Ok(content) = fs::read_to_string(&path) {
+        match serde_json::from_str::<Vec<LevelSettings>>(&content) {
+            Ok(mut levels) => {
+                fill_level_sentences(&mut levels, &sentences);
+                let mut level_problems = validate_levels(&levels);
+                if levels.is_empty() {
+                    level_problems.push("没有关卡".to_owned());
+                }
+                if level_problems.is_empty() {
+                    campaign = Some(levels);
+                }
+                problems.extend(level_problems.iter().map(|problem| format!("{}：{}", path.display(), problem)));
+            }
+            Err(err) => problems.push(format!("{}格式错误：{}", path.display(), err)),
+        }
+    }
+
+    // 关卡文件有问题时游戏会改用内置的关卡，内置关卡使用句子文件中的句子，同样需要检查
+    let levels = campaign.unwrap_or_else(|| {
+        let mut levels = default_levels();
+        fill_level_sentences(&mut levels, &sentences);
+        problems.extend(validate_levels(&levels));
+        levels
+    });
+    let count: usize = levels.iter().map(|level| level.sentences.len()).sum();
+    println!("检查了{}个关卡的{}个句子", levels.len(), count);
+    if problems.is_empty() {
+        println!("没有发现问题");
+        return Ok(());
+    }
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    Err(format!("发现{}个问题", problems.len()))
+}
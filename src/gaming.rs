@@ -7,93 +7,197 @@ mod exiting;
 mod checkpoint;
 mod upgrade;
 mod failed;
+mod resting;
+pub mod resume;
+mod difficulty;
+mod effects;
+pub mod inventory;
+mod scoring;
+mod lesson;
+mod stats;
+pub mod ghost;
 
 use rand::Rng;
 use bevy::app::App;
 use bevy::math::VectorSpace;
 use bevy::prelude::*;
-use bevy::window::WindowResized;
-use crate::{GamePlayer, GameRoutes, GameLetters, GameFonts, GameState, PlayState, Route, GameSettings, Players, save_game_users, Player, MAX_PLAYER_LEVELS};
-use crate::{DEFAULT_ROUTE_HEIGHT, GAME_INFO_AREA_HEIGHT, GAME_INFO_AREA_MARGIN, MAX_ROUTE_COUNT};
+use bevy::window::{WindowFocused, WindowResized};
+use crate::{GamePlayer, GameRoutes, GameLetters, GameFonts, GameState, PlayState, GameSettings, Players, save_game_users, Player, GameMode, level_star_image, level_star_label};
+use crate::{info_area_height, routes_top, GAME_INFO_AREA_HEIGHT};
+use xtyping_core::{progress, routes};
 use crate::ui::*;
 use common::*;
-use crate::gaming::spawn::{AircraftSpawnState, BombSpawnState, HealthPackSpawnState, ShieldSpawnState};
+use crate::gaming::spawn::{equipment_info, AircraftSpawnState, EquipmentSpawnStates};
+use crate::playtime::PlayTime;
+use crate::keystats::KeyStatsData;
+use crate::theme::{apply_theme, ThemeAsset, ThemeAssets};
+use crate::accessibility::{apply_accessibility, Accessibility};
+use crate::settings::UserSettings;
 
 pub fn play_game_plugin(app: &mut App) {
     app
         .init_resource::<GameRoutes>()
         .init_resource::<GameLetters>()
         .init_resource::<AircraftSpawnState>()
-        .init_resource::<BombSpawnState>()
-        .init_resource::<ShieldSpawnState>()
-        .init_resource::<HealthPackSpawnState>()
+        .init_resource::<EquipmentSpawnStates>()
+        .init_resource::<ActiveEffects>()
+        .init_resource::<Inventory>()
+        .init_resource::<ScoreStreak>()
+        .init_resource::<lesson::LessonStats>()
+        .init_resource::<ghost::GhostRace>()
+        .insert_resource(ghost::GhostRecords::load())
+        .insert_resource(inventory::EquipmentControls::load())
         .init_resource::<FlyingUnitCounter>()
         .init_resource::<SpeedFactor>()
+        .init_resource::<difficulty::DifficultyController>()
         .insert_resource(GameSaveTimer(Timer::from_seconds(10.0, TimerMode::Repeating)))
-        .add_observer(playing::on_bomb_exploded)
+        .add_observer(inventory::on_equipment_collected)
+        .add_observer(effects::on_equipment_activated)
+        .add_observer(scoring::on_score)
+        .add_observer(scoring::on_streak_broken)
         .add_observer(playing::on_update_health_bar)
-        .add_observer(playing::on_shield_activated)
-        .add_observer(playing::on_health_pack_apply)
-        .add_systems(OnEnter(GameState::Gaming), playing_game_setup)
+        .add_observer(playing::on_level_up)
+        .add_observer(lesson::on_lesson_outcome)
+        .add_observer(lesson::on_lesson_wrong_key)
+        .add_observer(stats::on_stats_outcome)
+        .add_observer(stats::on_stats_wrong_key)
+        .add_observer(stats::on_stats_correct_key)
+        .add_observer(ghost::on_ghost_outcome)
+        .add_observer(ghost::on_ghost_warship_letter)
+        .add_observer(playing::on_armor_hit)
+        .add_observer(playing::on_flying_unit_removed)
+        .add_observer(playing::on_flying_unit_added)
+        .add_observer(spawn::on_aircraft_split)
+        .add_observer(resume::on_save_round)
+        .add_observer(difficulty::on_aircraft_outcome)
+        .add_systems(OnEnter(GameState::Gaming), (apply_theme, apply_accessibility, playing_game_setup, apply_hud_settings).chain())
+        .add_systems(OnEnter(GameState::Gaming), ghost::ghost_race_setup)
         .add_systems(OnExit(GameState::Gaming), playing_game_exit)
         .add_systems(OnEnter(PlayState::Splash), splash::game_splash_setup)
-        .add_systems(OnEnter(PlayState::Playing), playing::playground_setup)
+        .add_systems(OnEnter(PlayState::Playing), (playing::playground_setup,
+                                                    resume::restore_round.run_if(resource_exists::<resume::ResumeRound>)).chain())
         .add_systems(OnEnter(PlayState::Paused), paused::paused_setup)
         .add_systems(OnEnter(PlayState::Exiting), exiting::confirm_exit_setup)
         .add_systems(OnEnter(PlayState::Checkpoint), checkpoint::checkpoint_setup)
         .add_systems(OnEnter(PlayState::Upgrading), upgrade::upgrading_setup)
         .add_systems(OnEnter(PlayState::Failed), failed::player_failed_setup)
-        .add_systems(Update, update_game_time)
+        .add_systems(OnEnter(PlayState::Resting), resting::resting_setup)
+        .add_systems(OnEnter(PlayState::LessonDone), lesson::lesson_result_setup)
+        .add_systems(Update, (sync_game_clock, update_game_time).chain())
+        .add_systems(Update, pause_on_focus_lost.run_if(on_message::<WindowFocused>
+            .and(in_state(PlayState::Playing))))
         .add_systems(Update, on_window_resized.run_if(on_message::<WindowResized>
             .and(in_state(GameState::Gaming))))
         .add_systems(Update, (move_space_stars,
-                              twinkle_space_stars,
                               save_game_data).run_if(in_state(GameState::Gaming)))
+        .add_systems(Update, twinkle_space_stars.run_if(in_state(GameState::Gaming)
+            .and(|a: Res<Accessibility>| !a.reduced_motion)))
         .add_systems(Update, restart_game.run_if(in_state(GameState::Restart)))
         .add_systems(Update, splash::fade_tip_messages.run_if(in_state(PlayState::Splash)))
         .add_systems(Update, (spawn::spawn_aircraft,
-                              spawn::spawn_equipment::<Bomb>,
-                              spawn::spawn_equipment::<Shield>,
-                              spawn::spawn_equipment::<HealthPack>,
+                              effects::tick_active_effects,
                               playing::move_flying_unit,
+                              playing::zigzag_aircraft,
                               playing::animate_miss_text,
                               playing::on_player_char_input,
                               playing::on_keyboard_input,
                               playing::update_aircraft_flames,
                               playing::update_player_status,
-                              playing::animate_explosion_sheet).run_if(in_state(PlayState::Playing)))
+                              playing::animate_explosion_sheet,
+                              resting::track_play_time,
+                              stats::track_practice_time).run_if(in_state(PlayState::Playing)))
+        .add_systems(Update, spawn::spawn_equipment.run_if(in_state(PlayState::Playing)))
+        .add_systems(Update, scoring::animate_score_popups.run_if(in_state(PlayState::Playing)))
+        .add_systems(Update, ghost::record_ghost_progress.run_if(in_state(PlayState::Playing)))
+        .add_systems(Update, scoring::update_streak_text.run_if(in_state(GameState::Gaming)
+            .and(resource_changed::<ScoreStreak>)))
+        .add_systems(Update, inventory::use_inventory_equipment.run_if(in_state(PlayState::Playing)))
+        .add_systems(Update, inventory::update_inventory_text.run_if(in_state(GameState::Gaming)
+            .and(resource_changed::<Inventory>)))
+        .add_systems(Update, effects::auto_aim.run_if(in_state(PlayState::Playing)
+            .and(|e: Res<ActiveEffects>| e.is_active(FlyingUnitKind::AutoAim))))
+        .add_systems(Update, difficulty::adjust_difficulty.run_if(in_state(PlayState::Playing)
+            .and(|c: Res<difficulty::DifficultyController>| c.enabled)))
+        .add_systems(Update, difficulty::update_difficulty_text.run_if(in_state(GameState::Gaming)
+            .and(resource_changed::<SpeedFactor>)))
         .add_systems(Update, playing::update_missiles_for_aircraft.run_if(in_state(PlayState::Playing).and(|res: Option<Res<WarshipSentence>>| res.is_none())))
         .add_systems(Update, playing::update_missiles_for_warship.run_if(in_state(PlayState::Playing).and(resource_exists::<WarshipSentence>)))
+        .add_systems(Update, playing::fade_missiles.run_if(in_state(PlayState::Playing)))
         .add_systems(Update, playing::equipment_effect.run_if(in_state(PlayState::Playing).and(|q: Query<(), With<EquipmentEffect>>| !q.is_empty())))
         .add_systems(Update, playing::switch_checkpoint_state.run_if(resource_exists::<CheckpointTimer>))
         .add_systems(Update, playing::update_warship_letter_arrow.run_if(resource_exists_and_changed::<WarshipSentence>))
+        .add_systems(Update, playing::relayout_warship_sentence.run_if(on_message::<WindowResized>
+            .and(resource_exists::<WarshipSentence>)))
         .add_systems(Update, playing::warship_fires.run_if(in_state(PlayState::Playing).and(resource_exists::<WarshipSentence>)))
         .add_systems(Update, spawn::spawn_space_warship.run_if(resource_exists::<SpaceWarshipTimer>))
-        .add_systems(Update, spawn::launch_space_warship.run_if(in_state(PlayState::Playing).and(resource_changed::<FlyingUnitCounter>)))
+        .add_systems(Update, spawn::launch_space_warship.run_if(in_state(PlayState::Playing).and(resource_changed::<FlyingUnitCounter>)
+            .and(|p: Res<GamePlayer>| p.mode == GameMode::Arcade)))
+        .add_systems(Update, lesson::finish_lesson.run_if(in_state(PlayState::Playing).and(resource_changed::<FlyingUnitCounter>)
+            .and(|p: Res<GamePlayer>| p.mode == GameMode::Lesson)))
         .add_systems(Update, paused::on_resume_game.run_if(in_state(PlayState::Paused)))
-        .add_systems(Update, exiting::on_exit_game_button.run_if(in_state(PlayState::Exiting)))
-        .add_systems(Update, exiting::on_cancel_exit_button.run_if(in_state(PlayState::Exiting)))
-        .add_systems(Update, checkpoint::on_exit_game_button.run_if(in_state(PlayState::Checkpoint)))
-        .add_systems(Update, checkpoint::on_continue_game_button.run_if(in_state(PlayState::Checkpoint)))
+        .add_plugins((crate::widgets::dialog_plugin::<exiting::ExitChoice>,
+                      crate::widgets::dialog_plugin::<checkpoint::CheckpointChoice>,
+                      crate::widgets::dialog_plugin::<upgrade::UpgradeContinue>,
+                      crate::widgets::dialog_plugin::<failed::FailedChoice>,
+                      crate::widgets::dialog_plugin::<lesson::LessonChoice>,
+                      crate::widgets::dialog_plugin::<resting::RestChoice>))
+        .add_systems(Update, exiting::on_exit_choice.run_if(in_state(PlayState::Exiting)))
+        .add_systems(Update, checkpoint::on_checkpoint_choice.run_if(in_state(PlayState::Checkpoint)))
         .add_systems(Update, upgrade::on_continue_game_button.run_if(in_state(PlayState::Upgrading)))
-        .add_systems(Update, failed::on_exit_game_button.run_if(in_state(PlayState::Failed)))
-        .add_systems(Update, failed::on_continue_game_button.run_if(in_state(PlayState::Failed)))
+        .add_systems(Update, failed::on_failed_choice.run_if(in_state(PlayState::Failed)))
+        .add_systems(Update, lesson::on_lesson_choice.run_if(in_state(PlayState::LessonDone)))
+        .add_systems(Update, (resting::update_rest_countdown,
+                              resting::on_rest_choice).run_if(in_state(PlayState::Resting)))
     ;
 }
 
+/// 按照用户设置隐藏连击倍数和难度信息
+fn apply_hud_settings(
+    settings: Res<UserSettings>,
+    mut streak: Single<&mut Visibility, With<StreakText>>,
+    difficulty: Single<&ChildOf, With<DifficultyText>>,
+    mut nodes: Query<&mut Visibility, Without<StreakText>>,
+) {
+    if !settings.show_streak {
+        **streak = Visibility::Hidden;
+    }
+    if !settings.show_difficulty
+        && let Ok(mut visibility) = nodes.get_mut(difficulty.parent()) {
+        *visibility = Visibility::Hidden;
+    }
+}
+
 fn playing_game_setup(mut commands: Commands, 
                       game_player: Res<GamePlayer>,
                       game_settings: Res<GameSettings>,
                       fonts: Res<GameFonts>, 
-                      asset_server: Res<AssetServer>, 
+                      assets: ThemeAssets,
+                      accessibility: Res<Accessibility>,
                       time: Res<Time>, 
                       window: Single<&Window>,
                       mut aircraft_spawn_state: ResMut<AircraftSpawnState>,
-                      mut bomb_spawn_state: ResMut<BombSpawnState>,
-                      mut shield_spawn_state: ResMut<ShieldSpawnState>,
-                      mut health_pack_spawn_state: ResMut<HealthPackSpawnState>,
+                      mut equipment_spawn_states: ResMut<EquipmentSpawnStates>,
                       mut flying_unit_counter: ResMut<FlyingUnitCounter>,
+                      mut inventory: ResMut<Inventory>,
+                      controls: Res<inventory::EquipmentControls>,
+                      mut play_time: ResMut<PlayTime>,
+                      resume: Option<Res<resume::ResumeRound>>,
                       mut next_state: ResMut<NextState<PlayState>>) {
+    commands.insert_resource(difficulty::DifficultyController::new(&game_player));
+    // 课程模式使用由课程生成的关卡
+    let active_level = match game_player.mode {
+        GameMode::Arcade => game_settings.level(game_player.player.level).clone(),
+        GameMode::Lesson => {
+            let curriculum = &game_settings.curriculum;
+            curriculum.lesson_level(curriculum.lesson_index(&game_player.player))
+        }
+    };
+    commands.insert_resource(ActiveLevel(active_level));
+    commands.insert_resource(lesson::LessonStats::default());
+    // 继续上次保存的游戏时，先恢复计数器以便界面显示正确的数值
+    *flying_unit_counter = resume.as_ref().map(|r| r.0.counter.clone()).unwrap_or_default();
+    *inventory = Inventory(resume.as_ref().map(|r| r.0.inventory.clone()).unwrap_or_default());
+    let game_secs = resume.as_ref().map(|r| r.0.game_secs).unwrap_or_default();
     commands.spawn((
         DespawnOnExit(GameState::Gaming),
         Node {
@@ -176,23 +280,34 @@ fn playing_game_setup(mut commands: Commands,
                                 ));
                             });
                             let avatar = format!("avatars/{}.png", player.avatar.as_str());
-                            spawn_image_node(builder, &asset_server, &avatar, Vec2::splat(64.), 0., 3.);
+                            spawn_image_node(builder, &assets.asset_server, &avatar, Vec2::splat(64.), 0., 3.);
                         });
                     });
                 // 用户名称
                 spawn_info_text(builder, &game_player.player.name, INFO_TEXT_COLOR, fonts.ui_font.clone(), 28.);
                 // 用户等级
-                spawn_marked_image(builder, LevelStarImage, &asset_server, &format!("images/star-{}.png", player.level),
-                                 Vec2::new(24.*(player.level as f32), 24.), 0., 0.);
+                builder.spawn(Node {
+                    align_items: AlignItems::Center,
+                    ..default()
+                }).with_children(|builder| {
+                    let (star_image, stars) = level_star_image(player.level);
+                    spawn_marked_image(builder, LevelStarImage, &assets.asset_server, &star_image,
+                                       Vec2::new(24.*(stars as f32), 24.), 0., 0.);
+                    spawn_marked_text(builder, LevelStarText, &level_star_label(player.level), INFO_TEXT_COLOR,
+                                      fonts.ui_font.clone(), 20.);
+                });
                 // 用户分数
                 builder.spawn(Node {
                     justify_content: JustifyContent::FlexEnd,
+                    align_items: AlignItems::Center,
                     ..default()
                 }).with_children(|builder| {
+                    // 连击倍数
+                    spawn_marked_text(builder, StreakText, "", scoring::STREAK_TEXT_COLOR, fonts.ui_font.clone(), 18.);
                     spawn_marked_text(builder, PlayerScore, &format!("{}", player.score), INFO_TEXT_COLOR, fonts.ui_font.clone(), 28.);
                 });
                 // 玩家血条
-                spawn_health_bar(builder, HealthBar{role: GameRole::Player, value: HEALTH_MAX_VALUE}, 100, 3);
+                spawn_health_bar(builder, HealthBar{role: GameRole::Player, value: HEALTH_MAX_VALUE}, 100, 3, &accessibility);
             });
 
         // 中间的对战图标及时间
@@ -206,7 +321,7 @@ fn playing_game_setup(mut commands: Commands,
                 ..default()
             },
         ).with_children(|builder| {
-            spawn_image_node(builder, &asset_server, "images/vs.png", Vec2::splat(56.), 0., 0.);
+            spawn_image_node(builder, &assets.asset_server, "images/vs.png", Vec2::splat(56.), 0., 0.);
             builder.spawn((
                 Text::new("00:00:00"),
                 TextFont {
@@ -216,10 +331,12 @@ fn playing_game_setup(mut commands: Commands,
                 },
                 TextColor(INFO_TEXT_COLOR),
                 GameTime {
-                    start_time: time.elapsed_secs_f64(),
+                    start_time: time.elapsed_secs_f64() - game_secs,
                     last_second: 0
                 }
             ));
+            // 与最好成绩比较的进度，还没有最好成绩时为空
+            spawn_marked_text(builder, GhostMarker, "", INFO_TEXT_COLOR, fonts.ui_font.clone(), 14.);
         });
 
         // 右边的敌人及其它资源信息
@@ -235,6 +352,7 @@ fn playing_game_setup(mut commands: Commands,
                     GridTrack::flex(1.),
                     GridTrack::flex(1.),
                     GridTrack::flex(1.),
+                    GridTrack::flex(1.2),
                 ],
                 grid_template_rows: vec![
                     GridTrack::flex(2.),
@@ -246,77 +364,123 @@ fn playing_game_setup(mut commands: Commands,
             },
         ).with_children(|builder| {
             builder.spawn(Node::default()).with_children(|builder| {
-                spawn_image_node(builder, &asset_server, "images/fighter-jet.png", Vec2::splat(30.), 2., 4.);
-                spawn_marked_text(builder, FlyingUnitText(FlyingUnitKind::Aircraft), "0/0", INFO_TEXT_COLOR, fonts.ui_font.clone(), 28.);
+                spawn_image_node(builder, &assets.asset_server, &assets.theme.path(ThemeAsset::CounterIcon), Vec2::splat(30.), 2., 4.);
+                spawn_marked_text(builder, FlyingUnitText(FlyingUnitKind::Aircraft), &format!("{}/{}", flying_unit_counter.destroyed, flying_unit_counter.missed), INFO_TEXT_COLOR, fonts.ui_font.clone(), 28.);
             });
             builder.spawn(Node::default()).with_children(|builder| {
-                spawn_image_node(builder, &asset_server, "images/bomb.png", Vec2::splat(30.), 2., 4.);
-                spawn_marked_text(builder, FlyingUnitText(FlyingUnitKind::Bomb), "0", INFO_TEXT_COLOR, fonts.ui_font.clone(), 28.);
+                spawn_image_node(builder, &assets.asset_server, &assets.theme.path(ThemeAsset::Bomb), Vec2::splat(30.), 2., 4.);
+                spawn_marked_text(builder, FlyingUnitText(FlyingUnitKind::Bomb), &format!("{}", flying_unit_counter.bomb), INFO_TEXT_COLOR, fonts.ui_font.clone(), 28.);
             });
             builder.spawn(Node::default()).with_children(|builder| {
-                spawn_image_node(builder, &asset_server, "images/first-aid-kit.png", Vec2::splat(30.), 2., 4.);
-                spawn_marked_text(builder, FlyingUnitText(FlyingUnitKind::HealthPack), "0", INFO_TEXT_COLOR, fonts.ui_font.clone(), 28.);
+                spawn_image_node(builder, &assets.asset_server, &assets.theme.path(ThemeAsset::HealthPack), Vec2::splat(30.), 2., 4.);
+                spawn_marked_text(builder, FlyingUnitText(FlyingUnitKind::HealthPack), &format!("{}", flying_unit_counter.health_pack), INFO_TEXT_COLOR, fonts.ui_font.clone(), 28.);
             });
             builder.spawn(Node::default()).with_children(|builder| {
-                spawn_image_node(builder, &asset_server, "images/shield.png", Vec2::splat(30.), 2., 4.);
-                spawn_marked_text(builder, FlyingUnitText(FlyingUnitKind::Shield), "0", INFO_TEXT_COLOR, fonts.ui_font.clone(), 28.);
+                spawn_image_node(builder, &assets.asset_server, &assets.theme.path(ThemeAsset::Shield), Vec2::splat(30.), 2., 4.);
+                spawn_marked_text(builder, FlyingUnitText(FlyingUnitKind::Shield), &format!("{}", flying_unit_counter.shield), INFO_TEXT_COLOR, fonts.ui_font.clone(), 28.);
+            });
+            // 当前难度，自动调整时显示为绿色
+            builder.spawn(Node {
+                align_items: AlignItems::Center,
+                ..default()
+            }).with_children(|builder| {
+                spawn_info_text(builder, "难度", INFO_TEXT_COLOR, fonts.ui_font.clone(), 16.);
+                spawn_marked_text(builder, DifficultyText, "x1.00", INFO_TEXT_COLOR, fonts.ui_font.clone(), 28.);
             });
             // 敌方血条
-            spawn_health_bar(builder, HealthBar{role: GameRole::Enemy, value: HEALTH_MAX_VALUE}, 100, 4);
+            spawn_health_bar(builder, HealthBar{role: GameRole::Enemy, value: HEALTH_MAX_VALUE}, 100, 5, &accessibility);
         });
     });
-    spawn_space_stars(&mut commands, &asset_server, window);
+    spawn_inventory_bar(&mut commands, &fonts, &assets, &controls, &inventory);
+    spawn_space_stars(&mut commands, &assets, window);
 
     *aircraft_spawn_state = AircraftSpawnState::default();
 
-    // 初始化炸弹的生成参数
-    *bomb_spawn_state = BombSpawnState::default();
-    let state = &mut bomb_spawn_state.as_mut().0;
-    state.speeds = game_settings.level_speeds.clone();
-    state.intervals = game_settings.bomb_intervals.clone();
-    //state.spawn = true;
-
-    // 初始化护盾的生成参数
-    *shield_spawn_state = ShieldSpawnState::default();
-    let state = &mut shield_spawn_state.as_mut().0;
-    state.speeds = game_settings.level_speeds.clone();
-    state.intervals = game_settings.shield_intervals.clone();
-    //state.spawn = true;
-
-    // 初始化血包的生成参数
-    *health_pack_spawn_state = HealthPackSpawnState::default();
-    let state = &mut health_pack_spawn_state.as_mut().0;
-    state.speeds = game_settings.level_speeds.clone();
-    state.intervals = game_settings.health_pack_intervals.clone();
-    //state.spawn = true;
-
-    *flying_unit_counter = FlyingUnitCounter::default();
-
-    next_state.set(PlayState::Splash);
+    // 初始化所有装备的生成参数
+    equipment_spawn_states.reset();
+    commands.insert_resource(ActiveEffects::default());
+    commands.insert_resource(ScoreStreak::default());
+
+    if let Some(ref resume) = resume {
+        let round = &resume.0;
+        aircraft_spawn_state.timer = Timer::from_seconds(round.aircraft_spawn.remaining, TimerMode::Once);
+        aircraft_spawn_state.count = round.aircraft_spawn.count;
+        for (kind, state) in equipment_spawn_states.0.iter_mut() {
+            if let Some(saved) = round.equipment_spawn.get(kind) {
+                saved.restore(state);
+            }
+        }
+        commands.trigger(UpdateHealthBarEvent(flying_unit_counter.destroyed as u16));
+    }
+
+    // 还在休息时间内的玩家直接进入休息界面
+    if play_time.rest_reason(&game_player.player.name).is_some() {
+        next_state.set(PlayState::Resting);
+    } else {
+        next_state.set(PlayState::Splash);
+    }
 }
 
-pub fn calculate_upgrade_percent(player: &Player, settings: &GameSettings) -> f32 {
-    if player.level == MAX_PLAYER_LEVELS {
-        return 100.;
+/// 信息栏下方的道具栏，显示每种装备的快捷键及剩余数量
+fn spawn_inventory_bar(
+    commands: &mut Commands,
+    fonts: &GameFonts,
+    assets: &ThemeAssets,
+    controls: &inventory::EquipmentControls,
+    inventory: &Inventory,
+) {
+    if controls.instant {
+        return;
     }
-    let mut scores = player.score;
-    for i in 0..player.level {
-        if scores >= settings.upgrade_scores[i as usize] {
-            scores -= settings.upgrade_scores[i as usize];
-        } else {
-            break;
+    commands.spawn((
+        DespawnOnExit(GameState::Gaming),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(GAME_INFO_AREA_HEIGHT + 10.),
+            right: Val::Px(10.),
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(12.),
+            ..default()
+        },
+    )).with_children(|builder| {
+        for (kind, name) in &controls.keys {
+            let Some(info) = equipment_info(*kind) else {
+                continue;
+            };
+            if !controls.storable(*kind) {
+                continue;
+            }
+            builder.spawn(Node {
+                align_items: AlignItems::Center,
+                ..default()
+            }).with_children(|builder| {
+                spawn_info_text(builder, name, INFO_TEXT_COLOR, fonts.info_font.clone(), 12.);
+                spawn_image_node(builder, &assets.asset_server, &assets.theme.path(info.texture), Vec2::splat(20.), 2., 0.);
+                let count = inventory.count(*kind);
+                let color = INFO_TEXT_COLOR.with_alpha(if count > 0 { 1. } else { 0.4 });
+                spawn_marked_text(builder, InventoryText(*kind), &format!("{}", count), color, fonts.ui_font.clone(), 18.);
+            });
         }
-    }
+    });
+}
 
-    100. * scores as f32 / settings.upgrade_scores[player.level as usize - 1] as f32
+pub fn calculate_upgrade_percent(player: &Player, settings: &GameSettings) -> f32 {
+    progress::upgrade_percent(&settings.upgrade_scores(), player.level, player.score)
 }
 
-fn update_and_save_player(player: &Player, players: &mut Players) {
+fn update_and_save_player(game_player: &GamePlayer, players: &mut Players) {
+    let player = &game_player.player;
     for p in players.0.iter_mut() {
         if player.name == p.name {
             if player != p {
-                p.level = player.level;
-                p.score = player.score;
+                // 命令行跳级时级别、分数和课程都是临时的，只保存练习成绩
+                if !game_player.jumped {
+                    p.level = player.level;
+                    p.score = player.score;
+                    p.lesson = player.lesson;
+                }
+                p.stats = player.stats.clone();
                 save_game_users(players);
                 break;
             }
@@ -324,8 +488,11 @@ fn update_and_save_player(player: &Player, players: &mut Players) {
     }
 }
 
-fn playing_game_exit(mut players: ResMut<Players>, game_player: Res<GamePlayer>) {
-    update_and_save_player(&game_player.player, &mut players);
+fn playing_game_exit(mut players: ResMut<Players>, game_player: Res<GamePlayer>, play_time: Res<PlayTime>,
+                     key_stats: Res<KeyStatsData>) {
+    update_and_save_player(&game_player, &mut players);
+    play_time.save();
+    key_stats.save();
 }
 
 fn restart_game(mut next_state: ResMut<NextState<GameState>>) {
@@ -336,7 +503,8 @@ fn spawn_health_bar(
     builder: &mut ChildSpawnerCommands,
     health_bar: HealthBar,
     health: u16,
-    span: u16
+    span: u16,
+    accessibility: &Accessibility,
 ) {
     builder.spawn((
         Node {
@@ -366,7 +534,7 @@ fn spawn_health_bar(
                 if health_bar.role == GameRole::Player { |i| i } else { |i| HEALTH_MAX_VALUE - i  };
             for i in 0..HEALTH_MAX_VALUE {
                 let color = if i < health {
-                    gradient_health_bar_color(index_fn(i))
+                    gradient_health_bar_color(index_fn(i), accessibility)
                 } else {
                     Color::srgb_u8(70,70,70)
                 };
@@ -380,10 +548,8 @@ fn spawn_health_bar(
     });
 }
 
-pub fn gradient_health_bar_color(value: u16) -> Color {
-    let red    = Color::srgb_u8(234, 67, 53).to_linear();
-    let yellow = Color::srgb_u8(251, 210, 8).to_linear();
-    let green  = Color::srgb_u8(52, 168, 82).to_linear();
+pub fn gradient_health_bar_color(value: u16, accessibility: &Accessibility) -> Color {
+    let [red, yellow, green] = accessibility.health_colors().map(|c| c.to_linear());
 
     let c = if value <= 50 {
         let t = value as f32 / 50.0;
@@ -403,9 +569,26 @@ struct SpaceStar {
     rate: f32,
 }
 
-fn spawn_space_stars(commands: &mut Commands, asset_server: &AssetServer, window: Single<&Window>) {
+/// 主题中的背景图片，铺满整个窗口
+#[derive(Component)]
+struct ThemeBackground;
+
+fn spawn_space_stars(commands: &mut Commands, assets: &ThemeAssets, window: Single<&Window>) {
     let mut rng = rand::rng();
-    let texture = asset_server.load("images/space_star.png");
+    let texture = assets.load(ThemeAsset::SpaceStar);
+
+    if let Some(background) = assets.theme.background() {
+        commands.spawn((
+            DespawnOnExit(GameState::Gaming),
+            Sprite {
+                image: assets.asset_server.load(background.to_owned()),
+                custom_size: Some(window.size()),
+                ..default()
+            },
+            Transform::from_translation(Vec3::new(0., 0., -10.)),
+            ThemeBackground,
+        ));
+    }
 
     let layers = vec![
         (30, 15.0_f32..25.0_f32, 0.10_f32..0.20_f32, -1.0_f32),  // 远（慢）
@@ -445,6 +628,26 @@ fn spawn_space_stars(commands: &mut Commands, asset_server: &AssetServer, window
     }
 }
 
+/// 只有在玩家交互状态下游戏时钟才会走动，暂停、退出确认和提示等状态都会冻结游戏时间
+fn sync_game_clock(state: Option<Res<State<PlayState>>>, mut time: ResMut<Time<Virtual>>) {
+    let playing = state.is_none_or(|s| *s.get() == PlayState::Playing);
+    if playing && time.is_paused() {
+        time.unpause();
+    } else if !playing && !time.is_paused() {
+        time.pause();
+    }
+}
+
+/// 窗口失去焦点时自动暂停游戏
+fn pause_on_focus_lost(
+    mut events: MessageReader<WindowFocused>,
+    mut next_state: ResMut<NextState<PlayState>>,
+) {
+    if let Some(event) = events.read().last() && !event.focused {
+        next_state.set(PlayState::Paused);
+    }
+}
+
 pub fn update_game_time(
     time: Res<Time>,
     mut query: Query<(&mut Text, &mut GameTime)>,
@@ -463,50 +666,79 @@ pub fn update_game_time(
     }
 }
 
-fn compute_route_count(window_height: f32) -> usize {
-    let total_height = window_height - GAME_INFO_AREA_HEIGHT - GAME_INFO_AREA_MARGIN;
-    let route_count = (total_height / DEFAULT_ROUTE_HEIGHT) as usize;
-    if route_count > MAX_ROUTE_COUNT { MAX_ROUTE_COUNT } else { route_count }
+fn compute_route_count(window_height: f32, ui_scale: f32) -> usize {
+    routes::route_count(window_height - info_area_height(ui_scale))
 }
 
+/// 窗口大小变化时需要移到新航道的飞行单元
+type RoutedUnits<'w, 's> = Query<'w, 's, (
+    &'static mut FlyingUnit,
+    &'static mut Transform,
+    Option<&'static mut ZigZag>,
+    Option<&'static Aircraft>,
+), Without<FighterJet>>;
+
 fn on_window_resized(
     mut resize_events: MessageReader<WindowResized>,
     mut commands: Commands,
     mut game_routes: ResMut<GameRoutes>,
     mut game_player: ResMut<GamePlayer>,
-    asset_server: Res<AssetServer>,
-    stars: Query<Entity, With<SpaceStar>>,
+    assets: ThemeAssets,
+    ui_scale: Res<UiScale>,
+    stars: Query<Entity, Or<(With<SpaceStar>, With<ThemeBackground>)>>,
     mut fighter_jet: Single<&mut Transform, With<FighterJet>>,
+    mut units: RoutedUnits,
+    mut aircraft_spawn: ResMut<AircraftSpawnState>,
     window: Single<&Window>
 ) {
     if let Some(_) = resize_events.read().last() {
         // 调整玩家战斗机位置
         fighter_jet.translation.x = FIGHTER_JET_MARGIN - window.width()/2.;
 
-        // 重新计算航道信息
-        let route_count = compute_route_count(window.height());
-        let last_route_count = game_routes.empty_routes.len() + game_routes.used_routes.len();
-        if route_count > last_route_count {
-            for i in 0..route_count-last_route_count {
-                game_routes.empty_routes.push(Route {
-                    id: (i+last_route_count) as i32,
-                    entities: Vec::new(),
-                })
+        // 重新计算航道信息，窗口变小时超出窗口的航道上的飞行单元移到其它航道
+        let x = |e: &Entity| units.get(*e).map_or(f32::INFINITY, |(_, t, _, _)| t.translation.x);
+        let moved = game_routes.resize(compute_route_count(window.height(), ui_scale.0), &mut rand::rng(),
+                                       MIN_ROUTE_SPACING, |a, b| (x(a) - x(b)).abs());
+        for (entity, id) in moved {
+            let Ok((mut unit, _, _, aircraft)) = units.get_mut(entity) else {
+                continue;
+            };
+            match id {
+                Some(id) => unit.route = id,
+                None => {
+                    // 其它航道都太拥挤时移除这个飞行单元，敌机稍后重新生成，否则永远等不到战舰出现
+                    if aircraft.is_some_and(|ac| ac.kind != EnemyKind::Fragment) {
+                        aircraft_spawn.count = aircraft_spawn.count.saturating_sub(1);
+                    }
+                    commands.entity(entity).despawn();
+                }
+            }
+        }
+        // 航道从信息栏下方开始排列，窗口高度变化后所有飞行单元都要移到航道新的位置
+        let top = routes_top(window.height(), ui_scale.0);
+        for (unit, mut transform, zigzag, _) in &mut units {
+            if unit.kind == FlyingUnitKind::Warship {
+                continue;
+            }
+            let y = routes::route_position(unit.route, top);
+            transform.translation.y = y;
+            if let Some(mut zigzag) = zigzag {
+                zigzag.target_y = y;
             }
         }
 
         game_player.safe_position = -(window.width() / 2. - FIGHTER_JET_MARGIN - FIGHTER_JET_SIZE * FIGHTER_JET_SCALE - 100.);
 
-        // 重新生成星空
+        // 重新生成背景和星空
         for entity in &stars {
             commands.entity(entity).despawn();
         }
-        spawn_space_stars(&mut commands, &asset_server, window);
+        spawn_space_stars(&mut commands, &assets, window);
     }
 }
 
 fn move_space_stars(
-    time: Res<Time>,
+    time: Res<Time<Real>>,
     mut query: Query<(&mut Transform, &SpaceStar)>,
     window: Single<&Window>,
 ) {
@@ -522,7 +754,7 @@ fn move_space_stars(
 }
 
 /// 星星闪烁
-fn twinkle_space_stars(time: Res<Time>, mut query: Query<(&mut Sprite, &mut SpaceStar)>) {
+fn twinkle_space_stars(time: Res<Time<Real>>, mut query: Query<(&mut Sprite, &mut SpaceStar)>) {
     let dt = time.delta_secs();
     for (mut sprite, mut star) in &mut query {
         star.phase += star.rate * dt;
@@ -532,12 +764,16 @@ fn twinkle_space_stars(time: Res<Time>, mut query: Query<(&mut Sprite, &mut Spac
 }
 
 fn save_game_data (
-    time: Res<Time>,
+    time: Res<Time<Real>>,
     mut timer: ResMut<GameSaveTimer>,
     mut players: ResMut<Players>,
     game_player: Res<GamePlayer>,
+    play_time: Res<PlayTime>,
+    key_stats: Res<KeyStatsData>,
 ) {
     if timer.0.tick(time.delta()).just_finished() {
-        update_and_save_player(&game_player.player, &mut players);
+        update_and_save_player(&game_player, &mut players);
+        play_time.save();
+        key_stats.save();
     }
 }
\ No newline at end of file
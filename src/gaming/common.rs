@@ -1,9 +1,11 @@
 use bevy::color::Color;
-use bevy::prelude::{Component, Deref, DerefMut, Entity, Event, Resource, Timer, Vec2};
-use crate::gaming::spawn::{AircraftSpawnState, BombSpawnState, HealthPackSpawnState, ShieldSpawnState};
-use crate::PlayState;
+use bevy::prelude::{Component, Deref, DerefMut, Entity, Event, Resource, Timer, TimerMode, Vec2, Vec3};
+use xtyping_core::scoring::Streak;
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::{LevelSettings, PlayState};
 
-/// 游戏时间显示
+/// 游戏时间显示，基于游戏暂停时会冻结的虚拟时钟
 #[derive(Component)]
 pub struct GameTime {
     pub start_time: f64,
@@ -18,6 +20,10 @@ pub struct LevelProgress;
 #[derive(Component)]
 pub struct LevelStarImage;
 
+/// 超过星级图片数量时显示的玩家等级
+#[derive(Component)]
+pub struct LevelStarText;
+
 /// 玩家当前得分文本
 #[derive(Component)]
 pub struct PlayerScore;
@@ -37,7 +43,7 @@ pub struct HealthBar {
 
 pub const HEALTH_MAX_VALUE: u16 = 100;
 
-#[derive(Resource, Default)]
+#[derive(Resource, Default, Clone, Deserialize, Serialize)]
 pub struct FlyingUnitCounter {
     pub destroyed: usize,
     pub missed: usize,
@@ -54,39 +60,38 @@ pub struct SplashTextRow {
 
 /// 玩家的战斗机
 #[derive(Component)]
-pub struct FighterJet {
-    pub protected: bool,
-    pub protect_since: f32,
-}
+pub struct FighterJet;
 
 pub const FIGHTER_JET_MARGIN: f32 = 80.0;
 pub const FIGHTER_JET_SCALE: f32 = 0.3;
 pub const FIGHTER_JET_SIZE: f32 = 300.;
 
 pub const TARGET_LETTER_SIZE: f32 = 32.;
-pub const TARGET_LETTER_COLOR: Color = Color::srgb_u8(88, 251, 254);
 
 pub const CHECKPOINT_LETTER_SIZE: f32 = 50.;
-pub const CHECKPOINT_LETTER_WAITING: Color = Color::srgb_u8(245, 53, 53);
-pub const CHECKPOINT_LETTER_TARGET: Color = Color::srgb_u8(245, 245, 53);
-pub const CHECKPOINT_LETTER_DESTROYED: Color = Color::srgb_u8(97, 97, 97);
+// Boss句子缩小到这个字体大小仍然放不下时换行显示
+pub const CHECKPOINT_LETTER_MIN_SIZE: f32 = 28.;
+pub const CHECKPOINT_FONT_RATIO: f32 = 0.65;
+pub const CHECKPOINT_SENTENCE_MARGIN: f32 = 20.;
 
-#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Deserialize, Serialize)]
 pub enum FlyingUnitKind {
     Aircraft,
     Bomb,
     Shield,
     HealthPack,
-    Warship
+    Warship,
+    SlowMotion,
+    DoubleScore,
+    AutoAim,
+    ExtraLife,
 }
 
 #[derive(Component)]
 pub struct FlyingUnitText(pub FlyingUnitKind);
 
-pub trait FlyingUnitTrait {
-    type SpawnState;
-    fn kind() -> FlyingUnitKind;
-}
+// 装备图片的显示大小，不同的图片统一缩放到相同的大小
+pub const EQUIPMENT_SIZE: f32 = 300.;
 
 #[derive(Component)]
 pub struct FlyingUnit {
@@ -98,35 +103,76 @@ pub struct FlyingUnit {
 
 #[derive(Component, Default)]
 pub struct Aircraft {
+    pub sprite: i32,   // 敌机图片的编号
+    pub kind: EnemyKind, // 敌机的种类
     pub ready: bool,   // 是否准备发射
     pub fire_pos: f32, // 发射的坐标位置
     pub flame: Option<Entity> // 发射的火球
 }
 
-impl FlyingUnitTrait for Aircraft {
-    type SpawnState = AircraftSpawnState;
-    fn kind() -> FlyingUnitKind { FlyingUnitKind::Aircraft }
+/// 敌机的种类，不同种类的移动方式和耐久度不同
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default, Deserialize, Serialize)]
+pub enum EnemyKind {
+    #[default]
+    Normal,   // 普通敌机
+    Armored,  // 装甲敌机，需要依次输入多个字符
+    ZigZag,   // 之字形飞行，会切换航道
+    Scout,    // 快速侦察机，不发射火球
+    Splitter, // 被击中后分裂成两架小敌机
+    Fragment, // 分裂出来的小敌机
 }
 
-pub const AIRCRAFT_KIND: i32 = 3;
-pub const AIRCRAFT_SIZE: f32 = 300.;
+impl EnemyKind {
+    /// 敌机图片的着色，用于区分不同种类
+    pub fn tint(&self) -> Color {
+        match self {
+            EnemyKind::Armored => Color::srgb_u8(170, 180, 255),
+            EnemyKind::Splitter | EnemyKind::Fragment => Color::srgb_u8(255, 170, 120),
+            _ => Color::WHITE,
+        }
+    }
 
-#[derive(Component, Default)]
-pub struct Bomb;
+    pub fn scale(&self) -> f32 {
+        match self {
+            EnemyKind::Scout | EnemyKind::Fragment => FIGHTER_JET_SCALE * 0.45,
+            _ => FIGHTER_JET_SCALE * 0.6,
+        }
+    }
+}
 
-impl FlyingUnitTrait for Bomb {
-    type SpawnState = BombSpawnState;
-    fn kind() -> FlyingUnitKind { FlyingUnitKind::Bomb }
+/// 装甲敌机还需要依次击中的字符
+#[derive(Component)]
+pub struct Armored {
+    pub letters: Vec<char>,
 }
 
-#[derive(Component, Default)]
-pub struct HealthPack;
+/// 之字形飞行的敌机
+#[derive(Component)]
+pub struct ZigZag {
+    pub timer: Timer,
+    // 正在飞往的航道位置
+    pub target_y: f32,
+}
+
+pub const ZIGZAG_SPEED: f32 = 60.;
+
+/// 击中了装甲敌机当前的字符
+#[derive(Event)]
+pub struct ArmorHitEvent(pub Entity);
 
-impl FlyingUnitTrait for HealthPack {
-    type SpawnState = HealthPackSpawnState;
-    fn kind() -> FlyingUnitKind { FlyingUnitKind::HealthPack }
+/// 分裂敌机被击中
+#[derive(Event)]
+pub struct AircraftSplitEvent {
+    pub translation: Vec3,
+    pub route: i32,
+    pub speed: f32,
 }
 
+pub const AIRCRAFT_KIND: i32 = 3;
+pub const AIRCRAFT_SIZE: f32 = 300.;
+// 同一航道上相邻飞行单元之间的最小水平距离，包括敌机图片和右边的字符（装甲敌机最多三个）
+pub const MIN_ROUTE_SPACING: f32 = 160.;
+
 pub const HEALTH_PACK_RESTORE: u16 = 10;
 
 #[derive(Component)]
@@ -135,15 +181,106 @@ pub struct EquipmentEffect {
     pub duration: f32,
 }
 
+// 减速装备生效时所有飞行单元的速度比例
+pub const SLOW_MOTION_RATIO: f32 = 0.5;
+
+// 自动瞄准击毁最近敌机的时间间隔
+pub const AUTO_AIM_INTERVAL: f32 = 1.;
+
+/// 玩家击中了一个装备
 #[derive(Event)]
-pub struct HealthPackApplyEvent;
+pub struct EquipmentCollectedEvent(pub FlyingUnitKind);
 
-#[derive(Component, Default)]
-pub struct Shield;
+/// 装备开始生效，可能是击中后立即生效，也可能是从道具栏中使用
+#[derive(Event)]
+pub struct EquipmentActivatedEvent(pub FlyingUnitKind);
+
+/// 道具栏中每种装备最多可以保存的数量
+pub const MAX_INVENTORY_COUNT: u32 = 9;
+
+/// 玩家收集的还没有使用的装备
+#[derive(Resource, Default, Clone)]
+pub struct Inventory(pub HashMap<FlyingUnitKind, u32>);
+
+impl Inventory {
+    pub fn count(&self, kind: FlyingUnitKind) -> u32 {
+        self.0.get(&kind).copied().unwrap_or_default()
+    }
+}
+
+/// 玩家击中目标得分，translation为显示得分的位置
+#[derive(Event)]
+pub struct ScoreEvent {
+    pub base: u32,
+    pub translation: Vec3,
+}
+
+/// 漏掉敌机或者按错键，连击中断
+#[derive(Event)]
+pub struct StreakBrokenEvent {
+    pub wrong_key: bool,
+    // 按错的键或者漏掉的敌机的字符
+    pub letter: char,
+}
+
+/// 玩家按下的字符键对应屏幕上的目标
+#[derive(Event)]
+pub struct CorrectKeyEvent;
+
+/// 玩家当前的连击次数
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct ScoreStreak(pub Streak);
 
-impl FlyingUnitTrait for Shield {
-    type SpawnState = ShieldSpawnState;
-    fn kind() -> FlyingUnitKind { FlyingUnitKind::Shield }
+/// 显示连击倍数的文字
+#[derive(Component)]
+pub struct StreakText;
+
+/// 道具栏中显示装备数量的文字
+#[derive(Component)]
+pub struct InventoryText(pub FlyingUnitKind);
+
+/// 当前生效的装备效果
+#[derive(Resource)]
+pub struct ActiveEffects {
+    // 持续性效果的剩余时间
+    pub timers: HashMap<FlyingUnitKind, Timer>,
+    // 剩余的复活次数
+    pub revives: u32,
+    // 自动瞄准的发射间隔
+    pub auto_aim: Timer,
+}
+
+impl Default for ActiveEffects {
+    fn default() -> Self {
+        ActiveEffects {
+            timers: HashMap::new(),
+            revives: 0,
+            auto_aim: Timer::from_seconds(AUTO_AIM_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+impl ActiveEffects {
+    pub fn is_active(&self, kind: FlyingUnitKind) -> bool {
+        self.timers.get(&kind).is_some_and(|t| !t.is_finished())
+    }
+
+    /// 激活持续性效果，已经生效的效果重新计时
+    pub fn activate(&mut self, kind: FlyingUnitKind, duration: f32) {
+        self.timers.insert(kind, Timer::from_seconds(duration, TimerMode::Once));
+    }
+
+    pub fn remaining(&self, kind: FlyingUnitKind) -> f32 {
+        self.timers.get(&kind).map(|t| t.remaining_secs()).unwrap_or_default()
+    }
+
+    pub fn score_multiplier(&self) -> u32 {
+        if self.is_active(FlyingUnitKind::DoubleScore) { 2 } else { 1 }
+    }
+
+    pub fn speed_ratio(&self) -> f32 {
+        if self.is_active(FlyingUnitKind::SlowMotion) { SLOW_MOTION_RATIO } else { 1. }
+    }
 }
 
 #[derive(Component)]
@@ -155,8 +292,22 @@ pub struct Missile {
     pub speed: f32,
     pub target: Entity,
     pub letter: char,
+    // 玩家按键发射的导弹会占用目标的字符，炸弹和自动瞄准发射的导弹不占用
+    pub typed: bool,
+    // 发射导弹的时间，与目标的SpawnTime相减就是玩家的反应时间
+    pub fire_time: f32,
 }
 
+/// 目标消失并且找不到新目标的导弹，沿原来的方向继续飞行并逐渐消失
+#[derive(Component)]
+pub struct FadingMissile(pub Timer);
+
+pub const MISSILE_FADE_SECS: f32 = 0.4;
+
+/// 飞行单元出现的时间，用于选择最早出现的目标和计算玩家的反应时间
+#[derive(Component)]
+pub struct SpawnTime(pub f32);
+
 /// 敌机发射的火焰武器
 #[derive(Component)]
 pub struct Flame {
@@ -167,16 +318,16 @@ pub struct Flame {
 #[derive(Component, Deref, DerefMut)]
 pub struct Explosion(pub Timer);
 
-pub const EXPLOSION_SHEET_MAX_INDEX: usize = 8;
-
 #[derive(Event)]
-pub struct BombExplodedEvent;
+pub struct UpdateHealthBarEvent(pub u16);
 
+/// 玩家在游戏中升级了
 #[derive(Event)]
-pub struct ShieldActivatedEvent;
+pub struct LevelUpEvent;
 
-#[derive(Event)]
-pub struct UpdateHealthBarEvent(pub u16);
+/// 这一局使用的关卡，闯关时对应玩家的级别，课程模式时由课程生成
+#[derive(Resource)]
+pub struct ActiveLevel(pub LevelSettings);
 
 #[derive(Resource, Default)]
 pub struct LastPlayState(pub PlayState);
@@ -209,6 +360,8 @@ pub struct WarshipLetter(pub usize);
 
 #[derive(Resource, Default)]
 pub struct WarshipSentence {
+    // 完整的句子，包含空格
+    pub sentence: String,
     pub letters: Vec<char>,
     pub current: usize,
 }
@@ -219,19 +372,58 @@ pub struct WarshipLetterArrow;
 pub const WARSHIP_WIDTH: f32 = 1036.;
 pub const WARSHIP_HEIGHT: f32 = 362.;
 
-#[derive(Resource)]
+#[derive(Resource, Clone, Deserialize, Serialize)]
+#[serde(default)]
 pub struct SpeedFactor {
     // 敌机的飞行速度加速比
     pub speed_factor: f32,
     // 加速比修改的次数
     pub factor_changes: i32,
+    // 敌机出现间隔的系数，越小出现得越频繁
+    pub interval_factor: f32,
 }
 
+/// 手动加速的最多次数
+pub const MAX_SPEED_CHANGES: i32 = 5;
+
 impl Default for SpeedFactor {
     fn default() -> SpeedFactor {
         SpeedFactor {
             speed_factor: 1.,
-            factor_changes: 0
+            factor_changes: 0,
+            interval_factor: 1.,
         }
     }
-}
\ No newline at end of file
+}
+
+impl SpeedFactor {
+    /// 手动加速changes次后的速度，每次速度加快25%，手动加速不改变敌机出现的间隔
+    pub fn from_changes(changes: i32) -> SpeedFactor {
+        let changes = changes.clamp(0, MAX_SPEED_CHANGES);
+        SpeedFactor {
+            speed_factor: 1.25_f32.powi(changes),
+            factor_changes: changes,
+            interval_factor: 1.,
+        }
+    }
+}
+
+/// 显示当前难度的文本
+#[derive(Component)]
+pub struct DifficultyText;
+
+/// 一架敌机被击落或者飞过了玩家的防线
+#[derive(Event)]
+pub struct AircraftOutcomeEvent {
+    pub hit: bool,
+    pub letter: char,
+    // 从敌机出现到被击落所用的秒数
+    pub reaction: f32,
+}
+/// 击中了战舰句子中的一个字符
+#[derive(Event)]
+pub struct WarshipLetterEvent;
+
+/// 与最好成绩比较的进度标记
+#[derive(Component)]
+pub struct GhostMarker;
@@ -0,0 +1,88 @@
+use std::collections::VecDeque;
+use bevy::prelude::*;
+use crate::{GamePlayer, GameSettings};
+use crate::gaming::common::*;
+use crate::ui::INFO_TEXT_COLOR;
+
+const AUTO_DIFFICULTY_COLOR: Color = Color::srgb_u8(135, 201, 22);
+
+/// 自动难度调整器，记录最近一段时间内敌机的击落结果
+#[derive(Resource, Default)]
+pub struct DifficultyController {
+    pub enabled: bool,
+    // 最近的结果：是否击落及反应时间
+    pub samples: VecDeque<(bool, f32)>,
+}
+
+impl DifficultyController {
+    pub fn new(game_player: &GamePlayer) -> Self {
+        DifficultyController {
+            enabled: game_player.player.auto_difficulty,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// 最近的击落比例及击落敌机的平均反应时间，还没有结果时返回None
+    fn statistics(&self) -> Option<(f32, f32)> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let hits: Vec<f32> = self.samples.iter().filter(|(hit, _)| *hit).map(|(_, r)| *r).collect();
+        let success = hits.len() as f32 / self.samples.len() as f32;
+        let reaction = if hits.is_empty() { f32::MAX } else { hits.iter().sum::<f32>() / hits.len() as f32 };
+        Some((success, reaction))
+    }
+}
+
+pub fn on_aircraft_outcome(
+    event: On<AircraftOutcomeEvent>,
+    mut controller: ResMut<DifficultyController>,
+    settings: Res<GameSettings>,
+) {
+    if !controller.enabled {
+        return;
+    }
+    controller.samples.push_back((event.hit, event.reaction));
+    while controller.samples.len() > settings.difficulty.sample_window {
+        controller.samples.pop_front();
+    }
+}
+
+/// 根据最近的击落比例和反应时间平滑地调整敌机速度及出现间隔
+pub fn adjust_difficulty(
+    controller: Res<DifficultyController>,
+    mut speed_factor: ResMut<SpeedFactor>,
+    settings: Res<GameSettings>,
+    time: Res<Time>,
+) {
+    let config = &settings.difficulty;
+    if controller.samples.len() < config.min_samples {
+        return;
+    }
+
+    // 击落比例高于目标或者反应很快时加大难度，否则降低难度
+    let Some((success, reaction)) = controller.statistics() else {
+        return;
+    };
+    let reaction_bias = ((config.target_reaction - reaction) / config.target_reaction).clamp(-1., 1.);
+    let pressure = (success - config.target_success + config.reaction_weight * reaction_bias).clamp(-1., 1.);
+
+    let (min_speed, max_speed) = config.speed_factor_range;
+    let (min_interval, max_interval) = config.interval_factor_range;
+    let speed = (speed_factor.speed_factor * (1. + pressure * config.adjust_rate * time.delta_secs()))
+        .clamp(min_speed, max_speed);
+    if speed != speed_factor.speed_factor {
+        speed_factor.speed_factor = speed;
+        speed_factor.interval_factor = (1. / speed).clamp(min_interval, max_interval);
+    }
+}
+
+pub fn update_difficulty_text(
+    controller: Res<DifficultyController>,
+    speed_factor: Res<SpeedFactor>,
+    mut text: Single<(&mut Text, &mut TextColor), With<DifficultyText>>,
+) {
+    let (ref mut text, ref mut color) = *text;
+    **text = Text::new(format!("x{:.2}", speed_factor.speed_factor));
+    **color = TextColor(if controller.enabled { AUTO_DIFFICULTY_COLOR } else { INFO_TEXT_COLOR });
+}
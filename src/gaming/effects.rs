@@ -0,0 +1,141 @@
+use bevy::prelude::*;
+use crate::{GamePlayer, GameSettings, GameState};
+use crate::gaming::common::*;
+use crate::gaming::playing::spawn_missile;
+use crate::theme::{ThemeAsset, ThemeAssets};
+
+/// 持续性装备效果的时间，0表示立即生效
+fn effect_duration(kind: FlyingUnitKind, settings: &GameSettings) -> f32 {
+    match kind {
+        FlyingUnitKind::Shield => settings.shield_active_time,
+        FlyingUnitKind::SlowMotion => settings.slow_motion_time,
+        FlyingUnitKind::DoubleScore => settings.double_score_time,
+        FlyingUnitKind::AutoAim => settings.auto_aim_time,
+        _ => 0.,
+    }
+}
+
+/// 装备生效时在玩家战斗机上显示的动画图片及颜色
+fn effect_visual(kind: FlyingUnitKind) -> Option<(ThemeAsset, Color)> {
+    match kind {
+        FlyingUnitKind::Bomb => Some((ThemeAsset::EquipmentEffect, Color::srgba(1., 1., 1., 0.5))),
+        FlyingUnitKind::Shield => Some((ThemeAsset::ShieldEffect, Color::srgba(1., 1., 1., 0.5))),
+        FlyingUnitKind::HealthPack | FlyingUnitKind::ExtraLife =>
+            Some((ThemeAsset::HealthEffect, Color::srgba(1., 1., 1., 0.5))),
+        FlyingUnitKind::SlowMotion => Some((ThemeAsset::EquipmentEffect, Color::srgba(0.4, 0.6, 1., 0.5))),
+        FlyingUnitKind::DoubleScore => Some((ThemeAsset::EquipmentEffect, Color::srgba(1., 0.85, 0.2, 0.5))),
+        FlyingUnitKind::AutoAim => Some((ThemeAsset::EquipmentEffect, Color::srgba(1., 0.4, 0.4, 0.5))),
+        FlyingUnitKind::Aircraft | FlyingUnitKind::Warship => None,
+    }
+}
+
+/// 在玩家战斗机上显示一个旋转的效果动画，remaining为剩余的显示时间
+pub fn spawn_effect_visual(
+    commands: &mut Commands,
+    assets: &ThemeAssets,
+    kind: FlyingUnitKind,
+    translation: Vec3,
+    remaining: f32,
+    duration: f32,
+) {
+    let Some((texture, color)) = effect_visual(kind) else {
+        return;
+    };
+    commands.spawn((
+        DespawnOnExit(GameState::Gaming),
+        Sprite {
+            image: assets.load(texture),
+            image_mode: SpriteImageMode::Auto,
+            color,
+            ..default()
+        },
+        Transform::from_translation(translation).with_scale(Vec3::splat(0.5)),
+        EquipmentEffect {
+            timer: Timer::from_seconds(remaining, TimerMode::Once),
+            duration
+        }
+    ));
+}
+
+/// 所有装备共用的效果处理：持续性效果开始计时，其它效果立即生效
+pub fn on_equipment_activated(
+    event: On<EquipmentActivatedEvent>,
+    mut commands: Commands,
+    mut effects: ResMut<ActiveEffects>,
+    mut game_player: ResMut<GamePlayer>,
+    fighter_jet: Single<&Transform, With<FighterJet>>,
+    aircraft: Query<(Entity, &FlyingUnit), With<Aircraft>>,
+    assets: ThemeAssets,
+    settings: Res<GameSettings>,
+    time: Res<Time>,
+    window: Single<&Window>,
+) {
+    let kind = event.0;
+    let duration = effect_duration(kind, &settings);
+    if duration > 0. {
+        effects.activate(kind, duration);
+    }
+
+    match kind {
+        FlyingUnitKind::Bomb => {
+            // 向所有敌机发射导弹
+            let missile = assets.load(ThemeAsset::Missile);
+            for (entity, unit) in aircraft.iter() {
+                spawn_missile(&mut commands, missile.clone(), &window, Missile {
+                    speed: settings.missile_speed,
+                    target: entity,
+                    letter: unit.letter.to_ascii_uppercase(),
+                    typed: false,
+                    fire_time: time.elapsed_secs(),
+                });
+            }
+        }
+        FlyingUnitKind::HealthPack => {
+            game_player.health = (game_player.health + HEALTH_PACK_RESTORE).min(HEALTH_MAX_VALUE);
+        }
+        FlyingUnitKind::ExtraLife => {
+            effects.revives += 1;
+        }
+        _ => {}
+    }
+
+    let visual = if duration > 0. { duration } else { 1.5 };
+    spawn_effect_visual(&mut commands, &assets, kind, fighter_jet.translation, visual, visual);
+}
+
+/// 持续性效果的计时，使用游戏暂停时会冻结的虚拟时钟
+pub fn tick_active_effects(mut effects: ResMut<ActiveEffects>, time: Res<Time>) {
+    for timer in effects.timers.values_mut() {
+        timer.tick(time.delta());
+    }
+    effects.timers.retain(|_, timer| !timer.is_finished());
+}
+
+/// 自动瞄准生效时，定时向离玩家最近的敌机发射导弹
+pub fn auto_aim(
+    mut commands: Commands,
+    mut effects: ResMut<ActiveEffects>,
+    aircraft: Query<(Entity, &FlyingUnit, &Transform), With<Aircraft>>,
+    missiles: Query<&Missile>,
+    assets: ThemeAssets,
+    settings: Res<GameSettings>,
+    time: Res<Time>,
+    window: Single<&Window>,
+) {
+    if !effects.auto_aim.tick(time.delta()).just_finished() {
+        return;
+    }
+    // 跳过已经被导弹锁定的敌机
+    let target = aircraft.iter()
+        .filter(|(entity, _, _)| !missiles.iter().any(|m| m.target == *entity))
+        .min_by(|a, b| a.2.translation.x.total_cmp(&b.2.translation.x));
+    if let Some((entity, unit, _)) = target {
+        spawn_missile(&mut commands, assets.load(ThemeAsset::MissileAdvanced), &window, Missile {
+            speed: settings.missile_speed,
+            target: entity,
+            letter: unit.letter.to_ascii_uppercase(),
+            typed: false,
+            fire_time: time.elapsed_secs(),
+        });
+    }
+}
@@ -1,17 +1,17 @@
 use bevy::asset::AssetServer;
-use bevy::color::Color;
-use bevy::math::Vec2;
 use bevy::prelude::*;
-use crate::{widgets, GameFonts, GamePlayer, GameState, PlayState};
+use crate::{GameFonts, GamePlayer, GameState, PlayState};
 use crate::gaming::common::LastPlayState;
-use crate::ui::{spawn_image_node, spawn_info_text};
-use crate::widgets::ModelDialog;
+use crate::heatmap::HeatmapView;
+use crate::widgets::{Dialog, DialogResult};
 
-#[derive(Component)]
-pub struct ButtonExitGame;
-
-#[derive(Component)]
-pub struct ButtonContinue;
+/// 闯关失败后的选择
+#[derive(Clone, Copy)]
+pub enum FailedChoice {
+    Quit,
+    Heatmap,
+    Continue,
+}
 
 pub fn player_failed_setup(
     mut commands: Commands,
@@ -19,76 +19,32 @@ pub fn player_failed_setup(
     game_fonts: Res<GameFonts>,
     asset_server: Res<AssetServer>
 ) {
-    let dialog = ModelDialog::new(&mut commands, PlayState::Failed, 60.);
-    commands.entity(dialog.container).with_children(|builder| {
-        spawn_info_text(builder, "Failed!", Color::srgb_u8(135, 201, 22),
-                        game_fonts.normal_font.clone(), 48.);
-        builder.spawn(
-            Node {
-                width: Val::Percent(90.),
-                height: Val::Auto,
-                flex_direction: FlexDirection::Row,
-                align_items: AlignItems::Center,
-                justify_content: JustifyContent::Center,
-                margin: UiRect::all(Val::Px(20.0)),
-                ..default()
-            }).with_children(|builder| {
-            spawn_image_node(builder, &asset_server, "images/failed.png", Vec2::splat(96.0), 30., 0.);
-            spawn_info_text(builder, &format!("{}，闯关失败啦！还要继续玩吗？", game_player.player.name),
-                            Color::srgb_u8(188, 190, 196), game_fonts.ui_font.clone(), 28.);
-        });
-        builder.spawn(
-            Node {
-                width: Val::Percent(50.),
-                height: Val::Auto,
-                flex_direction: FlexDirection::Row,
-                align_items: AlignItems::Center,
-                justify_content: JustifyContent::Center,
-                margin: UiRect::all(Val::Px(10.0)),
-                ..default()
-            }).with_children(|builder| {
-            builder.spawn(
-                widgets::PushButton::new(ButtonExitGame,
-                                         "不玩啦！",
-                                         Vec2::new(160.0,40.0),
-                                         true,
-                                         UiRect::right(Val::Px(10.0))
-                ));
-            builder.spawn(
-                widgets::PushButton::new(ButtonContinue,
-                                         "继续游戏",
-                                         Vec2::new(160.0,40.0),
-                                         true,
-                                         UiRect::left(Val::Px(10.0))
-                ));
-        });
-    });
+    Dialog::new("Failed!", &format!("{}，闯关失败啦！还要继续玩吗？", game_player.player.name))
+        .fonts(game_fonts.normal_font.clone(), game_fonts.ui_font.clone())
+        .image(asset_server.load("images/failed.png"))
+        .cancel_button("不玩啦！", FailedChoice::Quit)
+        .button("键盘热力图", FailedChoice::Heatmap)
+        .default_button("继续游戏", FailedChoice::Continue)
+        .spawn(&mut commands, PlayState::Failed);
 
     commands.insert_resource(LastPlayState(PlayState::Failed));
 }
 
-pub fn on_continue_game_button(
+pub fn on_failed_choice(
     mut commands: Commands,
     mut next_state: ResMut<NextState<GameState>>,
-    mut reader: MessageReader<widgets::ButtonClicked>,
-    query: Query<(), With<ButtonContinue>>,
+    mut reader: MessageReader<DialogResult<FailedChoice>>,
+    game_player: Res<GamePlayer>,
 ) {
-    if let Some(event) = reader.read().last()
-        && query.get(event.entity).is_ok() {
+    if let Some(result) = reader.read().last() {
         commands.remove_resource::<LastPlayState>();
-        next_state.set(GameState::Restart);
+        next_state.set(match result.value {
+            FailedChoice::Quit => GameState::Startup,
+            FailedChoice::Continue => GameState::Restart,
+            FailedChoice::Heatmap => {
+                commands.insert_resource(HeatmapView::new(&game_player.player.name));
+                GameState::Heatmap
+            }
+        });
     }
 }
-
-pub fn on_exit_game_button(
-    mut commands: Commands,
-    mut next_state: ResMut<NextState<GameState>>,
-    mut reader: MessageReader<widgets::ButtonClicked>,
-    query: Query<(), With<ButtonExitGame>>,
-) {
-    if let Some(event) = reader.read().last()
-        && query.get(event.entity).is_ok()  {
-        commands.remove_resource::<LastPlayState>();
-        next_state.set(GameState::Startup);
-    }
-}
\ No newline at end of file
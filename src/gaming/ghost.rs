@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::fs;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use xtyping_core::ghost::{GhostRun, GhostSample};
+use crate::{get_app_data_dir, GameMode, GamePlayer, GameSettings, GAME_APP_NAME};
+use crate::accessibility::Accessibility;
+use crate::gaming::common::*;
+use crate::gaming::resume::ResumeRound;
+
+const GHOST_RUNS_FILE: &str = "ghosts.json";
+
+/// 每个玩家在每个关卡的最好成绩曲线，第二层的键由游戏模式和关卡组成
+#[derive(Resource, Deserialize, Serialize, Default)]
+pub struct GhostRecords(HashMap<String, HashMap<String, GhostRun>>);
+
+impl GhostRecords {
+    pub fn load() -> GhostRecords {
+        let mut data_file = get_app_data_dir(GAME_APP_NAME);
+        data_file.push(GHOST_RUNS_FILE);
+        if !data_file.exists() {
+            return GhostRecords::default();
+        }
+        fs::read_to_string(&data_file)
+            .and_then(|data| serde_json::from_str::<GhostRecords>(&data).map_err(|err| err.into()))
+            .unwrap_or_else(|err| {
+                error!("Failed to parse ghost runs: {}", err);
+                GhostRecords::default()
+            })
+    }
+
+    pub fn save(&self) {
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            let mut data_file = get_app_data_dir(GAME_APP_NAME);
+            data_file.push(GHOST_RUNS_FILE);
+            if let Err(e) = fs::write(&data_file, json.as_bytes()) {
+                error!("Failed to save ghost runs: {}", e);
+            }
+        }
+    }
+}
+
+/// 这一局与最好成绩的比赛
+#[derive(Resource, Default)]
+pub struct GhostRace {
+    // 关卡对应的键，例如arcade-3或者lesson-2
+    key: String,
+    best: Option<GhostRun>,
+    // 这一局已经记录的曲线及当前的累计进度
+    pub current: GhostRun,
+    sample: GhostSample,
+}
+
+fn race_key(game_player: &GamePlayer, settings: &GameSettings) -> String {
+    match game_player.mode {
+        GameMode::Arcade => format!("arcade-{}", game_player.player.level),
+        GameMode::Lesson => format!("lesson-{}", settings.curriculum.lesson_index(&game_player.player)),
+    }
+}
+
+/// 进入游戏时取出这一关的最好成绩，继续保存的游戏时接着记录之前的曲线
+pub fn ghost_race_setup(
+    mut commands: Commands,
+    game_player: Res<GamePlayer>,
+    settings: Res<GameSettings>,
+    records: Res<GhostRecords>,
+    resume: Option<Res<ResumeRound>>,
+) {
+    let key = race_key(&game_player, &settings);
+    let best = records.0.get(&game_player.player.name).and_then(|runs| runs.get(&key)).cloned();
+    let current = resume.map(|r| r.0.ghost.clone()).unwrap_or_default();
+    let sample = current.last();
+    commands.insert_resource(GhostRace { key, best, current, sample });
+}
+
+pub fn on_ghost_outcome(event: On<AircraftOutcomeEvent>, mut race: ResMut<GhostRace>) {
+    if event.hit {
+        race.sample.hits += 1;
+    } else {
+        race.sample.misses += 1;
+    }
+}
+
+pub fn on_ghost_warship_letter(_event: On<WarshipLetterEvent>, mut race: ResMut<GhostRace>) {
+    race.sample.boss_letters += 1;
+}
+
+/// 每过一秒游戏时间记录一次进度，并显示领先或者落后最好成绩的数量
+pub fn record_ghost_progress(
+    mut race: ResMut<GhostRace>,
+    accessibility: Res<Accessibility>,
+    game_time: Single<&GameTime>,
+    mut marker: Single<(&mut Text, &mut TextColor), With<GhostMarker>>,
+    time: Res<Time>,
+) {
+    let secs = (time.elapsed_secs_f64() - game_time.start_time) as u32;
+    if secs <= race.current.duration() {
+        return;
+    }
+    let sample = race.sample;
+    race.current.record(secs, sample);
+    let Some(best) = &race.best else {
+        return;
+    };
+    let [behind, even, ahead] = accessibility.health_colors();
+    let (text, color) = match best.lead(secs, &sample) {
+        0 => ("与最好成绩持平".to_owned(), even),
+        lead if lead > 0 => (format!("领先最好成绩 {}", lead), ahead),
+        lead => (format!("落后最好成绩 {}", -lead), behind),
+    };
+    let (ref mut marker_text, ref mut marker_color) = *marker;
+    **marker_text = Text::new(text);
+    **marker_color = TextColor(color);
+}
+
+/// 通关时结束这一局的记录，成绩更好时保存为新的最好成绩，返回显示给玩家的比较结果
+pub fn finish_ghost_race(
+    race: &mut GhostRace,
+    records: &mut GhostRecords,
+    player: &str,
+    game_secs: f64,
+) -> String {
+    let sample = race.sample;
+    race.current.record((game_secs.ceil() as u32).max(1), sample);
+    let message = match &race.best {
+        None => "这是你在这一关的第一个成绩，下次来挑战它吧！".to_owned(),
+        Some(best) => {
+            let delta = race.current.delta(best);
+            let record = if race.current.beats(best) { "，新纪录！" } else { "" };
+            format!("与最好成绩相比：击中 {:+}，用时 {:+} 秒{}", delta.progress, delta.secs, record)
+        }
+    };
+    if race.best.as_ref().is_none_or(|best| race.current.beats(best)) {
+        race.best = Some(race.current.clone());
+        records.0.entry(player.to_owned()).or_default().insert(race.key.clone(), race.current.clone());
+        records.save();
+    }
+    message
+}
+
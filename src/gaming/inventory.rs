@@ -0,0 +1,133 @@
+use std::fs;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::{get_app_data_dir, GAME_APP_NAME};
+use crate::gaming::common::*;
+
+const EQUIPMENT_CONTROLS_FILE: &str = "controls.json";
+
+/// 装备的使用方式及道具栏的快捷键
+#[derive(Resource, Deserialize, Serialize, Clone)]
+pub struct EquipmentControls {
+    // 击中装备后立即生效，不放入道具栏
+    pub instant: bool,
+    // 使用道具栏中各种装备的按键，支持F1到F12
+    pub keys: Vec<(FlyingUnitKind, String)>,
+}
+
+impl Default for EquipmentControls {
+    fn default() -> Self {
+        EquipmentControls {
+            instant: false,
+            keys: vec![
+                (FlyingUnitKind::Bomb, "F1".to_owned()),
+                (FlyingUnitKind::HealthPack, "F2".to_owned()),
+                (FlyingUnitKind::Shield, "F3".to_owned()),
+                (FlyingUnitKind::SlowMotion, "F4".to_owned()),
+                (FlyingUnitKind::DoubleScore, "F5".to_owned()),
+                (FlyingUnitKind::AutoAim, "F6".to_owned()),
+            ],
+        }
+    }
+}
+
+impl EquipmentControls {
+    /// 读取设置文件，文件不存在时写入默认设置以便修改
+    pub fn load() -> EquipmentControls {
+        let mut data_file = get_app_data_dir(GAME_APP_NAME);
+        data_file.push(EQUIPMENT_CONTROLS_FILE);
+        if !data_file.exists() {
+            let controls = EquipmentControls::default();
+            controls.save();
+            return controls;
+        }
+        fs::read_to_string(&data_file)
+            .and_then(|data| serde_json::from_str::<EquipmentControls>(&data).map_err(|err| err.into()))
+            .unwrap_or_else(|err| {
+                error!("Failed to parse equipment controls: {}", err);
+                EquipmentControls::default()
+            })
+    }
+
+    pub fn save(&self) {
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            let mut data_file = get_app_data_dir(GAME_APP_NAME);
+            data_file.push(EQUIPMENT_CONTROLS_FILE);
+            if let Err(e) = fs::write(&data_file, json.as_bytes()) {
+                error!("Failed to save equipment controls: {}", e);
+            }
+        }
+    }
+
+    /// 可以放入道具栏的装备，没有设置按键的装备总是立即生效
+    pub fn storable(&self, kind: FlyingUnitKind) -> bool {
+        !self.instant && self.key_name(kind).is_some_and(|name| parse_key(name).is_some())
+    }
+
+    pub fn key_name(&self, kind: FlyingUnitKind) -> Option<&str> {
+        self.keys.iter().find(|(k, _)| *k == kind).map(|(_, name)| name.as_str())
+    }
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    let key = match name.trim().to_ascii_uppercase().as_str() {
+        "F1" => KeyCode::F1,
+        "F2" => KeyCode::F2,
+        "F3" => KeyCode::F3,
+        "F4" => KeyCode::F4,
+        "F5" => KeyCode::F5,
+        "F6" => KeyCode::F6,
+        "F7" => KeyCode::F7,
+        "F8" => KeyCode::F8,
+        "F9" => KeyCode::F9,
+        "F10" => KeyCode::F10,
+        "F11" => KeyCode::F11,
+        "F12" => KeyCode::F12,
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// 击中的装备放入道具栏，立即生效模式或者道具栏已满时直接使用
+pub fn on_equipment_collected(
+    event: On<EquipmentCollectedEvent>,
+    mut commands: Commands,
+    mut inventory: ResMut<Inventory>,
+    controls: Res<EquipmentControls>,
+) {
+    let kind = event.0;
+    if controls.storable(kind) && inventory.count(kind) < MAX_INVENTORY_COUNT {
+        *inventory.0.entry(kind).or_default() += 1;
+    } else {
+        commands.trigger(EquipmentActivatedEvent(kind));
+    }
+}
+
+/// 按下快捷键使用道具栏中的装备
+pub fn use_inventory_equipment(
+    mut commands: Commands,
+    mut inventory: ResMut<Inventory>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    controls: Res<EquipmentControls>,
+) {
+    for (kind, name) in &controls.keys {
+        let Some(key) = parse_key(name) else {
+            continue;
+        };
+        if keyboard_input.just_pressed(key) && inventory.count(*kind) > 0 {
+            *inventory.0.entry(*kind).or_default() -= 1;
+            commands.trigger(EquipmentActivatedEvent(*kind));
+        }
+    }
+}
+
+pub fn update_inventory_text(
+    inventory: Res<Inventory>,
+    mut texts: Query<(&mut Text, &mut TextColor, &InventoryText)>,
+) {
+    for (mut text, mut color, InventoryText(kind)) in &mut texts {
+        let count = inventory.count(*kind);
+        **text = format!("{}", count);
+        color.0.set_alpha(if count > 0 { 1. } else { 0.4 });
+    }
+}
@@ -0,0 +1,160 @@
+use bevy::asset::AssetServer;
+use bevy::prelude::*;
+use crate::{GameFonts, GamePlayer, GameSettings, GameState, PlayState};
+use crate::gaming::common::*;
+use crate::gaming::ghost::{finish_ghost_race, GhostRace, GhostRecords};
+use crate::heatmap::HeatmapView;
+use crate::widgets::{Dialog, DialogResult};
+
+/// 一课结束后的选择
+#[derive(Clone, Copy)]
+pub enum LessonChoice {
+    Rest,
+    Heatmap,
+    Continue,
+}
+
+/// 这一课中玩家的表现
+#[derive(Resource, Default)]
+pub struct LessonStats {
+    pub hits: u32,
+    pub misses: u32,
+    pub wrong_keys: u32,
+    // 击落敌机的反应时间总和
+    pub reaction_total: f32,
+}
+
+impl LessonStats {
+    /// 准确率，按错键和漏掉的敌机都算作错误
+    pub fn accuracy(&self) -> f32 {
+        let total = self.hits + self.misses + self.wrong_keys;
+        if total == 0 { 0. } else { self.hits as f32 / total as f32 }
+    }
+
+    pub fn reaction(&self) -> f32 {
+        if self.hits == 0 { f32::MAX } else { self.reaction_total / self.hits as f32 }
+    }
+}
+
+/// 一课结束后的结果
+#[derive(Resource)]
+pub struct LessonResult {
+    pub index: usize,
+    pub passed: bool,
+    pub accuracy: f32,
+    pub reaction: f32,
+}
+
+pub fn on_lesson_outcome(event: On<AircraftOutcomeEvent>, mut stats: ResMut<LessonStats>) {
+    if event.hit {
+        stats.hits += 1;
+        stats.reaction_total += event.reaction;
+    } else {
+        stats.misses += 1;
+    }
+}
+
+pub fn on_lesson_wrong_key(event: On<StreakBrokenEvent>, mut stats: ResMut<LessonStats>) {
+    if event.wrong_key {
+        stats.wrong_keys += 1;
+    }
+}
+
+/// 所有敌机都出现过后结束这一课，达到准确率和反应时间的要求时解锁下一课
+pub fn finish_lesson(
+    mut commands: Commands,
+    mut player: ResMut<GamePlayer>,
+    mut next_state: ResMut<NextState<PlayState>>,
+    counter: Res<FlyingUnitCounter>,
+    active_level: Res<ActiveLevel>,
+    stats: Res<LessonStats>,
+    settings: Res<GameSettings>,
+) {
+    if counter.destroyed + counter.missed < active_level.0.aircraft_count() {
+        return;
+    }
+    let curriculum = &settings.curriculum;
+    let index = curriculum.lesson_index(&player.player);
+    let accuracy = stats.accuracy();
+    let reaction = stats.reaction();
+    let passed = accuracy >= curriculum.target_accuracy && reaction <= curriculum.target_reaction;
+    if passed && player.player.lesson as usize == index {
+        player.player.lesson = (index + 1) as u32;
+    }
+    commands.insert_resource(LessonResult { index, passed, accuracy, reaction });
+    next_state.set(PlayState::LessonDone);
+}
+
+pub fn lesson_result_setup(
+    mut commands: Commands,
+    result: Res<LessonResult>,
+    game_player: Res<GamePlayer>,
+    game_fonts: Res<GameFonts>,
+    settings: Res<GameSettings>,
+    mut race: ResMut<GhostRace>,
+    mut records: ResMut<GhostRecords>,
+    game_time: Single<&GameTime>,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>
+) {
+    let curriculum = &settings.curriculum;
+    let (title, image, message, button) = if !result.passed {
+        ("Try Again", "images/grimacing.png",
+         format!("{}，还没有达到要求，再练习一次吧！", game_player.player.name), "再练一次")
+    } else if result.index + 1 < curriculum.lessons.len() {
+        let next = &curriculum.lessons[result.index + 1];
+        ("Well Done!", "images/congratulations.png",
+         format!("{}，你学会了这一课！下一课：{} {}", game_player.player.name, next.name, spaced_keys(&next.keys)),
+         "下一课")
+    } else {
+        ("Well Done!", "images/congratulations.png",
+         format!("{}，你已经学完了所有的课程！", game_player.player.name), "继续练习")
+    };
+    let reaction = if result.reaction == f32::MAX { "-".to_owned() } else { format!("{:.1}秒", result.reaction) };
+    let summary = format!("准确率 {:.0}%（要求 {:.0}%）  平均反应 {}（要求 {:.1}秒）",
+                          result.accuracy * 100., curriculum.target_accuracy * 100.,
+                          reaction, curriculum.target_reaction);
+
+    // 只有通过的课程才与最好成绩比较
+    let text = if result.passed {
+        let ghost = finish_ghost_race(&mut race, &mut records, &game_player.player.name,
+                                      time.elapsed_secs_f64() - game_time.start_time);
+        format!("{}\n{}\n{}", message, summary, ghost)
+    } else {
+        format!("{}\n{}", message, summary)
+    };
+    Dialog::new(title, &text)
+        .fonts(game_fonts.normal_font.clone(), game_fonts.ui_font.clone())
+        .image(asset_server.load(image))
+        .cancel_button("我要休息", LessonChoice::Rest)
+        .button("键盘热力图", LessonChoice::Heatmap)
+        .default_button(button, LessonChoice::Continue)
+        .spawn(&mut commands, PlayState::LessonDone);
+
+    commands.insert_resource(LastPlayState(PlayState::LessonDone));
+}
+
+/// 把一课的按键用空格分开显示
+pub fn spaced_keys(keys: &str) -> String {
+    keys.chars().map(|c| c.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+pub fn on_lesson_choice(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut reader: MessageReader<DialogResult<LessonChoice>>,
+    game_player: Res<GamePlayer>,
+) {
+    if let Some(result) = reader.read().last() {
+        commands.remove_resource::<LastPlayState>();
+        commands.remove_resource::<LessonResult>();
+        next_state.set(match result.value {
+            LessonChoice::Rest => GameState::Startup,
+            LessonChoice::Continue => GameState::Restart,
+            LessonChoice::Heatmap => {
+                commands.insert_resource(HeatmapView::new(&game_player.player.name));
+                GameState::Heatmap
+            }
+        });
+    }
+}
@@ -1,31 +1,42 @@
+use std::collections::HashMap;
 use rand::{Rng, random};
 use bevy::asset::AssetServer;
 use bevy::color::Color;
 use bevy::input::keyboard::{Key, KeyboardInput};
 use bevy::math::{Vec3};
 use bevy::prelude::*;
-use crate::{GameRoutes, GameLetters, GameSettings, Route, GamePlayer, GameFonts, ExplosionTexture, PlayState, GameState, MAX_PLAYER_LEVELS};
+use crate::{routes_top, GameRoutes, GameLetters, GameSettings, GamePlayer, GameFonts, ExplosionTexture, PlayState, GameState, GameMode, level_star_image, level_star_label};
 use crate::gaming::common::*;
+use crate::gaming::difficulty::DifficultyController;
+use crate::gaming::spawn::layout_warship_sentence;
+use crate::gaming::effects::spawn_effect_visual;
+use crate::theme::{ThemeAsset, ThemeAssets};
+use crate::accessibility::Accessibility;
+use crate::settings::{TargetingPolicy, UserSettings};
 use crate::gaming::{calculate_upgrade_percent, compute_route_count, gradient_health_bar_color};
+use xtyping_core::progress;
 
 pub fn playground_setup(
     mut commands: Commands,
     mut game_routes: ResMut<GameRoutes>,
     mut game_letters: ResMut<GameLetters>,
     mut game_player: ResMut<GamePlayer>,
-    game_settings: Res<GameSettings>,
-    asset_server: Res<AssetServer>,
+    active_level: Res<ActiveLevel>,
+    assets: ThemeAssets,
+    ui_scale: Res<UiScale>,
+    settings: Res<UserSettings>,
     window: Single<&Window>,
     last_state: Option<Res<LastPlayState>>,
 ) {
-    commands.insert_resource(SpeedFactor::default());
     if last_state.is_some() {
         commands.remove_resource::<LastPlayState>();
         return;
     }
+    // 每局游戏从设置中的默认速度开始
+    commands.insert_resource(SpeedFactor::from_changes(settings.speed_changes));
     // 玩家的战斗机
-    let fighter_jet_path = format!("images/fighter_jet_{}.png", game_player.player.level);
-    let texture = asset_server.load(fighter_jet_path);
+    // 战斗机图片只有星级图片那么多种，更高的级别使用最后一种
+    let texture = assets.load(ThemeAsset::fighter_jet(game_player.player.level));
     commands.spawn((
         DespawnOnExit(GameState::Gaming),
         Sprite {
@@ -36,38 +47,51 @@ pub fn playground_setup(
         },
         Transform::from_translation(Vec3::new(FIGHTER_JET_MARGIN - window.width()/2., 0., 2.))
             .with_scale(Vec3::splat(FIGHTER_JET_SCALE)),
-        FighterJet { protected: false, protect_since: 0. },
+        FighterJet,
     ));
 
     // 计算并创建敌机的航道
-    let route_count = compute_route_count(window.height());
-    game_routes.empty_routes = Vec::with_capacity(route_count);
-    for i in 0..route_count {
-        game_routes.empty_routes.push(Route {
-            id: i as i32,
-            entities: Vec::new(),
-        })
-    }
+    game_routes.reset(compute_route_count(window.height(), ui_scale.0));
 
-    // 加载玩家等级对应的字符
-    game_letters.candidate_letters = game_settings.level_letters[game_player.player.level as usize - 1].clone();
+    // 加载当前关卡的字符
+    game_letters.clear();
+    game_letters.reset(&active_level.0.letters);
 
     // 计算玩家的安全距离
     game_player.safe_position = -(window.width() / 2. - FIGHTER_JET_MARGIN - FIGHTER_JET_SIZE * FIGHTER_JET_SCALE - 50.);
     game_player.health = HEALTH_MAX_VALUE;
 }
 
+/// 飞行单元不论是被击落、飞出边界还是被炸弹清除，都要释放它占用的航道并把字符放回候选池
+pub fn on_flying_unit_removed(
+    event: On<Remove, FlyingUnit>,
+    mut game_routes: ResMut<GameRoutes>,
+    mut game_letters: ResMut<GameLetters>,
+    units: Query<(&FlyingUnit, Option<&Armored>)>,
+) {
+    let Ok((unit, armored)) = units.get(event.entity) else {
+        return;
+    };
+    if unit.kind == FlyingUnitKind::Warship {
+        return;
+    }
+    game_routes.release(unit.route, &event.entity);
+    game_letters.put_back([unit.letter]);
+    if let Some(armored) = armored {
+        game_letters.put_back(armored.letters.iter().copied());
+    }
+}
+
 pub fn move_flying_unit(
     mut commands: Commands,
     mut query: Query<(Entity, &FlyingUnit, &mut Transform)>,
-    mut game_routes: ResMut<GameRoutes>,
-    mut game_letters: ResMut<GameLetters>,
     mut counter: ResMut<FlyingUnitCounter>,
     mut aircraft: Query<&mut Aircraft>,
     mut counter_texts: Query<(&mut Text, &FlyingUnitText), With<FlyingUnitText>>,
+    effects: Res<ActiveEffects>,
     game_fonts: Res<GameFonts>,
     game_player: Res<GamePlayer>,
-    asset_server: Res<AssetServer>,
+    assets: ThemeAssets,
     time: Res<Time>,
     window: Single<&Window>
 ) {
@@ -79,36 +103,23 @@ pub fn move_flying_unit(
     let mut rng = rand::rng();
     for (entity, unit, mut transform) in &mut query {
         // 沿着 -X 方向移动
-        transform.translation.x -= unit.speed * time.delta_secs();
+        transform.translation.x -= unit.speed * effects.speed_ratio() * time.delta_secs();
         let pos = transform.translation.xy();
 
         // 到达销毁边界时，移除整个实体
         if pos.x < game_player.safe_position {
-            if unit.kind != FlyingUnitKind::Warship {
-                if let Some(pos) = game_routes.used_routes.iter().position(|r| r.id == unit.route) {
-                    // 如果只包含这一个 Entity，则将Route转移到未使用队列
-                    if game_routes.used_routes[pos].entities.len() == 1
-                        && game_routes.used_routes[pos].entities[0] == entity {
-                        let mut route = game_routes.used_routes.swap_remove(pos);
-                        route.entities.clear();
-                        game_routes.empty_routes.push(route);
-                    } else {
-                        // 否则仅移除目标 Entity
-                        game_routes.used_routes[pos].entities.retain(|&e| e != entity);
-                    }
-                }
-
-                // 把字母放回候选队列中
-                game_letters.candidate_letters.push(unit.letter);
-            }
-
-            // 销毁实体
+            // 销毁实体，航道和字符由on_flying_unit_removed释放
             commands.entity(entity).despawn();
 
             // 如果当前是敌机，出现一个“MISS”的文本提示
             if unit.kind == FlyingUnitKind::Aircraft {
-                counter.missed += 1;
-                *text = Text::new(format!("{}/{}", counter.destroyed, counter.missed));
+                // 分裂出来的小敌机不计入统计
+                if aircraft.get(entity).is_ok_and(|ac| ac.kind != EnemyKind::Fragment) {
+                    counter.missed += 1;
+                    *text = Text::new(format!("{}/{}", counter.destroyed, counter.missed));
+                }
+                commands.trigger(AircraftOutcomeEvent { hit: false, letter: unit.letter, reaction: 0. });
+                commands.trigger(StreakBrokenEvent { wrong_key: false, letter: unit.letter });
 
                 // 生成Miss文字动画
                 commands.spawn((
@@ -132,11 +143,12 @@ pub fn move_flying_unit(
             }
         } else if unit.kind == FlyingUnitKind::Aircraft && pos.x < 0. {
             // 当前实体是敌机，根据距离判断是准备进入发射火球状态还是可以发射了
-            if let Ok(mut ac) = aircraft.get_mut(entity) {
+            // 侦察机不发射火球
+            if let Ok(mut ac) = aircraft.get_mut(entity) && ac.kind != EnemyKind::Scout {
                 if ac.ready {
                     if ac.flame.is_none() && pos.x < ac.fire_pos {
                         // 达到了发射位置，现在发射火球
-                        let texture = asset_server.load("images/flame.png");
+                        let texture = assets.load(ThemeAsset::Flame);
                         let pos_y = if pos.y > 0. { -20. } else { 20. };
                         let flame = commands.spawn((
                             DespawnOnExit(GameState::Gaming),
@@ -162,6 +174,48 @@ pub fn move_flying_unit(
     }
 }
 
+/// 之字形敌机定时切换到相邻的航道，相邻航道上离得太近的位置有其它飞行单元时不切换
+pub fn zigzag_aircraft(
+    mut game_routes: ResMut<GameRoutes>,
+    mut query: Query<(Entity, &mut FlyingUnit, &mut Transform, &mut ZigZag)>,
+    others: Query<&Transform, (With<FlyingUnit>, Without<ZigZag>)>,
+    time: Res<Time>,
+    ui_scale: Res<UiScale>,
+    window: Single<&Window>,
+) {
+    let mut rng = rand::rng();
+    let route_count = game_routes.len() as i32;
+    let top = routes_top(window.height(), ui_scale.0);
+    // 蛇行敌机的水平位置，蛇行只改变纵坐标，换道时也要和其它蛇行敌机保持间距
+    let zigzag_x: HashMap<Entity, f32> = query.iter().map(|(e, _, t, _)| (e, t.translation.x)).collect();
+    for (entity, mut unit, mut transform, mut zigzag) in &mut query {
+        if zigzag.timer.tick(time.delta()).just_finished() {
+            let step = if rng.random_bool(0.5) { 1 } else { -1 };
+            let mut id = unit.route + step;
+            if id < 0 || id >= route_count {
+                id = unit.route - step;
+            }
+            let x = transform.translation.x;
+            let gap = |e: &Entity| {
+                let other_x = others.get(*e).map(|t| t.translation.x).ok().or_else(|| zigzag_x.get(e).copied());
+                other_x.filter(|_| *e != entity).map_or(f32::INFINITY, |ox| (ox - x).abs())
+            };
+            // 先占用新航道，成功后再离开原来的航道，避免飞机不在任何航道上
+            if game_routes.is_clear(id, MIN_ROUTE_SPACING, gap)
+                && let Some(route) = game_routes.occupy(id) {
+                route.units.push(entity);
+                zigzag.target_y = route.position(top);
+                let old_route = std::mem::replace(&mut unit.route, id);
+                game_routes.release(old_route, &entity);
+            }
+        }
+
+        let dy = zigzag.target_y - transform.translation.y;
+        let step = ZIGZAG_SPEED * time.delta_secs();
+        transform.translation.y += dy.clamp(-step, step);
+    }
+}
+
 pub fn animate_miss_text(
     time: Res<Time>,
     mut query: Query<(&mut TextColor, &mut Transform, &mut MissText), With<MissText>>
@@ -178,81 +232,149 @@ pub fn animate_miss_text(
 pub fn on_keyboard_input(
     keyboard_input: Res<ButtonInput<KeyCode>>,
     mut speed_factor: ResMut<SpeedFactor>,
-    mut next_state: ResMut<NextState<PlayState>>
+    mut next_state: ResMut<NextState<PlayState>>,
+    controller: Res<DifficultyController>,
 ) {
     if keyboard_input.just_released(KeyCode::Space) {
         next_state.set(PlayState::Paused);
     } else if keyboard_input.just_released(KeyCode::Escape) {
         next_state.set(PlayState::Exiting);
+    } else if controller.enabled {
+        // 自动调整难度时不允许手动修改速度
     } else if keyboard_input.just_released(KeyCode::ArrowUp) {
-        if speed_factor.factor_changes < 5 {
-            speed_factor.factor_changes += 1;
-        }
-        speed_factor.speed_factor = 1.25_f32.powi(speed_factor.factor_changes);
+        *speed_factor = SpeedFactor::from_changes(speed_factor.factor_changes + 1);
     } else if keyboard_input.just_released(KeyCode::ArrowDown) {
-        if speed_factor.factor_changes > 0 {
-            speed_factor.factor_changes -= 1;
-        }
-        speed_factor.speed_factor = 1.25_f32.powi(speed_factor.factor_changes);
+        *speed_factor = SpeedFactor::from_changes(speed_factor.factor_changes - 1);
+    }
+}
+
+/// 记录飞行单元出现的时间，恢复的飞行单元已经带有保存时的出现时间
+pub fn on_flying_unit_added(event: On<Add, FlyingUnit>, mut commands: Commands, time: Res<Time>) {
+    commands.entity(event.entity).insert_if_new(SpawnTime(time.elapsed_secs()));
+}
+
+/// 每个目标正在飞来的导弹数量
+fn missiles_in_flight<'a>(missiles: impl Iterator<Item = &'a Missile>) -> HashMap<Entity, usize> {
+    let mut counts = HashMap::new();
+    for missile in missiles.filter(|missile| missile.typed) {
+        *counts.entry(missile.target).or_default() += 1;
+    }
+    counts
+}
+
+/// 飞行单元下一枚导弹应该对应的字符。已经有导弹飞向目标时，这些导弹要击中的字符不能再用，
+/// 装甲敌机和战舰依次取后面的字符，普通敌机返回None
+fn next_target_letter(unit: &FlyingUnit, armored: Option<&Armored>, sentence: Option<&WarshipSentence>,
+                      in_flight: usize) -> Option<char> {
+    if unit.kind == FlyingUnitKind::Warship {
+        return sentence.and_then(|s| s.letters.get(s.current + in_flight)).copied();
+    }
+    match in_flight {
+        0 => Some(unit.letter),
+        n => armored.and_then(|a| a.letters.get(n - 1)).copied(),
     }
 }
 
+/// 按照设置的目标策略为玩家按下的字符选择目标，已经有导弹飞向的目标留给后面的按键
 pub fn on_player_char_input(
     mut commands: Commands,
     mut keyboard_inputs: MessageReader<KeyboardInput>,
-    mut query: Query<(Entity, &FlyingUnit)>,
+    units: Query<(Entity, &FlyingUnit, &Transform, Option<&Armored>, Option<&SpawnTime>)>,
+    missiles: Query<&Missile, Without<FadingMissile>>,
     sentence: Option<Res<WarshipSentence>>,
     game_settings: Res<GameSettings>,
-    asset_server: Res<AssetServer>,
+    settings: Res<UserSettings>,
+    fighter_jet: Single<&Transform, With<FighterJet>>,
+    assets: ThemeAssets,
+    time: Res<Time>,
     window: Single<&Window>
 ) {
+    let mut in_flight = missiles_in_flight(missiles.iter());
     for event in keyboard_inputs.read() {
         if !event.state.is_pressed() {
             continue;
         }
         if let Key::Character(character) = &event.logical_key
             && let Some(c) = character.chars().next() {
-            // 找到玩家输入字符对应的飞行单元（可能多个）
-            let missile = asset_server.load("images/missile.png");
-            let missile_pos = FIGHTER_JET_MARGIN - window.width()/2. + FIGHTER_JET_SIZE*FIGHTER_JET_SCALE/2.;
-            for (entity, unit) in &mut query {
-                if sentence.is_none() && unit.letter.to_ascii_uppercase() != c.to_ascii_uppercase() {
-                    continue;
+            let letter = c.to_ascii_uppercase();
+            // 找到下一个字符与玩家输入相同的飞行单元（可能多个）
+            let mut targets: Vec<_> = units.iter()
+                .filter(|(entity, unit, _, armored, _)| {
+                    let count = in_flight.get(entity).copied().unwrap_or_default();
+                    next_target_letter(unit, *armored, sentence.as_deref(), count)
+                        .is_some_and(|l| l.to_ascii_uppercase() == letter)
+                })
+                .collect();
+            match settings.targeting {
+                TargetingPolicy::Nearest => {
+                    let origin = fighter_jet.translation.truncate();
+                    targets.sort_by(|a, b| origin.distance(a.2.translation.truncate())
+                        .total_cmp(&origin.distance(b.2.translation.truncate())));
+                    targets.truncate(1);
                 }
-                commands.spawn((
-                    DespawnOnExit(GameState::Gaming),
-                    Sprite {
-                        image: missile.clone(),
-                        image_mode: SpriteImageMode::Auto,
-                        color: Color::WHITE,
-                        ..default()
-                    },
-                    Transform::from_translation(Vec3::new(missile_pos, -20., 1.))
-                        .with_scale(Vec3::splat(FIGHTER_JET_SCALE)),
-                    Missile {
-                        speed: game_settings.missile_speed,
-                        target: entity,
-                        letter: c.to_ascii_uppercase(),
-                    }
-                ));
+                TargetingPolicy::Oldest => {
+                    targets.sort_by(|a, b| a.4.map_or(0., |t| t.0).total_cmp(&b.4.map_or(0., |t| t.0)));
+                    targets.truncate(1);
+                }
+                TargetingPolicy::All => (),
+            }
+            let missile = assets.load(ThemeAsset::Missile);
+            for (entity, ..) in &targets {
+                spawn_missile(&mut commands, missile.clone(), &window, Missile {
+                    speed: game_settings.missile_speed,
+                    target: *entity,
+                    letter,
+                    typed: true,
+                    fire_time: time.elapsed_secs(),
+                });
+                *in_flight.entry(*entity).or_default() += 1;
+            }
+            // 按住不放产生的重复输入不算按错键
+            if event.repeat {
+                continue;
+            }
+            if !targets.is_empty() {
+                commands.trigger(CorrectKeyEvent);
+            } else {
+                commands.trigger(StreakBrokenEvent { wrong_key: true, letter: c });
             }
         }
     }
 }
 
+/// 从玩家战斗机发射一枚追踪目标的导弹
+pub fn spawn_missile(
+    commands: &mut Commands,
+    texture: Handle<Image>,
+    window: &Window,
+    missile: Missile,
+) {
+    let missile_pos = FIGHTER_JET_MARGIN - window.width()/2. + FIGHTER_JET_SIZE*FIGHTER_JET_SCALE/2.;
+    commands.spawn((
+        DespawnOnExit(GameState::Gaming),
+        Sprite {
+            image: texture,
+            image_mode: SpriteImageMode::Auto,
+            color: Color::WHITE,
+            ..default()
+        },
+        Transform::from_translation(Vec3::new(missile_pos, -20., 1.))
+            .with_scale(Vec3::splat(FIGHTER_JET_SCALE)),
+        missile,
+    ));
+}
+
 pub fn update_player_status(
     mut commands: Commands,
     settings: Res<GameSettings>,
-    asset_server: Res<AssetServer>,
     mut player: ResMut<GamePlayer>,
     mut health_bars: Query<(Entity, &mut HealthBar)>,
     mut score_text: Single<&mut Text, With<PlayerScore>>,
     mut color_query: Query<&mut BackgroundColor>,
     mut upgrade_progress: Single<&mut Node, With<LevelProgress>>,
-    mut game_letters: ResMut<GameLetters>,
-    mut level_stars: Single<(&mut ImageNode, &mut Node), (With<LevelStarImage>, Without<LevelProgress>)>,
     mut next_state: ResMut<NextState<PlayState>>,
     children_query: Query<&Children>,
+    accessibility: Res<Accessibility>,
     letters: Query<Entity, With<WarshipLetter>>,
     flames: Query<Entity, With<Flame>>,
     indicator: Query<Entity, With<WarshipLetterArrow>>,
@@ -263,18 +385,12 @@ pub fn update_player_status(
     **score_text = Text::new(format!("{}", player.player.score));
 
     // 判断用户是否升级了
-    if player.player.level != MAX_PLAYER_LEVELS {
-        let mut base_score = 0_u32;
-        for i in 0..player.player.level {
-            base_score += settings.upgrade_scores[i as usize];
-        }
-        if player.player.score >= base_score {
-            player.player.level += 1;
-            level_stars.0.image = asset_server.load(&format!("images/star-{}.png", player.player.level));
-            level_stars.1.width = Val::Px(24.*(player.player.level as f32));
-            game_letters.candidate_letters = settings.level_letters[player.player.level as usize - 1].clone();
-            next_state.set(PlayState::Upgrading);
-        }
+    // 课程模式不会升级
+    if player.mode == GameMode::Arcade
+        && let Some(level) = progress::next_level(&settings.upgrade_scores(), player.player.level, player.player.score) {
+        player.player.level = level;
+        commands.trigger(LevelUpEvent);
+        next_state.set(PlayState::Upgrading);
     }
     upgrade_progress.width = Val::Percent(calculate_upgrade_percent(&player.player, &settings));
 
@@ -283,7 +399,7 @@ pub fn update_player_status(
             if let Ok(children) = children_query.get(entity) {
                 for (index, child) in children.iter().enumerate() {
                     let bg_color = if index < player.health as usize {
-                        gradient_health_bar_color(index as u16)
+                        gradient_health_bar_color(index as u16, &accessibility)
                     } else {
                         Color::srgb_u8(70,70,70)
                     };
@@ -326,24 +442,58 @@ fn spawn_explosion_animation(commands: &mut Commands, transform: Transform, expl
     ));
 }
 
+/// 目标消失的导弹转向另一个下一个字符相同并且离导弹最近的目标，找不到时逐渐消失
+fn retarget_missile(
+    commands: &mut Commands,
+    entity: Entity,
+    missile: &mut Missile,
+    position: Vec2,
+    in_flight: &mut HashMap<Entity, usize>,
+    flying_units: &Query<(Entity, &FlyingUnit, &Transform), (With<FlyingUnit>, Without<Missile>)>,
+    armored: &Query<&Armored>,
+) -> bool {
+    let target = flying_units.iter()
+        .filter(|(target, unit, _)| {
+            let count = in_flight.get(target).copied().unwrap_or_default();
+            next_target_letter(unit, armored.get(*target).ok(), None, count)
+                .is_some_and(|l| l.to_ascii_uppercase() == missile.letter)
+        })
+        .min_by(|a, b| position.distance(a.2.translation.truncate())
+            .total_cmp(&position.distance(b.2.translation.truncate())));
+    if let Some((target, ..)) = target {
+        missile.target = target;
+        if missile.typed {
+            *in_flight.entry(target).or_default() += 1;
+        }
+        true
+    } else {
+        commands.entity(entity).insert(FadingMissile(Timer::from_seconds(MISSILE_FADE_SECS, TimerMode::Once)));
+        false
+    }
+}
+
 pub fn update_missiles_for_aircraft(
     mut commands: Commands,
-    mut missiles: Query<(Entity, &Missile, &mut Transform), Without<FlyingUnit>>,
-    mut player: ResMut<GamePlayer>,
+    mut missiles: Query<(Entity, &mut Missile, &mut Transform), (Without<FlyingUnit>, Without<FadingMissile>)>,
     mut counter: ResMut<FlyingUnitCounter>,
     mut counter_texts: Query<(&mut Text, &FlyingUnitText), With<FlyingUnitText>>,
     aircraft: Query<&Aircraft>,
+    armored: Query<&Armored>,
+    settings: Res<GameSettings>,
     time: Res<Time>,
+    spawn_times: Query<&SpawnTime>,
     explosion: ResMut<ExplosionTexture>,
-    flying_units: Query<(&FlyingUnit, &Transform), (With<FlyingUnit>, Without<Missile>)>,
+    flying_units: Query<(Entity, &FlyingUnit, &Transform), (With<FlyingUnit>, Without<Missile>)>,
 ) {
-    for (entity, missile, mut transform) in &mut missiles {
-        // 获取目标
-        let (unit, target_transform) = if let Ok(t) = flying_units.get(missile.target) {
-            t
-        } else {
-            // 目标不存在则移除导弹
-            commands.entity(entity).despawn();
+    let mut in_flight = missiles_in_flight(missiles.iter().map(|(_, missile, _)| missile));
+    for (entity, mut missile, mut transform) in &mut missiles {
+        // 获取目标，目标已经被其它导弹击落或者飞出边界时换一个目标
+        if flying_units.get(missile.target).is_err()
+            && !retarget_missile(&mut commands, entity, &mut missile, transform.translation.truncate(),
+                                 &mut in_flight, &flying_units, &armored) {
+            continue;
+        }
+        let Ok((_, unit, target_transform)) = flying_units.get(missile.target) else {
             continue;
         };
 
@@ -361,59 +511,70 @@ pub fn update_missiles_for_aircraft(
         // 命中检测
         if current_pos.distance(target_pos) < 30.0 {
             commands.entity(entity).despawn();
+            // 装甲敌机需要依次击中所有字符才会被摧毁
+            if let Ok(armored) = armored.get(missile.target) && !armored.letters.is_empty() {
+                if missile.letter == unit.letter.to_ascii_uppercase() {
+                    commands.trigger(ScoreEvent {
+                        base: settings.scoring.aircraft_score,
+                        translation: target_transform.translation,
+                    });
+                    commands.trigger(ArmorHitEvent(missile.target));
+                }
+                continue;
+            }
             commands.entity(missile.target).despawn();
 
             // 更新统计信息
             match unit.kind {
                 FlyingUnitKind::Aircraft => {
-                    counter.destroyed += 1;
-                    let mut text = counter_texts.iter_mut().find_map(
-                        |(txt, FlyingUnitText(kind))| {
-                            matches!(kind, FlyingUnitKind::Aircraft).then_some(txt)
-                        }
-                    ).unwrap();
-                    *text = Text::new(format!("{}/{}", counter.destroyed, counter.missed));
-                    player.player.score += 1;
+                    let enemy = aircraft.get(missile.target).map(|ac| ac.kind).unwrap_or_default();
+                    // 分裂出来的小敌机不计入统计
+                    if enemy != EnemyKind::Fragment {
+                        counter.destroyed += 1;
+                        let mut text = counter_texts.iter_mut().find_map(
+                            |(txt, FlyingUnitText(kind))| {
+                                matches!(kind, FlyingUnitKind::Aircraft).then_some(txt)
+                            }
+                        ).unwrap();
+                        *text = Text::new(format!("{}/{}", counter.destroyed, counter.missed));
+                        // 更新敌机的血条
+                        commands.trigger(UpdateHealthBarEvent(counter.destroyed as u16));
+                    }
+                    commands.trigger(ScoreEvent {
+                        base: settings.scoring.aircraft_score,
+                        translation: target_transform.translation,
+                    });
                     // 销毁发射的火球
                     if let Ok(ac) = aircraft.get(missile.target) && ac.flame.is_some() {
                         commands.entity(ac.flame.unwrap()).despawn();
                     }
-                    // 更新敌机的血条
-                    commands.trigger(UpdateHealthBarEvent(counter.destroyed as u16));
+                    if enemy == EnemyKind::Splitter {
+                        commands.trigger(AircraftSplitEvent {
+                            translation: target_transform.translation,
+                            route: unit.route,
+                            speed: unit.speed,
+                        });
+                    }
+                    // 反应时间是从敌机出现到发射导弹的时间，导弹换了目标时新目标可能在发射后才出现
+                    let reaction = spawn_times.get(missile.target)
+                        .map_or(0., |spawned| (missile.fire_time - spawned.0).max(0.));
+                    commands.trigger(AircraftOutcomeEvent { hit: true, letter: unit.letter, reaction });
                 },
                 FlyingUnitKind::Warship => {},
-                FlyingUnitKind::Bomb => {
-                    counter.bomb += 1;
-                    let mut text = counter_texts.iter_mut().find_map(
-                        |(txt, FlyingUnitText(kind))| {
-                            matches!(kind, FlyingUnitKind::Bomb).then_some(txt)
-                        }
-                    ).unwrap();
-                    *text = Text::new(format!("{}", counter.bomb));
-                    commands.trigger(BombExplodedEvent);
-                },
-                FlyingUnitKind::Shield => {
-                    counter.shield += 1;
-                    let mut text = counter_texts.iter_mut().find_map(
-                        |(txt, FlyingUnitText(kind))| {
-                            matches!(kind, FlyingUnitKind::Shield).then_some(txt)
-                        }
-                    ).unwrap();
-                    *text = Text::new(format!("{}", counter.shield));
-                    // 给玩家增加护盾
-                    commands.trigger(ShieldActivatedEvent);
-                },
-                FlyingUnitKind::HealthPack => {
-                    counter.health_pack += 1;
-                    let mut text = counter_texts.iter_mut().find_map(
-                        |(txt, FlyingUnitText(kind))| {
-                            matches!(kind, FlyingUnitKind::HealthPack).then_some(txt)
-                        }
-                    ).unwrap();
-                    *text = Text::new(format!("{}", counter.health_pack));
-                    player.health = (player.health + HEALTH_PACK_RESTORE).min(HEALTH_MAX_VALUE);
-                    // 给玩家显示健康恢复特效
-                    commands.trigger(HealthPackApplyEvent);
+                kind => {
+                    // 更新界面上显示的累计收集数量
+                    let count = match kind {
+                        FlyingUnitKind::Bomb => { counter.bomb += 1; Some(counter.bomb) }
+                        FlyingUnitKind::Shield => { counter.shield += 1; Some(counter.shield) }
+                        FlyingUnitKind::HealthPack => { counter.health_pack += 1; Some(counter.health_pack) }
+                        _ => None,
+                    };
+                    if let Some(count) = count
+                        && let Some(mut text) = counter_texts.iter_mut().find_map(
+                            |(txt, FlyingUnitText(k))| (*k == kind).then_some(txt)) {
+                        *text = Text::new(format!("{}", count));
+                    }
+                    commands.trigger(EquipmentCollectedEvent(kind));
                 }
             }
 
@@ -427,27 +588,52 @@ pub fn update_missiles_for_aircraft(
 
 pub fn update_warship_letter_arrow(
     sentence: Res<WarshipSentence>,
-    letters: Query<(&Transform, &WarshipLetter), With<WarshipLetter>>,
+    letters: Query<(&Transform, &TextFont, &WarshipLetter), With<WarshipLetter>>,
     mut arrow: Single<(&mut Transform, &mut Visibility), (With<WarshipLetterArrow>, Without<WarshipLetter>)>,
 ) {
-    arrow.0.translation.x = letters.iter()
-        .find_map(|(transform, letter)| (letter.0 == sentence.current)
-            .then(|| transform.translation.x)).unwrap_or(0.0);
+    // 句子换行显示时箭头也要移动到对应的行
+    if let Some((transform, font, _)) = letters.iter().find(|(_, _, letter)| letter.0 == sentence.current) {
+        arrow.0.translation.x = transform.translation.x;
+        arrow.0.translation.y = transform.translation.y + font.font_size / 2. + 15.;
+    }
+}
+
+/// 窗口大小变化后重新排列Boss句子
+pub fn relayout_warship_sentence(
+    mut letters: Query<(&mut Transform, &mut TextFont, &WarshipLetter)>,
+    mut sentence: ResMut<WarshipSentence>,
+    window: Single<&Window>,
+) {
+    let (font_size, positions) = layout_warship_sentence(&sentence.sentence, &window);
+    for (mut transform, mut font, letter) in &mut letters {
+        if let Some(pos) = positions.get(letter.0) {
+            transform.translation = pos.extend(transform.translation.z);
+            font.font_size = font_size;
+        }
+    }
+    // 触发箭头位置的更新
+    sentence.set_changed();
 }
 
 pub fn update_missiles_for_warship(
     mut commands: Commands,
-    mut player: ResMut<GamePlayer>,
-    mut missiles: Query<(Entity, &Missile, &mut Transform), Without<FlyingUnit>>,
+    mut missiles: Query<(Entity, &Missile, &mut Transform), (Without<FlyingUnit>, Without<FadingMissile>)>,
     mut sentence: ResMut<WarshipSentence>,
     mut letters: Query<(&WarshipLetter, &mut TextColor), (With<WarshipLetter>, Without<Missile>)>,
-    warship: Single<(&mut FlyingUnit, &Transform), (With<SpaceWarship>, Without<Missile>, Without<WarshipLetter>)>,
+    warship: Single<(Entity, &mut FlyingUnit, &Transform), (With<SpaceWarship>, Without<Missile>, Without<WarshipLetter>)>,
+    settings: Res<GameSettings>,
+    accessibility: Res<Accessibility>,
     time: Res<Time>,
     explosion: ResMut<ExplosionTexture>,
     window: Single<&Window>
 ) {
-    let (mut unit, target_transform) = warship.into_inner();
+    let (warship, mut unit, target_transform) = warship.into_inner();
     for (entity, missile, mut transform) in &mut missiles {
+        if missile.target != warship {
+            // 战舰出现前发射的导弹已经没有目标
+            commands.entity(entity).insert(FadingMissile(Timer::from_seconds(MISSILE_FADE_SECS, TimerMode::Once)));
+            continue;
+        }
         let target_pos = target_transform.translation.truncate();
         let current_pos = transform.translation.truncate();
         let dir = (target_pos - current_pos).normalize_or_zero();
@@ -462,29 +648,38 @@ pub fn update_missiles_for_warship(
             if unit.letter.to_ascii_uppercase() != missile.letter {
                 continue;
             }
+            commands.trigger(WarshipLetterEvent);
             if sentence.current == sentence.letters.len() - 1 {
                 // 所有字符都被击毁，玩家通关了
                 for (letter, mut color) in &mut letters {
                     if letter.0 == sentence.current {
-                        *color = TextColor(CHECKPOINT_LETTER_DESTROYED);
+                        *color = TextColor(accessibility.checkpoint_destroyed());
                         break;
                     }
                 }
                 commands.remove_resource::<WarshipSentence>();
                 commands.entity(missile.target).despawn();
-                player.player.score += 50;
+                commands.trigger(ScoreEvent {
+                    base: settings.scoring.warship_score,
+                    translation: target_transform.translation,
+                });
 
                 commands.insert_resource(CheckpointTimer(Timer::from_seconds(1., TimerMode::Once)));
             } else {
                 // 调整给玩家看的字符列表
                 for (letter, mut color) in &mut letters {
                     if letter.0 == sentence.current {
-                        *color = TextColor(CHECKPOINT_LETTER_DESTROYED);
+                        *color = TextColor(accessibility.checkpoint_destroyed());
                     } else if letter.0 == sentence.current + 1 {
-                        *color = TextColor(CHECKPOINT_LETTER_TARGET);
+                        *color = TextColor(accessibility.checkpoint_target());
                     }
                 }
 
+                commands.trigger(ScoreEvent {
+                    base: settings.scoring.warship_letter_score,
+                    translation: target_transform.translation,
+                });
+
                 // 绑定下一个待击毁的字符
                 sentence.current += 1;
                 unit.letter = sentence.letters[sentence.current];
@@ -504,12 +699,13 @@ pub fn animate_explosion_sheet(
     mut commands: Commands,
     time: Res<Time>,
     mut query: Query<(Entity, &mut Explosion, &mut Sprite)>,
+    explosion: Res<ExplosionTexture>,
 ) {
     for (entity, mut timer, mut sprite) in &mut query {
         timer.tick(time.delta());
 
         if timer.just_finished() && let Some(atlas) = &mut sprite.texture_atlas {
-            if atlas.index < EXPLOSION_SHEET_MAX_INDEX {
+            if atlas.index + 1 < explosion.frames {
                 atlas.index += 1;
             } else {
                 // 爆炸动画结束，删除爆炸效果
@@ -523,13 +719,15 @@ pub fn update_aircraft_flames(
     mut commands: Commands,
     mut game_player: ResMut<GamePlayer>,
     mut flames: Query<(Entity, &Flame, &mut Transform), Without<FighterJet>>,
-    mut fighter_jet: Single<(&mut FighterJet, &Transform), With<FighterJet>>,
+    fighter_jet: Single<&Transform, With<FighterJet>>,
+    mut effects: ResMut<ActiveEffects>,
+    assets: ThemeAssets,
     time: Res<Time>,
-    settings: Res<GameSettings>
 ) {
+    let protected = effects.is_active(FlyingUnitKind::Shield);
     for (flame_entity, flame, mut transform) in &mut flames {
         // 获取目标玩家
-        let target_pos = fighter_jet.1.translation.truncate();
+        let target_pos = fighter_jet.translation.truncate();
         let current_pos = transform.translation.truncate();
 
         // 方向
@@ -543,107 +741,76 @@ pub fn update_aircraft_flames(
         transform.rotation = Quat::from_rotation_z(angle);
 
         // 命中检测
-        if fighter_jet.0.protected &&
-            time.elapsed_secs() - fighter_jet.0.protect_since > settings.shield_active_time {
-            fighter_jet.0.protected = false;
-        }
-        if fighter_jet.0.protected {
+        if protected {
             if current_pos.distance(target_pos) < 80.0 {
                 commands.entity(flame_entity).despawn();
             }
         } else if current_pos.distance(target_pos) < 30.0 {
             commands.entity(flame_entity).despawn();
             if game_player.health != 0 {
-                game_player.health -= flame.hurt;
+                game_player.health = game_player.health.saturating_sub(flame.hurt);
+                // 有额外生命时玩家被击毁后立即复活
+                if game_player.health == 0 && effects.revives > 0 {
+                    effects.revives -= 1;
+                    game_player.health = HEALTH_MAX_VALUE / 2;
+                    spawn_effect_visual(&mut commands, &assets, FlyingUnitKind::ExtraLife,
+                                        fighter_jet.translation, 1.5, 1.5);
+                }
             }
         }
     }
 }
 
-pub fn on_bomb_exploded(
-    _: On<BombExplodedEvent>,
-    mut commands: Commands,
-    query: Single<&Transform, With<FighterJet>>,
-    aircraft: Query<(Entity, &FlyingUnit), With<Aircraft>>,
-    asset_server: Res<AssetServer>,
-    game_settings: Res<GameSettings>,
-    window: Single<&Window>
+/// 装甲敌机被击中当前字符后切换到下一个字符
+pub fn on_armor_hit(
+    event: On<ArmorHitEvent>,
+    mut units: Query<(&mut FlyingUnit, &mut Armored, &Children)>,
+    mut labels: Query<&mut Text2d>,
+    mut game_letters: ResMut<GameLetters>,
 ) {
-    let texture = asset_server.load("images/enhance.png");
-    commands.spawn((
-        DespawnOnExit(GameState::Gaming),
-        Sprite {
-            image: texture.clone(),
-            image_mode: SpriteImageMode::Auto,
-            color: Color::srgba(1., 1., 1., 0.5),
-            ..default()
-        },
-        Transform::from_translation(query.translation).with_scale(Vec3::splat(0.5)),
-        EquipmentEffect {
-            timer: Timer::from_seconds(1.5, TimerMode::Once),
-            duration: 1.5
+    let Ok((mut unit, mut armored, children)) = units.get_mut(event.0) else {
+        return;
+    };
+    game_letters.put_back([unit.letter]);
+    unit.letter = armored.letters.remove(0);
+    let label: String = std::iter::once(unit.letter).chain(armored.letters.iter().copied()).collect();
+    for child in children.iter() {
+        if let Ok(mut text) = labels.get_mut(child) {
+            **text = label.clone();
         }
-    ));
-
-    let missile = asset_server.load("images/missile.png");
-    let missile_pos = FIGHTER_JET_MARGIN - window.width()/2. + FIGHTER_JET_SIZE*FIGHTER_JET_SCALE/2.;
-    for (entity, unit) in aircraft.iter() {
-        commands.spawn((
-            DespawnOnExit(GameState::Gaming),
-            Sprite {
-                image: missile.clone(),
-                image_mode: SpriteImageMode::Auto,
-                color: Color::WHITE,
-                ..default()
-            },
-            Transform::from_translation(Vec3::new(missile_pos, -20., 0.))
-                .with_scale(Vec3::splat(FIGHTER_JET_SCALE)),
-            Missile {
-                speed: game_settings.missile_speed,
-                target: entity,
-                letter: unit.letter,
-            }
-        ));
     }
 }
 
-pub fn on_shield_activated (
-    _: On<ShieldActivatedEvent>,
-    mut commands: Commands,
-    mut query: Single<(&mut FighterJet, &Transform), With<FighterJet>>,
-    time: Res<Time>,
+/// 玩家升级后更新星级显示，并换成新级别的关卡
+pub fn on_level_up(
+    _: On<LevelUpEvent>,
+    mut game_letters: ResMut<GameLetters>,
+    mut active_level: ResMut<ActiveLevel>,
+    mut level_stars: Single<(&mut ImageNode, &mut Node), With<LevelStarImage>>,
+    mut level_text: Single<&mut Text, With<LevelStarText>>,
+    player: Res<GamePlayer>,
     settings: Res<GameSettings>,
-    assets: Res<AssetServer>
+    asset_server: Res<AssetServer>,
 ) {
-    query.0.protected = true;
-    query.0.protect_since = time.elapsed_secs();
-    let texture = assets.load("images/shield_activated.png");
-    commands.spawn((
-        DespawnOnExit(GameState::Gaming),
-        Sprite {
-            image: texture.clone(),
-            image_mode: SpriteImageMode::Auto,
-            color: Color::srgba(1., 1., 1., 0.5),
-            ..default()
-        },
-        Transform::from_translation(query.1.translation).with_scale(Vec3::splat(0.5)),
-        EquipmentEffect {
-            timer: Timer::from_seconds(settings.shield_active_time, TimerMode::Once),
-            duration: settings.shield_active_time
-        }
-    ));
+    let level = player.player.level;
+    let (star_image, stars) = level_star_image(level);
+    level_stars.0.image = asset_server.load(star_image);
+    level_stars.1.width = Val::Px(24.*(stars as f32));
+    **level_text = Text::new(level_star_label(level));
+    active_level.0 = settings.level(level).clone();
+    game_letters.reset(&active_level.0.letters);
 }
 
 /// 用于更新敌方的血条
 pub fn on_update_health_bar(
     event: On<UpdateHealthBarEvent>,
     mut health_bars: Query<(Entity, &mut HealthBar)>,
-    game_settings: Res<GameSettings>,
-    player: Res<GamePlayer>,
+    active_level: Res<ActiveLevel>,
+    accessibility: Res<Accessibility>,
     children_query: Query<&Children>,
     mut color_query: Query<&mut BackgroundColor>
 ) {
-    let total = game_settings.aircraft_count[(player.player.level-1) as usize] as u16;
+    let total = active_level.0.aircraft_count() as u16;
     if total < event.0 {
         return;
     }
@@ -654,7 +821,7 @@ pub fn on_update_health_bar(
             if let Ok(children) = children_query.get(entity) {
                 for (index, child) in children.iter().enumerate() {
                     let bg_color = if (HEALTH_MAX_VALUE - index as u16) <= health {
-                        gradient_health_bar_color(HEALTH_MAX_VALUE - index as u16)
+                        gradient_health_bar_color(HEALTH_MAX_VALUE - index as u16, &accessibility)
                     } else {
                         Color::srgb_u8(70,70,70)
                     };
@@ -663,40 +830,24 @@ pub fn on_update_health_bar(
                     }
                 }
             }
-            bar.value = player.health;
+            bar.value = health;
         }
     }
 }
 
-pub fn on_health_pack_apply(
-    _: On<HealthPackApplyEvent>,
-    mut commands: Commands,
-    query: Single<&Transform, With<FighterJet>>,
-    assets: Res<AssetServer>
-) {
-    let texture = assets.load("images/health_pack_apply.png");
-    commands.spawn((
-        DespawnOnExit(GameState::Gaming),
-        Sprite {
-            image: texture.clone(),
-            image_mode: SpriteImageMode::Auto,
-            color: Color::srgba(1., 1., 1., 0.5),
-            ..default()
-        },
-        Transform::from_translation(query.translation).with_scale(Vec3::splat(0.5)),
-        EquipmentEffect {timer: Timer::from_seconds(1.5, TimerMode::Once), duration: 1.5}
-        ));
-}
-
 pub fn equipment_effect(
     mut commands: Commands,
     time: Res<Time>,
+    accessibility: Res<Accessibility>,
     mut query: Query<(Entity, &mut Transform, &mut EquipmentEffect)>,
 ) {
     for (e, mut transform, mut equipment) in &mut query {
         if !equipment.timer.is_finished() {
             equipment.timer.tick(time.delta());
-            
+            if accessibility.reduced_motion {
+                continue;
+            }
+
             let delta_angle = -std::f32::consts::TAU * time.delta_secs() / equipment.duration;
             transform.rotate_z(delta_angle);
         } else {
@@ -710,8 +861,8 @@ pub fn warship_fires(
     warship: Single<(&mut SpaceWarship, &Transform)>,
     time: Res<Time>,
     settings: Res<GameSettings>,
-    player: Res<GamePlayer>,
-    assets: Res<AssetServer>
+    active_level: Res<ActiveLevel>,
+    assets: ThemeAssets
 ) {
     let (mut warship, transform) = warship.into_inner();
     let cx = transform.translation.x;
@@ -738,7 +889,7 @@ pub fn warship_fires(
             }
             warship.gun_state = [false; 12];
             warship.timer = Timer::from_seconds(
-                settings.warship_gun_interval[player.player.level as usize - 1],
+                active_level.0.warship_gun_interval,
                 TimerMode::Repeating);
             warship.fired = true;
             warship.gun_fired = 0;
@@ -761,7 +912,7 @@ pub fn warship_fires(
             }
             if gun_fired {
                 let pos = warship.gun_pos[gun];
-                let texture = assets.load("images/flame_gun.png");
+                let texture = assets.load(ThemeAsset::FlameGun);
                 commands.spawn((
                     DespawnOnExit(GameState::Gaming),
                     Sprite {
@@ -783,7 +934,7 @@ pub fn warship_fires(
             if !gun_fired {
                 let fire: bool = random();
                 if warship.cannon && fire {
-                    let texture = assets.load("images/flame_cannon.png");
+                    let texture = assets.load(ThemeAsset::FlameCannon);
                     let pos = warship.cannon_pos;
                     commands.spawn((
                         DespawnOnExit(GameState::Gaming),
@@ -800,7 +951,7 @@ pub fn warship_fires(
                 }
                 warship.fired = false;
                 warship.timer = Timer::from_seconds(
-                    settings.warship_fire_interval[player.player.level as usize - 1],
+                    active_level.0.warship_fire_interval,
                     TimerMode::Repeating
                 );
             }
@@ -832,4 +983,22 @@ pub fn switch_checkpoint_state(
 
         next_state.set(PlayState::Checkpoint);
     }
-}
\ No newline at end of file
+}
+
+/// 没有目标的导弹沿原来的方向继续飞行并逐渐消失
+pub fn fade_missiles(
+    mut commands: Commands,
+    mut missiles: Query<(Entity, &Missile, &mut FadingMissile, &mut Transform, &mut Sprite)>,
+    time: Res<Time>,
+) {
+    for (entity, missile, mut fading, mut transform, mut sprite) in &mut missiles {
+        fading.0.tick(time.delta());
+        if fading.0.is_finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let dir = transform.rotation * Vec3::X;
+        transform.translation += dir * missile.speed * time.delta_secs();
+        sprite.color.set_alpha(fading.0.fraction_remaining());
+    }
+}
@@ -0,0 +1,122 @@
+use bevy::asset::AssetServer;
+use bevy::prelude::*;
+use crate::{GameFonts, GamePlayer, GameState, PlayState};
+use crate::gaming::common::LastPlayState;
+use crate::playtime::{PlayTime, RestReason};
+use crate::widgets::{Dialog, DialogNote, DialogResult};
+
+/// 休息结束后的选择
+#[derive(Clone, Copy)]
+pub enum RestChoice {
+    Quit,
+    Continue,
+}
+
+/// 休息对话框，休息结束前没有按钮，结束后换成带按钮的对话框
+#[derive(Component)]
+pub struct RestDialog {
+    done: bool,
+}
+
+/// 累计玩家的游戏时间，达到限制后进入强制休息
+pub fn track_play_time(
+    mut commands: Commands,
+    mut play_time: ResMut<PlayTime>,
+    mut next_state: ResMut<NextState<PlayState>>,
+    game_player: Res<GamePlayer>,
+    time: Res<Time>,
+) {
+    if play_time.accumulate(&game_player.player.name, time.delta_secs()).is_some() {
+        play_time.save();
+        commands.insert_resource(LastPlayState(PlayState::Resting));
+        next_state.set(PlayState::Resting);
+    }
+}
+
+/// 显示休息对话框，连续游戏的休息时间没有结束时不显示按钮，当天时间用完时只能退出
+fn spawn_rest_dialog(
+    commands: &mut Commands,
+    reason: Option<RestReason>,
+    game_player: &GamePlayer,
+    game_fonts: &GameFonts,
+    asset_server: &AssetServer,
+) {
+    let message = if reason == Some(RestReason::Daily) {
+        format!("{}，今天的游戏时间已经用完啦，明天再来吧！", game_player.player.name)
+    } else {
+        format!("{}，已经玩了很久啦，站起来活动一下，看看远处吧！", game_player.player.name)
+    };
+    let mut dialog = Dialog::new("Take a Break", &message)
+        .fonts(game_fonts.normal_font.clone(), game_fonts.ui_font.clone())
+        .image(asset_server.load("images/grimacing.png"));
+    let done = reason != Some(RestReason::Session);
+    if !done {
+        dialog = dialog.note("");
+    } else {
+        dialog = dialog.cancel_button("不玩啦！", RestChoice::Quit);
+        if reason.is_none() {
+            dialog = dialog.default_button("继续游戏", RestChoice::Continue);
+        }
+    }
+    let root = dialog.spawn(commands, PlayState::Resting);
+    commands.entity(root).insert(RestDialog { done });
+}
+
+pub fn resting_setup(
+    mut commands: Commands,
+    mut play_time: ResMut<PlayTime>,
+    game_player: Res<GamePlayer>,
+    game_fonts: Res<GameFonts>,
+    asset_server: Res<AssetServer>
+) {
+    let reason = play_time.rest_reason(&game_player.player.name);
+    spawn_rest_dialog(&mut commands, reason, &game_player, &game_fonts, &asset_server);
+}
+
+/// 更新休息倒计时，休息结束后换成带按钮的对话框
+pub fn update_rest_countdown(
+    mut commands: Commands,
+    mut play_time: ResMut<PlayTime>,
+    mut countdown: Query<&mut Text, With<DialogNote>>,
+    dialog: Single<(Entity, &RestDialog)>,
+    game_player: Res<GamePlayer>,
+    game_fonts: Res<GameFonts>,
+    asset_server: Res<AssetServer>,
+) {
+    let (entity, dialog) = dialog.into_inner();
+    if dialog.done {
+        return;
+    }
+
+    let name = &game_player.player.name;
+    let reason = play_time.rest_reason(name);
+    if reason == Some(RestReason::Session) {
+        let remaining = play_time.rest_remaining(name);
+        for mut text in &mut countdown {
+            **text = format!("还需要休息 {:02}:{:02}", remaining / 60, remaining % 60);
+        }
+        return;
+    }
+
+    commands.entity(entity).despawn();
+    spawn_rest_dialog(&mut commands, reason, &game_player, &game_fonts, &asset_server);
+}
+
+pub fn on_rest_choice(
+    mut commands: Commands,
+    mut next_play_state: ResMut<NextState<PlayState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut reader: MessageReader<DialogResult<RestChoice>>,
+    last_state: Option<Res<LastPlayState>>,
+) {
+    if let Some(result) = reader.read().last() {
+        match result.value {
+            RestChoice::Quit => {
+                commands.remove_resource::<LastPlayState>();
+                next_state.set(GameState::Startup);
+            }
+            // 游戏中途休息的直接继续，否则从头开始
+            RestChoice::Continue => next_play_state.set(if last_state.is_some() { PlayState::Playing } else { PlayState::Splash }),
+        }
+    }
+}
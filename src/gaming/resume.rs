@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+use std::fs;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use xtyping_core::ghost::GhostRun;
+use crate::{get_app_data_dir, routes_top, GameMode, GameLetters, GamePlayer, GameRoutes, GameSettings, GAME_APP_NAME};
+use crate::gaming::common::*;
+use crate::gaming::spawn::*;
+use crate::gaming::effects::spawn_effect_visual;
+use crate::gaming::ghost::GhostRace;
+use crate::theme::ThemeAssets;
+use crate::accessibility::LetterStyle;
+
+const SAVED_ROUNDS_FILE: &str = "rounds.json";
+
+/// 保存的飞行单元
+#[derive(Deserialize, Serialize, Clone)]
+pub struct SavedUnit {
+    pub kind: FlyingUnitKind,
+    pub sprite: i32,
+    #[serde(default)]
+    pub enemy: EnemyKind,
+    // 装甲敌机还需要击中的字符
+    #[serde(default)]
+    pub armor: Vec<char>,
+    pub route: i32,
+    pub letter: char,
+    pub speed: f32,
+    pub x: f32,
+    pub y: f32,
+    // 已经出现的秒数，恢复后继续用于计算反应时间
+    #[serde(default)]
+    pub age: f32,
+}
+
+/// 保存的生成计时器状态
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct SavedSpawnState {
+    pub remaining: f32,
+    pub count: usize,
+    pub spawn: bool,
+}
+
+impl SavedSpawnState {
+    fn from_state(state: &SpawnState) -> Self {
+        SavedSpawnState {
+            remaining: state.timer.remaining_secs(),
+            count: state.count,
+            spawn: state.spawn,
+        }
+    }
+
+    pub fn restore(&self, state: &mut SpawnState) {
+        state.timer = Timer::from_seconds(self.remaining, TimerMode::Once);
+        state.count = self.count;
+        state.spawn = self.spawn;
+    }
+}
+
+/// 保存的战舰及句子的进度
+#[derive(Deserialize, Serialize, Clone)]
+pub struct SavedWarship {
+    pub sentence: String,
+    pub current: usize,
+    pub x: f32,
+    pub speed: f32,
+}
+
+/// 玩家中途退出时保存的一局游戏的完整状态
+#[derive(Deserialize, Serialize, Clone)]
+pub struct SavedRound {
+    pub level: u32,
+    pub health: u16,
+    pub game_secs: f64,
+    pub counter: FlyingUnitCounter,
+    pub speed_factor: SpeedFactor,
+    pub units: Vec<SavedUnit>,
+    pub warship: Option<SavedWarship>,
+    pub aircraft_spawn: SavedSpawnState,
+    // 各种装备的生成状态
+    #[serde(default)]
+    pub equipment_spawn: HashMap<FlyingUnitKind, SavedSpawnState>,
+    // 持续性装备效果的剩余时间
+    #[serde(default)]
+    pub effects: HashMap<FlyingUnitKind, f32>,
+    #[serde(default)]
+    pub revives: u32,
+    // 道具栏中还没有使用的装备
+    #[serde(default)]
+    pub inventory: HashMap<FlyingUnitKind, u32>,
+    // 与最好成绩比较的进度曲线
+    #[serde(default)]
+    pub ghost: GhostRun,
+}
+
+impl SavedRound {
+    /// 编辑过或者损坏的保存数据可能让游戏无法继续，例如战舰句子的进度超出了句子的长度
+    fn is_valid(&self) -> bool {
+        self.warship.as_ref()
+            .is_none_or(|warship| warship.current < warship.sentence.chars().filter(|c| *c != ' ').count())
+    }
+}
+
+/// 需要恢复的游戏状态，进入游戏后使用
+#[derive(Resource)]
+pub struct ResumeRound(pub SavedRound);
+
+/// 保存当前这一局游戏
+#[derive(Event)]
+pub struct SaveRoundEvent;
+
+fn load_saved_rounds() -> HashMap<String, SavedRound> {
+    let mut data_file = get_app_data_dir(GAME_APP_NAME);
+    data_file.push(SAVED_ROUNDS_FILE);
+    if !data_file.exists() {
+        return HashMap::new();
+    }
+    fs::read_to_string(&data_file)
+        .and_then(|data| serde_json::from_str::<HashMap<String, SavedRound>>(&data).map_err(|err| err.into()))
+        .unwrap_or_else(|err| {
+            error!("Failed to parse saved rounds: {}", err);
+            HashMap::new()
+        })
+}
+
+fn save_saved_rounds(rounds: &HashMap<String, SavedRound>) {
+    if let Ok(json) = serde_json::to_string_pretty(rounds) {
+        let mut data_file = get_app_data_dir(GAME_APP_NAME);
+        data_file.push(SAVED_ROUNDS_FILE);
+        if let Err(e) = fs::write(&data_file, json.as_bytes()) {
+            error!("Failed to save rounds: {}", e);
+        }
+    }
+}
+
+/// 获取玩家保存的游戏，只有与玩家当前级别一致时才有效，无效的保存数据会被删除
+pub fn load_saved_round(player: &str, level: u32) -> Option<SavedRound> {
+    let round = load_saved_rounds().remove(player).filter(|round| round.level == level)?;
+    if !round.is_valid() {
+        error!("Discarding invalid saved round of player {}", player);
+        remove_saved_round(player);
+        return None;
+    }
+    Some(round)
+}
+
+pub fn remove_saved_round(player: &str) {
+    let mut rounds = load_saved_rounds();
+    if rounds.remove(player).is_some() {
+        save_saved_rounds(&rounds);
+    }
+}
+
+/// 中途退出时需要保存的飞行单元
+type SavedUnits<'w, 's> = Query<'w, 's, (
+    &'static FlyingUnit,
+    &'static Transform,
+    Option<&'static Aircraft>,
+    Option<&'static Armored>,
+    Option<&'static SpawnTime>,
+)>;
+
+pub fn on_save_round(
+    _: On<SaveRoundEvent>,
+    game_player: Res<GamePlayer>,
+    counter: Res<FlyingUnitCounter>,
+    speed_factor: Res<SpeedFactor>,
+    sentence: Option<Res<WarshipSentence>>,
+    checkpoint: Option<Res<CheckpointTimer>>,
+    aircraft_spawn: Res<AircraftSpawnState>,
+    equipment_spawn_states: Res<EquipmentSpawnStates>,
+    effects: Res<ActiveEffects>,
+    inventory: Res<Inventory>,
+    race: Res<GhostRace>,
+    units: SavedUnits,
+    game_time: Single<&GameTime>,
+    time: Res<Time>,
+) {
+    // 已经通关或者失败的游戏、课程练习以及命令行跳级的游戏不需要保存
+    if checkpoint.is_some() || game_player.health == 0 || game_player.mode == GameMode::Lesson || game_player.jumped {
+        return;
+    }
+
+    let mut warship = None;
+    let mut saved_units = Vec::new();
+    for (unit, transform, aircraft, armored, spawn_time) in &units {
+        if unit.kind == FlyingUnitKind::Warship {
+            if let Some(ref sentence) = sentence {
+                warship = Some(SavedWarship {
+                    sentence: sentence.sentence.clone(),
+                    current: sentence.current,
+                    x: transform.translation.x,
+                    speed: unit.speed,
+                });
+            }
+            continue;
+        }
+        saved_units.push(SavedUnit {
+            kind: unit.kind,
+            sprite: aircraft.map(|a| a.sprite).unwrap_or_default(),
+            enemy: aircraft.map(|a| a.kind).unwrap_or_default(),
+            armor: armored.map(|a| a.letters.clone()).unwrap_or_default(),
+            route: unit.route,
+            letter: unit.letter,
+            speed: unit.speed,
+            x: transform.translation.x,
+            y: transform.translation.y,
+            age: spawn_time.map_or(0., |t| time.elapsed_secs() - t.0),
+        });
+    }
+
+    let round = SavedRound {
+        level: game_player.player.level,
+        health: game_player.health,
+        game_secs: time.elapsed_secs_f64() - game_time.start_time,
+        counter: counter.clone(),
+        speed_factor: speed_factor.clone(),
+        units: saved_units,
+        warship,
+        aircraft_spawn: SavedSpawnState {
+            remaining: aircraft_spawn.timer.remaining_secs(),
+            count: aircraft_spawn.count,
+            spawn: true,
+        },
+        equipment_spawn: equipment_spawn_states.0.iter()
+            .map(|(kind, state)| (*kind, SavedSpawnState::from_state(state)))
+            .collect(),
+        effects: effects.timers.keys().map(|kind| (*kind, effects.remaining(*kind))).collect(),
+        revives: effects.revives,
+        inventory: inventory.0.clone(),
+        ghost: race.current.clone(),
+    };
+
+    let mut rounds = load_saved_rounds();
+    rounds.insert(game_player.player.name.clone(), round);
+    save_saved_rounds(&rounds);
+}
+
+/// 进入游戏后恢复保存的飞行单元、生命值、速度以及装备效果
+pub fn restore_round(
+    mut commands: Commands,
+    resume: Res<ResumeRound>,
+    mut game_routes: ResMut<GameRoutes>,
+    mut game_letters: ResMut<GameLetters>,
+    mut game_player: ResMut<GamePlayer>,
+    mut effects: ResMut<ActiveEffects>,
+    mut aircraft_spawn: ResMut<AircraftSpawnState>,
+    fighter_jet: Single<&Transform, With<FighterJet>>,
+    settings: Res<GameSettings>,
+    style: LetterStyle,
+    assets: ThemeAssets,
+    ui_scale: Res<UiScale>,
+    time: Res<Time>,
+    window: Single<&Window>,
+) {
+    let round = &resume.0;
+    commands.remove_resource::<ResumeRound>();
+    commands.insert_resource(round.speed_factor.clone());
+    game_player.health = round.health;
+
+    let mut rng = rand::rng();
+    // 已经恢复的飞行单元的横坐标，用于为航道已经不存在的飞行单元另外分配航道
+    let mut restored_x: HashMap<Entity, f32> = HashMap::new();
+    for unit in &round.units {
+        // 窗口比保存时小，原来的航道已经不存在时换一条航道
+        let mut y = unit.y;
+        let route = match game_routes.occupy(unit.route) {
+            Some(route) => Some(route),
+            None => {
+                let gap = |e: &Entity| restored_x.get(e).map_or(f32::INFINITY, |x| (x - unit.x).abs());
+                let route = game_routes.allocate(&mut rng, MIN_ROUTE_SPACING, gap);
+                if let Some(ref route) = route {
+                    y = route.position(routes_top(window.height(), ui_scale.0));
+                }
+                route
+            }
+        };
+        let Some(route) = route else {
+            // 放不下的敌机不恢复，让它重新生成，否则永远等不到战舰出现
+            if unit.kind == FlyingUnitKind::Aircraft && unit.enemy != EnemyKind::Fragment {
+                aircraft_spawn.count = aircraft_spawn.count.saturating_sub(1);
+            }
+            continue;
+        };
+        game_letters.take_letter(unit.letter);
+        for letter in &unit.armor {
+            game_letters.take_letter(*letter);
+        }
+        let flying_unit = FlyingUnit {
+            route: route.id,
+            letter: unit.letter,
+            speed: unit.speed,
+            kind: unit.kind,
+        };
+        let translation = Vec3::new(unit.x, y, 0.);
+        let id = match unit.kind {
+            FlyingUnitKind::Aircraft => spawn_aircraft_unit(
+                &mut commands, &assets, &style, unit.sprite.max(1), unit.enemy, unit.armor.clone(),
+                translation, flying_unit),
+            kind => {
+                let Some(info) = equipment_info(kind) else {
+                    continue;
+                };
+                spawn_equipment_unit(&mut commands, &assets, info, &style, translation, flying_unit)
+            }
+        };
+        // 保留飞行单元已经出现的时间，反应时间不从恢复时重新计算
+        commands.entity(id).insert(SpawnTime(time.elapsed_secs() - unit.age));
+        route.units.push(id);
+        restored_x.insert(id, unit.x);
+    }
+
+    if let Some(ref warship) = round.warship {
+        spawn_warship(&mut commands, &assets, &style, &window,
+                      &warship.sentence, warship.current, warship.x, warship.speed);
+    }
+
+    effects.revives = round.revives;
+    for (kind, remaining) in &round.effects {
+        if *remaining <= 0. {
+            continue;
+        }
+        effects.activate(*kind, *remaining);
+        // 护盾需要继续显示防护效果
+        if *kind == FlyingUnitKind::Shield {
+            spawn_effect_visual(&mut commands, &assets, *kind, fighter_jet.translation,
+                                *remaining, settings.shield_active_time);
+        }
+    }
+}
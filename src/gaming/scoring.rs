@@ -0,0 +1,102 @@
+use bevy::prelude::*;
+use crate::{GameFonts, GameMode, GamePlayer, GameSettings, GameState};
+use crate::gaming::common::*;
+use crate::accessibility::Accessibility;
+use crate::settings::UserSettings;
+use xtyping_core::scoring::penalty;
+
+const SCORE_POPUP_COLOR: Color = Color::srgb_u8(255, 215, 64);
+const PENALTY_POPUP_COLOR: Color = Color::srgb_u8(255, 100, 100);
+pub const STREAK_TEXT_COLOR: Color = Color::srgb_u8(255, 215, 64);
+
+/// 得分后飘起并逐渐消失的分数
+#[derive(Component)]
+pub struct ScorePopup(pub Timer);
+
+fn spawn_score_popup(commands: &mut Commands, game_fonts: &GameFonts, text: String, color: Color, translation: Vec3) {
+    commands.spawn((
+        DespawnOnExit(GameState::Gaming),
+        Text2d::new(text),
+        TextFont {
+            font: game_fonts.normal_font.clone(),
+            font_size: 20.,
+            ..default()
+        },
+        TextColor(color),
+        Transform::from_translation(translation.with_z(2.)),
+        ScorePopup(Timer::from_seconds(0.8, TimerMode::Once)),
+    ));
+}
+
+/// 连击次数加一，按照连击倍数和装备效果计算得分
+pub fn on_score(
+    event: On<ScoreEvent>,
+    mut commands: Commands,
+    mut streak: ResMut<ScoreStreak>,
+    mut player: ResMut<GamePlayer>,
+    effects: Res<ActiveEffects>,
+    settings: Res<GameSettings>,
+    game_fonts: Res<GameFonts>,
+) {
+    let (points, multiplier) = streak.hit(&settings.scoring, event.base, effects.score_multiplier());
+    // 课程进度与闯关分开记录，练习课程不改变闯关的分数
+    if points == 0 || player.mode == GameMode::Lesson {
+        return;
+    }
+    player.player.score += points;
+
+    let text = if multiplier > 1 { format!("+{} x{}", points, multiplier) } else { format!("+{}", points) };
+    spawn_score_popup(&mut commands, &game_fonts, text, SCORE_POPUP_COLOR, event.translation);
+}
+
+/// 连击中断，按错键时还可能扣分
+pub fn on_streak_broken(
+    event: On<StreakBrokenEvent>,
+    mut commands: Commands,
+    mut streak: ResMut<ScoreStreak>,
+    mut player: ResMut<GamePlayer>,
+    settings: Res<UserSettings>,
+    game_fonts: Res<GameFonts>,
+    fighter_jet: Single<&Transform, With<FighterJet>>,
+) {
+    // 没有连击时不修改资源，避免触发界面更新
+    if streak.hits > 0 {
+        streak.reset();
+    }
+    if player.mode == GameMode::Lesson {
+        return;
+    }
+    let penalty = penalty(settings.wrong_key_penalty, player.player.score);
+    if event.wrong_key && penalty > 0 {
+        player.player.score -= penalty;
+        spawn_score_popup(&mut commands, &game_fonts, format!("-{}", penalty), PENALTY_POPUP_COLOR,
+                          fighter_jet.translation + Vec3::new(0., 40., 0.));
+    }
+}
+
+pub fn animate_score_popups(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut TextColor, &mut Transform, &mut ScorePopup)>,
+    accessibility: Res<Accessibility>,
+    time: Res<Time>,
+) {
+    for (entity, mut color, mut transform, mut popup) in &mut query {
+        if popup.0.tick(time.delta()).is_finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        color.set_alpha(popup.0.fraction_remaining());
+        if !accessibility.reduced_motion {
+            transform.translation.y += time.delta_secs() * 40.;
+        }
+    }
+}
+
+pub fn update_streak_text(
+    streak: Res<ScoreStreak>,
+    settings: Res<GameSettings>,
+    mut text: Single<&mut Text, With<StreakText>>,
+) {
+    let multiplier = streak.multiplier(&settings.scoring);
+    **text = Text::new(if multiplier > 1 { format!("x{} ", multiplier) } else { String::new() });
+}
@@ -1,36 +1,67 @@
+use std::collections::HashMap;
 use rand::Rng;
-use bevy::asset::AssetServer;
+use rand::seq::IndexedRandom;
 use bevy::color::Color;
 use bevy::math::Vec3;
 use bevy::prelude::*;
-use crate::{GamePlayer, GameRoutes, GameLetters, GameSettings, GameFonts, Route, GameState};
+use crate::{routes_top, GameRoutes, GameLetters, GameState};
 use crate::gaming::common::*;
+use crate::theme::{ThemeAsset, ThemeAssets};
+use crate::accessibility::LetterStyle;
 
-fn random_route<'a>(game_data: &'a mut GameRoutes, rng: &mut impl Rng) -> &'a mut Route {
-    if game_data.empty_routes.is_empty() {
-        let index = rng.random_range(0..game_data.used_routes.len());
-        &mut game_data.used_routes[index]
-    } else {
-        let index = rng.random_range(0..game_data.empty_routes.len());
-        let route = game_data.empty_routes.swap_remove(index);
-        let index = game_data.used_routes.len();
-        game_data.used_routes.push(route.clone());
-        &mut game_data.used_routes[index]
-    }
-}
+const EQUIPMENT_RECHECK_SECS: f32 = 10.;
+// 所有航道都太拥挤时，等待一会儿再尝试生成飞行单元
+const ROUTE_RETRY_SECS: f32 = 0.2;
 
-fn random_letter(game_data: &mut GameLetters, rng: &mut impl Rng) -> char {
-    if game_data.candidate_letters.is_empty() {
-        let index = rng.random_range(0..game_data.choosed_letters.len());
-        game_data.choosed_letters[index]
-    } else {
-        let index = rng.random_range(0..game_data.candidate_letters.len());
-        let letter = game_data.candidate_letters.swap_remove(index);
-        if !game_data.choosed_letters.contains(&letter) {
-            game_data.choosed_letters.push(letter);
+/// 生成一架带有目标字符的敌机，装甲敌机的armor为当前字符之后还需要击中的字符
+pub fn spawn_aircraft_unit(
+    commands: &mut Commands,
+    assets: &ThemeAssets,
+    style: &LetterStyle,
+    sprite: i32,
+    kind: EnemyKind,
+    armor: Vec<char>,
+    translation: Vec3,
+    unit: FlyingUnit,
+) -> Entity {
+    let label: String = std::iter::once(unit.letter).chain(armor.iter().copied()).collect();
+    let texture = assets.load(ThemeAsset::aircraft(sprite));
+    let scale = kind.scale();
+    let mut entity = commands.spawn((
+        DespawnOnExit(GameState::Gaming),
+        Sprite {
+            image: texture,
+            image_mode: SpriteImageMode::Auto,
+            color: kind.tint(),
+            ..default()
+        },
+        Transform::from_translation(translation)
+            .with_scale(Vec3::splat(scale)),
+        unit,
+        Aircraft {
+            sprite,
+            kind,
+            ..default()
+        },
+        children![(
+            style.target_letter(label, TARGET_LETTER_SIZE / scale),
+            Transform::from_translation(Vec3::new(AIRCRAFT_SIZE/2.+style.accessibility.letter_size(TARGET_LETTER_SIZE)/2.+18., 0.0, 0.0)),
+        )]
+    ));
+    match kind {
+        EnemyKind::Armored if !armor.is_empty() => {
+            entity.insert(Armored { letters: armor });
+        }
+        EnemyKind::ZigZag => {
+            let mut rng = rand::rng();
+            entity.insert(ZigZag {
+                timer: Timer::from_seconds(rng.random_range(1.5..3.), TimerMode::Repeating),
+                target_y: translation.y,
+            });
         }
-        letter
+        _ => {}
     }
+    entity.id()
 }
 
 #[derive(Resource, Default)]
@@ -44,198 +75,253 @@ pub fn spawn_aircraft(
     mut state: ResMut<AircraftSpawnState>,
     mut game_routes: ResMut<GameRoutes>,
     mut game_letters: ResMut<GameLetters>,
-    game_player: Res<GamePlayer>,
+    units: Query<&Transform, With<FlyingUnit>>,
     speed_factor: Res<SpeedFactor>,
     time: Res<Time>,
-    asset_server: Res<AssetServer>,
-    game_settings: Res<GameSettings>,
-    game_fonts: Res<GameFonts>,
+    assets: ThemeAssets,
+    active_level: Res<ActiveLevel>,
+    style: LetterStyle,
+    ui_scale: Res<UiScale>,
     window: Single<&Window>
 ) {
     if state.timer.tick(time.delta()).just_finished() {
         // 达到了创建新敌机的时间
-        let level = game_player.player.level as usize;
-        // 随机选择一个敌机将要使用的航道
+        let level = &active_level.0;
+        // 随机选择一个敌机将要使用的航道，没有空闲的航道时稍后再试
         let mut rng = rand::rng();
-        let route = random_route(&mut game_routes, &mut rng);
-        let letter = random_letter(&mut game_letters, &mut rng);
+        let x = window.width() / 2.;
+        let gap = |e: &Entity| units.get(*e).map_or(f32::INFINITY, |t| x - t.translation.x);
+        let Some(route) = game_routes.allocate(&mut rng, MIN_ROUTE_SPACING, gap) else {
+            state.timer = Timer::from_seconds(ROUTE_RETRY_SECS, TimerMode::Once);
+            return;
+        };
+        let letter = game_letters.random_letter(&mut rng);
+        // 按照当前级别的权重选择敌机种类
+        let kind = level.enemy_weights
+            .choose_weighted(&mut rng, |(_, weight)| *weight)
+            .map(|(kind, _)| *kind)
+            .unwrap_or_default();
+        let (sprite, armor, speed_ratio) = match kind {
+            EnemyKind::Armored => {
+                let count = rng.random_range(1..=2);
+                (3, (0..count).map(|_| game_letters.random_letter(&mut rng)).collect(), 0.8)
+            }
+            EnemyKind::ZigZag => (2, Vec::new(), 1.),
+            EnemyKind::Scout => (1, Vec::new(), 1.6),
+            _ => (rng.random_range(1..=AIRCRAFT_KIND), Vec::new(), 1.),
+        };
         // 生成敌机
-        let kind = rng.random_range(1..=AIRCRAFT_KIND);
-        let texture = asset_server.load(format!("images/aircraft_{}.png", kind));
-        let speed = game_settings.level_speeds[level - 1];
-        let id = commands.spawn((
-            DespawnOnExit(GameState::Gaming),
-            Sprite {
-                image: texture.clone(),
-                image_mode: SpriteImageMode::Auto,
-                color: Color::WHITE,
-                ..default()
-            },
-            Transform::from_translation(Vec3::new(window.width()/2., route.get_position(window.height()), 0.))
-                .with_scale(Vec3::splat(FIGHTER_JET_SCALE*0.6)),
+        let speed = level.speed;
+        let id = spawn_aircraft_unit(
+            &mut commands,
+            &assets,
+            &style,
+            sprite,
+            kind,
+            armor,
+            Vec3::new(x, route.position(routes_top(window.height(), ui_scale.0)), 0.),
             FlyingUnit {
                 route: route.id,
                 letter,
-                speed: rng.random_range(speed.0..=speed.1) * speed_factor.speed_factor,
+                speed: rng.random_range(speed.0..=speed.1) * speed_factor.speed_factor * speed_ratio,
                 kind: FlyingUnitKind::Aircraft
-            },
-            Aircraft::default(),
-            children![(
-                Text2d::new(letter),
-                TextFont {
-                    font: game_fonts.letter_font.clone(),
-                    font_size: TARGET_LETTER_SIZE / (FIGHTER_JET_SCALE * 0.6),
-                    ..Default::default()
-                },
-                TextColor(TARGET_LETTER_COLOR),
-                Transform::from_translation(Vec3::new(AIRCRAFT_SIZE/2.+TARGET_LETTER_SIZE/2.+18., 0.0, 0.0)),
-            )]
-        )).id();
-        route.entities.push(id);
+            });
+        route.units.push(id);
         state.count += 1;
 
-        // 重置到新的随机时间
-        if state.count < game_settings.aircraft_count[game_player.player.level as usize - 1] {
-            let range = game_settings.aircraft_intervals[level - 1];
+        // 重置到新的随机时间，新的一波开始前还需要额外等待
+        if let Some((wave, first)) = level.wave_at(state.count) {
+            let range = wave.interval;
+            let pause = if first { wave.pause } else { 0. };
             let next_duration = rng.random_range(range.0..=range.1);
             state.timer = Timer::from_seconds(
-                next_duration/1.15_f32.powi(speed_factor.factor_changes),
+                next_duration * speed_factor.interval_factor + pause,
                 TimerMode::Once
             );
         }
     }
 }
 
-#[derive(Default)]
-pub struct SpawnState {
-    pub timer: Timer,
-    pub count: usize,
-    pub spawn: bool,
-    pub texture: String,
-    pub speeds: Vec<(f32, f32)>,
-    pub intervals: Vec<(f32, f32)>,
-}
-
-#[derive(Resource)]
-pub struct BombSpawnState(pub SpawnState);
-
-impl Default for BombSpawnState {
-    fn default() -> Self {
-        BombSpawnState(SpawnState {
-            texture: "images/bomb.png".into(),
-            ..default()
-        })
+/// 分裂敌机被击中后，在相邻的两个航道上各生成一架小敌机，航道上离得太近的位置已经有飞行单元时不生成
+pub fn on_aircraft_split(
+    event: On<AircraftSplitEvent>,
+    mut commands: Commands,
+    mut game_routes: ResMut<GameRoutes>,
+    mut game_letters: ResMut<GameLetters>,
+    units: Query<&Transform, With<FlyingUnit>>,
+    assets: ThemeAssets,
+    style: LetterStyle,
+    ui_scale: Res<UiScale>,
+    window: Single<&Window>,
+) {
+    let mut rng = rand::rng();
+    let route_count = game_routes.len() as i32;
+    // 分裂到两侧相邻的航道，靠边时改用向内的下一个航道，保证碎片不在原航道且互不重叠
+    let lanes = [-1, 1, -2, 2].into_iter()
+        .map(|step| event.route + step)
+        .filter(|id| (0..route_count).contains(id))
+        .take(2);
+    for id in lanes {
+        let x = event.translation.x;
+        let gap = |e: &Entity| units.get(*e).map_or(f32::INFINITY, |t| (t.translation.x - x).abs());
+        if !game_routes.is_clear(id, MIN_ROUTE_SPACING, gap) {
+            continue;
+        }
+        let Some(route) = game_routes.occupy(id) else {
+            continue;
+        };
+        let letter = game_letters.random_letter(&mut rng);
+        let unit = spawn_aircraft_unit(
+            &mut commands,
+            &assets,
+            &style,
+            rng.random_range(1..=AIRCRAFT_KIND),
+            EnemyKind::Fragment,
+            Vec::new(),
+            Vec3::new(event.translation.x, route.position(routes_top(window.height(), ui_scale.0)), 0.),
+            FlyingUnit {
+                route: id,
+                letter,
+                speed: event.speed,
+                kind: FlyingUnitKind::Aircraft
+            });
+        route.units.push(unit);
     }
 }
 
-impl AsMut<SpawnState> for BombSpawnState {
-    fn as_mut(&mut self) -> &mut SpawnState {
-        &mut self.0
-    }
+/// 一种会随机出现的装备的图片及显示大小
+pub struct EquipmentInfo {
+    pub kind: FlyingUnitKind,
+    pub texture: ThemeAsset,
+    pub size: Vec2,
 }
 
-#[derive(Resource)]
-pub struct ShieldSpawnState(pub SpawnState);
+/// 所有会随机出现的装备，增加新的装备只需要在这里加一项，并在关卡中设置它出现的时间间隔
+pub const EQUIPMENT: [EquipmentInfo; 7] = [
+    EquipmentInfo { kind: FlyingUnitKind::Bomb, texture: ThemeAsset::Bomb, size: Vec2::splat(EQUIPMENT_SIZE) },
+    EquipmentInfo { kind: FlyingUnitKind::Shield, texture: ThemeAsset::Shield, size: Vec2::splat(EQUIPMENT_SIZE) },
+    EquipmentInfo { kind: FlyingUnitKind::HealthPack, texture: ThemeAsset::HealthPack, size: Vec2::splat(EQUIPMENT_SIZE) },
+    EquipmentInfo { kind: FlyingUnitKind::SlowMotion, texture: ThemeAsset::SlowMotion, size: Vec2::splat(EQUIPMENT_SIZE) },
+    EquipmentInfo { kind: FlyingUnitKind::DoubleScore, texture: ThemeAsset::DoubleScore, size: Vec2::splat(EQUIPMENT_SIZE) },
+    EquipmentInfo { kind: FlyingUnitKind::AutoAim, texture: ThemeAsset::AutoAim, size: Vec2::new(EQUIPMENT_SIZE, EQUIPMENT_SIZE / 2.) },
+    EquipmentInfo { kind: FlyingUnitKind::ExtraLife, texture: ThemeAsset::ExtraLife, size: Vec2::splat(EQUIPMENT_SIZE) },
+];
 
-impl Default for ShieldSpawnState {
-    fn default() -> Self {
-        ShieldSpawnState(SpawnState {
-            texture: "images/shield.png".into(),
-            ..default()
-        })
-    }
+/// 装备种类对应的设置，敌机和战舰返回None
+pub fn equipment_info(kind: FlyingUnitKind) -> Option<&'static EquipmentInfo> {
+    EQUIPMENT.iter().find(|info| info.kind == kind)
 }
 
-impl AsMut<SpawnState> for ShieldSpawnState {
-    fn as_mut(&mut self) -> &mut SpawnState {
-        &mut self.0
-    }
+#[derive(Default)]
+pub struct SpawnState {
+    pub timer: Timer,
+    pub count: usize,
+    pub spawn: bool,
 }
 
-#[derive(Resource)]
-pub struct HealthPackSpawnState(pub SpawnState);
+/// 所有装备的生成状态
+#[derive(Resource, Default)]
+pub struct EquipmentSpawnStates(pub HashMap<FlyingUnitKind, SpawnState>);
 
-impl Default for HealthPackSpawnState {
-    fn default() -> Self {
-        HealthPackSpawnState(SpawnState {
-            texture: "images/first-aid-kit.png".into(),
-            ..default()
-        })
+impl EquipmentSpawnStates {
+    /// 开始新的一局时重置所有装备的生成参数
+    pub fn reset(&mut self) {
+        self.0 = EQUIPMENT.iter().map(|info| (info.kind, SpawnState::default())).collect();
     }
 }
 
-impl AsMut<SpawnState> for HealthPackSpawnState {
-    fn as_mut(&mut self) -> &mut SpawnState {
-        &mut self.0
-    }
+/// 生成一个带有目标字符的装备
+pub fn spawn_equipment_unit(
+    commands: &mut Commands,
+    assets: &ThemeAssets,
+    info: &EquipmentInfo,
+    style: &LetterStyle,
+    translation: Vec3,
+    unit: FlyingUnit,
+) -> Entity {
+    let letter = unit.letter;
+    commands.spawn((
+        DespawnOnExit(GameState::Gaming),
+        Sprite {
+            image: assets.load(info.texture),
+            image_mode: SpriteImageMode::Auto,
+            color: Color::WHITE,
+            custom_size: Some(info.size),
+            ..default()
+        },
+        Transform::from_translation(translation)
+            .with_scale(Vec3::splat(FIGHTER_JET_SCALE * 0.6)),
+        unit,
+        children![(
+            style.target_letter(letter.to_string(), TARGET_LETTER_SIZE / (FIGHTER_JET_SCALE * 0.6)),
+            Transform::from_translation(Vec3::new(0., -30., 0.)).with_scale(Vec3::splat(0.8)),
+        )]
+    )).id()
 }
 
-pub fn spawn_equipment<Marker: Default+Component+FlyingUnitTrait>(
+pub fn spawn_equipment(
     mut commands: Commands,
-    mut spawn_state: ResMut<Marker::SpawnState>,
+    mut spawn_states: ResMut<EquipmentSpawnStates>,
     mut game_routes: ResMut<GameRoutes>,
     mut game_letters: ResMut<GameLetters>,
+    units: Query<&Transform, With<FlyingUnit>>,
     sentence: Option<Res<WarshipSentence>>,
-    game_player: Res<GamePlayer>,
+    active_level: Res<ActiveLevel>,
     time: Res<Time>,
-    asset_server: Res<AssetServer>,
-    game_fonts: Res<GameFonts>,
+    assets: ThemeAssets,
+    style: LetterStyle,
+    ui_scale: Res<UiScale>,
     window: Single<&Window>
-)
-where
-    Marker::SpawnState: AsMut<SpawnState> + Resource
-{
+) {
     if sentence.is_some() {
         return;
     }
 
-    let state = spawn_state.as_mut().as_mut();
-    if state.timer.tick(time.delta()).just_finished() {
+    let mut rng = rand::rng();
+    let level = &active_level.0;
+    for info in &EQUIPMENT {
+        let Some(state) = spawn_states.0.get_mut(&info.kind) else {
+            continue;
+        };
+        if !state.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
         // 达到了创建的时间
-        let mut rng = rand::rng();
-        let level = game_player.player.level as usize;
+        // 当前关卡没有设置的装备不会出现，过一段时间再检查（玩家可能已经升级）
+        let Some(range) = level.equipment_intervals.get(&info.kind).copied() else {
+            state.spawn = false;
+            state.timer = Timer::from_seconds(EQUIPMENT_RECHECK_SECS, TimerMode::Once);
+            continue;
+        };
         if state.spawn {
-            // 随机选择一个将要使用的航道
-            let route = random_route(&mut game_routes, &mut rng);
-            let letter = random_letter(&mut game_letters, &mut rng);
+            // 随机选择一个将要使用的航道，没有空闲的航道时稍后再试
+            let x = window.width() / 2.;
+            let gap = |e: &Entity| units.get(*e).map_or(f32::INFINITY, |t| x - t.translation.x);
+            let Some(route) = game_routes.allocate(&mut rng, MIN_ROUTE_SPACING, gap) else {
+                state.timer = Timer::from_seconds(ROUTE_RETRY_SECS, TimerMode::Once);
+                continue;
+            };
+            let letter = game_letters.random_letter(&mut rng);
             // 生成装备
-            let texture = asset_server.load(state.texture.clone());
-            let speed = state.speeds[level - 1];
-            let id = commands.spawn((
-                DespawnOnExit(GameState::Gaming),
-                Sprite {
-                    image: texture.clone(),
-                    image_mode: SpriteImageMode::Auto,
-                    color: Color::WHITE,
-                    ..default()
-                },
-                Transform::from_translation(Vec3::new(window.width() / 2., route.get_position(window.height()), 0.))
-                    .with_scale(Vec3::splat(FIGHTER_JET_SCALE * 0.6)),
+            let speed = level.speed;
+            let id = spawn_equipment_unit(
+                &mut commands,
+                &assets,
+                info,
+                &style,
+                Vec3::new(x, route.position(routes_top(window.height(), ui_scale.0)), 0.),
                 FlyingUnit {
                     route: route.id,
                     letter,
                     speed: rng.random_range(speed.0..=speed.1),
-                    kind: Marker::kind(),
-                },
-                Marker::default(),
-                children![(
-                    Text2d::new(letter),
-                    TextFont {
-                        font: game_fonts.letter_font.clone(),
-                        font_size: TARGET_LETTER_SIZE / (FIGHTER_JET_SCALE * 0.6),
-                        ..Default::default()
-                    },
-                    TextColor(TARGET_LETTER_COLOR),
-                    Transform::from_translation(Vec3::new(0., -30., 0.)).with_scale(Vec3::splat(0.8)),
-                )]
-            )).id();
-            route.entities.push(id);
+                    kind: info.kind,
+                });
+            route.units.push(id);
             state.count += 1;
         }
         state.spawn = true;
 
         // 重置到新的随机时间
-        let range = state.intervals[level - 1];
         let next_duration = rng.random_range(range.0..=range.1);
         state.timer = Timer::from_seconds(next_duration, TimerMode::Once);
     }
@@ -243,11 +329,10 @@ where
 
 pub fn launch_space_warship(
     mut commands: Commands,
-    game_settings: Res<GameSettings>,
-    player: Res<GamePlayer>,
+    active_level: Res<ActiveLevel>,
     counter: Res<FlyingUnitCounter>
 ) {
-    let total = game_settings.aircraft_count[player.player.level as usize - 1];
+    let total = active_level.0.aircraft_count();
     if counter.destroyed + counter.missed >= total {
         commands.insert_resource(SpaceWarshipTimer(Timer::from_seconds(1., TimerMode::Once)))
     }
@@ -258,10 +343,9 @@ pub fn spawn_space_warship(
     mut timer: ResMut<SpaceWarshipTimer>,
     flying_unit: Query<Entity, With<FlyingUnit>>,
     time: Res<Time>,
-    player: Res<GamePlayer>,
-    settings: Res<GameSettings>,
-    game_fonts: Res<GameFonts>,
-    assets: Res<AssetServer>,
+    active_level: Res<ActiveLevel>,
+    style: LetterStyle,
+    assets: ThemeAssets,
     window: Single<&Window>
 ) {
     if timer.0.tick(time.delta()).is_finished() {
@@ -272,113 +356,163 @@ pub fn spawn_space_warship(
         }
 
         let mut rng = rand::rng();
-        let level_index = player.player.level as usize - 1;
-        let sentence_index = rng.random_range(0..settings.level_sentences[level_index].len());
-        let sentence_chars: Vec<_>= settings.level_sentences[level_index][sentence_index].chars().collect();
-        let letter_count = sentence_chars.len() as f32;
-        let mut index = 0_usize;
-        let mut letters = sentence_chars.clone();
-        letters.retain(|c| *c != ' ');
-        commands.insert_resource(
-            WarshipSentence{
-                letters,
-                current: 0,
+        let level = &active_level.0;
+        let Some(sentence) = level.sentences.choose(&mut rng) else {
+            error!("No warship sentences for current level");
+            return;
+        };
+        let speed = level.speed.0 * 0.45;
+        spawn_warship(&mut commands, &assets, &style, &window, sentence, 0,
+                      (window.width() + WARSHIP_WIDTH) / 2. - 160., speed);
+    }
+}
+
+/// 计算Boss句子中每个字符（不含空格）的位置及字体大小，
+/// 句子太长时先缩小字体，缩小到最小字体仍然放不下时按单词换行
+pub fn layout_warship_sentence(sentence: &str, window: &Window) -> (f32, Vec<Vec2>) {
+    let available = window.width() - CHECKPOINT_SENTENCE_MARGIN * 2.;
+    let count = sentence.chars().count().max(1) as f32;
+    let size = (available / (count * CHECKPOINT_FONT_RATIO)).clamp(CHECKPOINT_LETTER_MIN_SIZE, CHECKPOINT_LETTER_SIZE);
+    let advance = size * CHECKPOINT_FONT_RATIO;
+    let max_chars = ((available / advance) as usize).max(1);
+
+    let mut lines: Vec<Vec<char>> = vec![Vec::new()];
+    for word in sentence.split(' ') {
+        let line = lines.last_mut().unwrap();
+        let word_len = word.chars().count();
+        if !line.is_empty() && line.len() + 1 + word_len > max_chars {
+            lines.push(word.chars().collect());
+        } else {
+            if !line.is_empty() {
+                line.push(' ');
             }
-        );
+            line.extend(word.chars());
+        }
+    }
 
-        let font_ratio = 0.65;
-        let start_x = -(letter_count * CHECKPOINT_LETTER_SIZE * font_ratio / 2.);
-        let start_y = -window.height() / 2. + CHECKPOINT_LETTER_SIZE / 2. + 5.;
-        let mut x = start_x;
-        for letter in &sentence_chars {
+    let bottom = -window.height() / 2. + size / 2. + 5.;
+    let mut positions = Vec::new();
+    for (row, line) in lines.iter().enumerate() {
+        let y = bottom + (lines.len() - 1 - row) as f32 * size * 1.1;
+        let start_x = -(line.len() as f32 * advance / 2.);
+        for (i, letter) in line.iter().enumerate() {
             if *letter != ' ' {
-                commands.spawn((
-                    DespawnOnExit(GameState::Gaming),
-                    Text2d::new(letter.to_string()),
-                    TextFont {
-                        font: game_fonts.letter_font.clone(),
-                        font_size: CHECKPOINT_LETTER_SIZE,
-                        ..Default::default()
-                    },
-                    TextColor(if index == 0 { CHECKPOINT_LETTER_TARGET } else { CHECKPOINT_LETTER_WAITING }),
-                    Transform::from_translation(
-                        Vec3 {
-                            x,
-                            y: start_y,
-                            z: 1.
-                        }),
-                    WarshipLetter(index),
-                ));
-                index += 1;
+                positions.push(Vec2::new(start_x + i as f32 * advance, y));
             }
-            x += CHECKPOINT_LETTER_SIZE * font_ratio;
         }
-        commands.spawn((
-            DespawnOnExit(GameState::Gaming),
-            Sprite {
-                image: assets.load("images/down-arrow.png"),
-                image_mode: SpriteImageMode::Auto,
-                color: Color::WHITE,
-                ..default()
-            },
-            Transform::from_translation(Vec3::new(
-                start_x,
-                start_y + CHECKPOINT_LETTER_SIZE/2. + 15.,
-                0.)).with_scale(Vec3::splat(0.6)
-            ),
-            WarshipLetterArrow
-        ));
+    }
+    (size, positions)
+}
+
+/// 生成关卡boss战舰及其底部需要输入的句子，current之前的字符视为已经击毁
+pub fn spawn_warship(
+    commands: &mut Commands,
+    assets: &ThemeAssets,
+    style: &LetterStyle,
+    window: &Window,
+    sentence: &str,
+    current: usize,
+    x: f32,
+    speed: f32,
+) {
+    let mut letters: Vec<_> = sentence.chars().collect();
+    letters.retain(|c| *c != ' ');
+    let target = letters[current];
+    let (font_size, positions) = layout_warship_sentence(sentence, window);
+    commands.insert_resource(
+        WarshipSentence{
+            sentence: sentence.to_owned(),
+            letters: letters.clone(),
+            current,
+        }
+    );
 
-        // 加载关卡boss
-        let half_window = window.width() / 2.;
-        let speed = settings.level_speeds[level_index].0 * 0.45;
-        let texture = assets.load("images/space-warship.png");
+    for (index, (letter, pos)) in letters.iter().zip(&positions).enumerate() {
+        let color = if index < current {
+            style.accessibility.checkpoint_destroyed()
+        } else if index == current {
+            style.accessibility.checkpoint_target()
+        } else {
+            style.accessibility.checkpoint_waiting()
+        };
         commands.spawn((
             DespawnOnExit(GameState::Gaming),
-            Sprite {
-                image: texture.clone(),
-                image_mode: SpriteImageMode::Auto,
-                color: Color::WHITE,
-                ..default()
+            Text2d::new(letter.to_string()),
+            TextFont {
+                font: style.fonts.letter_font.clone(),
+                font_size,
+                ..Default::default()
             },
-            Transform::from_translation(Vec3::new((window.width() + WARSHIP_WIDTH) / 2. - 160., 0., 0.)),
-            FlyingUnit {
-                route: 0,
-                letter: sentence_chars[0],
-                speed,
-                kind: FlyingUnitKind::Warship
-            },
-            SpaceWarship {
-                timer: Timer::from_seconds(1., TimerMode::Repeating),
-                fired: false,
-                gun_count: 0,
-                gun_state: [false; 12],
-                gun_pos: [
-                    Vec2::new(224. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 104.),
-                    Vec2::new(224. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 260.),
-                    Vec2::new(456. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 50.),
-                    Vec2::new(456. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 315.),
-                    Vec2::new(624. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 17.),
-                    Vec2::new(624. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 349.),
-                    Vec2::new(870. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 133.),
-                    Vec2::new(870. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 230.),
-                    Vec2::new(870. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 144.),
-                    Vec2::new(870. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 219.),
-                    Vec2::new(939. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 80.),
-                    Vec2::new(939. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 286.)
-                ],
-                gun_dist: [
-                    half_window + WARSHIP_WIDTH / 2. - 276.,
-                    half_window + WARSHIP_WIDTH / 2. - 510.,
-                    half_window + WARSHIP_WIDTH / 2. - 680.,
-                    half_window + WARSHIP_WIDTH / 2. - 928.,
-                    half_window + WARSHIP_WIDTH / 2. - 990.
-                ],
-                gun_fired: 0,
-                cannon: false,
-                cannon_pos: Vec2::new(661. - WARSHIP_WIDTH / 2., 0.),
-                cannon_dist: half_window + WARSHIP_WIDTH / 2. - 792.,
-            }
+            TextColor(color),
+            TextBackgroundColor(style.accessibility.letter_backplate()),
+            Transform::from_translation(pos.extend(1.)),
+            WarshipLetter(index),
         ));
     }
-}
\ No newline at end of file
+    commands.spawn((
+        DespawnOnExit(GameState::Gaming),
+        Sprite {
+            image: assets.asset_server.load("images/down-arrow.png"),
+            image_mode: SpriteImageMode::Auto,
+            color: Color::WHITE,
+            ..default()
+        },
+        Transform::from_translation(Vec3::new(
+            positions[current].x,
+            positions[current].y + font_size/2. + 15.,
+            0.)).with_scale(Vec3::splat(0.6)
+        ),
+        WarshipLetterArrow
+    ));
+
+    // 加载关卡boss
+    let half_window = window.width() / 2.;
+    let texture = assets.load(ThemeAsset::Warship);
+    commands.spawn((
+        DespawnOnExit(GameState::Gaming),
+        Sprite {
+            image: texture.clone(),
+            image_mode: SpriteImageMode::Auto,
+            color: Color::WHITE,
+            ..default()
+        },
+        Transform::from_translation(Vec3::new(x, 0., 0.)),
+        FlyingUnit {
+            route: 0,
+            letter: target,
+            speed,
+            kind: FlyingUnitKind::Warship
+        },
+        SpaceWarship {
+            timer: Timer::from_seconds(1., TimerMode::Repeating),
+            fired: false,
+            gun_count: 0,
+            gun_state: [false; 12],
+            gun_pos: [
+                Vec2::new(224. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 104.),
+                Vec2::new(224. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 260.),
+                Vec2::new(456. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 50.),
+                Vec2::new(456. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 315.),
+                Vec2::new(624. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 17.),
+                Vec2::new(624. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 349.),
+                Vec2::new(870. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 133.),
+                Vec2::new(870. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 230.),
+                Vec2::new(870. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 144.),
+                Vec2::new(870. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 219.),
+                Vec2::new(939. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 80.),
+                Vec2::new(939. - WARSHIP_WIDTH / 2., WARSHIP_HEIGHT / 2. - 286.)
+            ],
+            gun_dist: [
+                half_window + WARSHIP_WIDTH / 2. - 276.,
+                half_window + WARSHIP_WIDTH / 2. - 510.,
+                half_window + WARSHIP_WIDTH / 2. - 680.,
+                half_window + WARSHIP_WIDTH / 2. - 928.,
+                half_window + WARSHIP_WIDTH / 2. - 990.
+            ],
+            gun_fired: 0,
+            cannon: false,
+            cannon_pos: Vec2::new(661. - WARSHIP_WIDTH / 2., 0.),
+            cannon_dist: half_window + WARSHIP_WIDTH / 2. - 792.,
+        }
+    ));
+}
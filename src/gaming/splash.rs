@@ -2,12 +2,15 @@ use bevy::asset::AssetServer;
 use bevy::color::Color;
 use bevy::math::Vec2;
 use bevy::prelude::*;
-use crate::{GameFonts, PlayState};
+use crate::{GameFonts, GameMode, GamePlayer, GameSettings, PlayState};
 use crate::gaming::common::SplashTextRow;
+use crate::gaming::lesson::spaced_keys;
 use crate::ui::{spawn_image_node, spawn_info_text};
 
 pub fn game_splash_setup(mut commands: Commands,
                          asset_server: Res<AssetServer>,
+                         game_player: Res<GamePlayer>,
+                         settings: Res<GameSettings>,
                          fonts: Res<GameFonts>) {
     commands.spawn((
         DespawnOnExit(PlayState::Splash),
@@ -37,6 +40,26 @@ pub fn game_splash_setup(mut commands: Commands,
 
             let text_color = Color::srgba_u8(188, 190, 196, 0);
             let key_color = Color::srgba_u8(255, 100, 100, 0);
+            // 课程模式提示这一课新学的键
+            if game_player.mode == GameMode::Lesson {
+                let curriculum = &settings.curriculum;
+                let index = curriculum.lesson_index(&game_player.player);
+                let lesson = &curriculum.lessons[index];
+                builder.spawn((
+                    Node {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        margin: UiRect::all(Val::Px(5.0)),
+                        ..default()
+                    },
+                    SplashTextRow { timer: Timer::from_seconds(4.0, TimerMode::Once) }
+                ))
+                    .with_children(|builder| {
+                        spawn_info_text(builder, &format!("第{}课 {}，新学的键：", index + 1, lesson.name),
+                                        text_color, fonts.ui_font.clone(), 32.);
+                        spawn_info_text(builder, &spaced_keys(&lesson.keys), key_color, fonts.ui_font.clone(), 32.);
+                    });
+            }
             builder.spawn((
                 Node {
                     flex_direction: FlexDirection::Row,
@@ -72,7 +95,7 @@ pub fn game_splash_setup(mut commands: Commands,
 }
 
 pub fn fade_tip_messages(
-    time: Res<Time>,
+    time: Res<Time<Real>>,
     mut query: Query<(&mut SplashTextRow, &Children)>,
     mut text_query: Query<&mut TextColor>,
     mut image_query: Query<&mut ImageNode>,
@@ -0,0 +1,33 @@
+use bevy::prelude::*;
+use crate::GamePlayer;
+use crate::gaming::common::*;
+use crate::keystats::KeyStatsData;
+
+/// 累计玩家的练习数据，保存在玩家信息中用于生成成绩报告，同时按键记录用于键盘热力图
+pub fn on_stats_outcome(event: On<AircraftOutcomeEvent>, mut player: ResMut<GamePlayer>, mut keys: ResMut<KeyStatsData>) {
+    let key = keys.record(&player.player.name, event.letter);
+    if event.hit {
+        key.hits += 1;
+        key.reaction_secs += event.reaction;
+        player.player.stats.hits += 1;
+    } else {
+        key.misses += 1;
+        player.player.stats.misses += 1;
+    }
+}
+
+pub fn on_stats_wrong_key(event: On<StreakBrokenEvent>, mut player: ResMut<GamePlayer>, mut keys: ResMut<KeyStatsData>) {
+    if event.wrong_key {
+        keys.record(&player.player.name, event.letter).wrong += 1;
+        player.player.stats.wrong_keys += 1;
+    }
+}
+
+pub fn on_stats_correct_key(_event: On<CorrectKeyEvent>, mut player: ResMut<GamePlayer>) {
+    player.player.stats.correct_keys += 1;
+}
+
+/// 只累计真正在打字的时间，暂停和对话框显示时不计算
+pub fn track_practice_time(mut player: ResMut<GamePlayer>, time: Res<Time>) {
+    player.player.stats.practice_secs += time.delta_secs();
+}
@@ -1,14 +1,12 @@
 use bevy::asset::AssetServer;
-use bevy::color::Color;
-use bevy::math::Vec2;
 use bevy::prelude::*;
-use crate::{widgets, GameFonts, GamePlayer, PlayState};
+use crate::{GameFonts, GamePlayer, PlayState};
 use crate::gaming::common::LastPlayState;
-use crate::ui::{spawn_image_node, spawn_info_text};
-use crate::widgets::ModelDialog;
+use crate::widgets::{Dialog, DialogResult};
 
-#[derive(Component)]
-pub struct ButtonContinue;
+/// 升级对话框只有继续游戏一个选项
+#[derive(Clone, Copy)]
+pub struct UpgradeContinue;
 
 pub fn upgrading_setup(
     mut commands: Commands,
@@ -16,54 +14,21 @@ pub fn upgrading_setup(
     game_fonts: Res<GameFonts>,
     asset_server: Res<AssetServer>
 ) {
-    let dialog = ModelDialog::new(&mut commands, PlayState::Upgrading, 60.);
-    commands.entity(dialog.container).with_children(|builder| {
-        spawn_info_text(builder, "Level Upgraded!", Color::srgb_u8(135, 201, 22),
-                        game_fonts.normal_font.clone(), 48.);
-        builder.spawn(
-            Node {
-                width: Val::Percent(90.),
-                height: Val::Auto,
-                flex_direction: FlexDirection::Row,
-                align_items: AlignItems::Center,
-                justify_content: JustifyContent::Center,
-                margin: UiRect::all(Val::Px(20.0)),
-                ..default()
-            }).with_children(|builder| {
-            spawn_image_node(builder, &asset_server, "images/congratulations.png", Vec2::splat(96.0), 30., 0.);
-            spawn_info_text(builder, &format!("{}，祝贺你成功升为{}级！你太厉害啦！", game_player.player.name, game_player.player.level),
-                            Color::srgb_u8(188, 190, 196), game_fonts.ui_font.clone(), 28.);
-        });
-        builder.spawn(
-            Node {
-                width: Val::Percent(50.),
-                height: Val::Auto,
-                flex_direction: FlexDirection::Row,
-                align_items: AlignItems::Center,
-                justify_content: JustifyContent::Center,
-                margin: UiRect::all(Val::Px(10.0)),
-                ..default()
-            }).with_children(|builder| {
-            builder.spawn(
-                widgets::PushButton::new(ButtonContinue,
-                                         "继续游戏",
-                                         Vec2::new(200.0,40.0),
-                                         true,
-                                         UiRect::left(Val::Px(10.0))
-                ));
-        });
-    });
+    Dialog::new("Level Upgraded!",
+                &format!("{}，祝贺你成功升为{}级！你太厉害啦！", game_player.player.name, game_player.player.level))
+        .fonts(game_fonts.normal_font.clone(), game_fonts.ui_font.clone())
+        .image(asset_server.load("images/congratulations.png"))
+        .default_button("继续游戏", UpgradeContinue)
+        .spawn(&mut commands, PlayState::Upgrading);
 
     commands.insert_resource(LastPlayState(PlayState::Upgrading));
 }
 
 pub fn on_continue_game_button(
     mut next_state: ResMut<NextState<PlayState>>,
-    mut reader: MessageReader<widgets::ButtonClicked>,
-    query: Query<(), With<ButtonContinue>>,
+    mut reader: MessageReader<DialogResult<UpgradeContinue>>,
 ) {
-    if let Some(event) = reader.read().last()
-        && query.get(event.entity).is_ok() {
+    if reader.read().last().is_some() {
         next_state.set(PlayState::Playing);
     }
 }
@@ -0,0 +1,661 @@
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+use super::*;
+use ui::*;
+use widgets::TextConfig;
+use crate::playtime::{PlayTime, PlayTimeLimit};
+use crate::theme::{available_themes, theme_name};
+use crate::accessibility::Accessibility;
+use crate::classroom::{class_name, export_reports, import_roster, NO_CLASS_NAME};
+use crate::widgets::{blur_input_box, Dropdown, InputBox, InputBoxOptions, ModelDialog};
+
+// 玩家时间限制列表的可见高度，玩家较多时滚动显示
+const LIMITS_LIST_HEIGHT: f32 = 280.;
+const LIMITS_ROW_HEIGHT: f32 = 47.;
+
+pub fn guardian_plugin(app: &mut App) {
+    app
+        .add_systems(OnEnter(GameState::Guardian), guardian_setup)
+        .add_systems(OnExit(GameState::Guardian), guardian_exit)
+        .add_systems(Update, (on_confirm_button,
+                              on_difficulty_toggle,
+                              on_theme_toggle,
+                              on_accessibility_button,
+                              on_accessibility_option,
+                              on_accessibility_done,
+                              on_save_button,
+                              on_import_roster_button,
+                              on_export_reports_button,
+                              on_class_filter,
+                              on_limits_scroll,
+                              on_back_button).run_if(in_state(GameState::Guardian)));
+}
+
+#[derive(Component, Default)]
+struct GuardianEntity;
+
+#[derive(Component)]
+struct ButtonConfirm;
+
+#[derive(Component)]
+struct ButtonSave;
+
+#[derive(Component)]
+struct ButtonBack;
+
+#[derive(Component)]
+struct ButtonImportRoster;
+
+#[derive(Component)]
+struct ButtonExportReports;
+
+/// 显示导入名单和导出报告结果的文字
+#[derive(Component)]
+struct ClassroomHintText;
+
+/// 按班级筛选玩家的下拉框
+#[derive(Component)]
+struct ClassFilterDropdown;
+
+/// 玩家时间限制列表的滚动区域
+#[derive(Component)]
+struct LimitsList;
+
+/// 时间限制列表中一个玩家的行，记录玩家所在的班级用于筛选
+#[derive(Component)]
+struct LimitsRow {
+    class: String,
+}
+
+#[derive(Component)]
+struct PinText;
+
+#[derive(Component)]
+struct PinHintText;
+
+#[derive(Clone, Copy)]
+enum LimitKind {
+    Daily,
+    Session,
+    Break,
+}
+
+/// 玩家时间限制的输入框
+#[derive(Component)]
+struct LimitField {
+    player: String,
+    kind: LimitKind,
+}
+
+/// 开启或关闭玩家自动难度调整的按钮
+#[derive(Component)]
+struct DifficultyToggle {
+    player: String,
+    enabled: bool,
+}
+
+/// 切换玩家主题的按钮，每次点击换成下一个主题
+#[derive(Component)]
+struct ThemeToggle {
+    player: String,
+    theme: String,
+}
+
+/// 打开玩家无障碍设置对话框的按钮，保存前修改的设置也记录在这里
+#[derive(Component)]
+struct AccessibilityButton {
+    player: String,
+    settings: Accessibility,
+}
+
+/// 无障碍设置对话框，button为打开对话框的按钮
+#[derive(Component)]
+struct AccessibilityDialog {
+    button: Entity,
+    settings: Accessibility,
+}
+
+#[derive(Component)]
+struct ButtonAccessibilityDone;
+
+/// 无障碍设置对话框中的选项按钮
+#[derive(Component, Clone, Copy)]
+enum AccessibilityOption {
+    Palette,
+    HighContrast,
+    LetterScale,
+    ReducedMotion,
+    DyslexiaFont,
+}
+
+impl AccessibilityOption {
+    fn label(&self) -> &'static str {
+        match self {
+            AccessibilityOption::Palette => "配色方案",
+            AccessibilityOption::HighContrast => "高对比度",
+            AccessibilityOption::LetterScale => "字符大小",
+            AccessibilityOption::ReducedMotion => "减少动画",
+            AccessibilityOption::DyslexiaFont => "阅读障碍字体",
+        }
+    }
+
+    fn value_text(&self, settings: &Accessibility) -> String {
+        match self {
+            AccessibilityOption::Palette => settings.palette.name().to_owned(),
+            AccessibilityOption::HighContrast => toggle_text(settings.high_contrast).to_owned(),
+            AccessibilityOption::LetterScale => format!("{}%", (settings.letter_scale * 100.).round()),
+            AccessibilityOption::ReducedMotion => toggle_text(settings.reduced_motion).to_owned(),
+            AccessibilityOption::DyslexiaFont => toggle_text(settings.dyslexia_font).to_owned(),
+        }
+    }
+
+    fn toggle(&self, settings: &mut Accessibility) {
+        match self {
+            AccessibilityOption::Palette => settings.palette = settings.palette.next(),
+            AccessibilityOption::HighContrast => settings.high_contrast = !settings.high_contrast,
+            AccessibilityOption::LetterScale => settings.letter_scale = settings.next_letter_scale(),
+            AccessibilityOption::ReducedMotion => settings.reduced_motion = !settings.reduced_motion,
+            AccessibilityOption::DyslexiaFont => settings.dyslexia_font = !settings.dyslexia_font,
+        }
+    }
+}
+
+fn toggle_text(enabled: bool) -> &'static str {
+    if enabled { "开启" } else { "关闭" }
+}
+
+fn guardian_setup(mut commands: Commands, fonts: Res<GameFonts>, play_time: Res<PlayTime>) {
+    let first_time = !play_time.has_pin();
+    spawn_startup_root::<GuardianEntity>(&mut commands)
+        .with_children(|parent| {
+            spawn_game_title(parent, &fonts, 1., 20., 15., 20., true);
+            let tip = if first_time {
+                "首次进入家长设置，请设置一个家长密码"
+            } else {
+                "请输入家长密码"
+            };
+            spawn_instructions(parent, tip, &fonts, 100.0);
+            InputBox::new(
+                parent,
+                PinText,
+                TextConfig {
+                    text: "".to_string(),
+                    font: fonts.info_font.clone(),
+                    font_size: 16.0,
+                    color: Color::WHITE,
+                    shadow: false
+                },
+                "在此输入密码",
+                Vec2::new(300., 32.),
+                UiRect::all(Val::Px(10.)),
+                InputBoxOptions { max_chars: Some(16), masked: true, ..default() });
+            spawn_marked_text(parent, PinHintText, "", Color::srgb_u8(255, 100, 100), fonts.normal_font.clone(), NORMAL_FONT_SIZE);
+            spawn_buttons(parent, ButtonConfirm, "确认");
+        });
+}
+
+fn spawn_buttons<C: Component>(parent: &mut ChildSpawnerCommands, marker: C, text: &str) {
+    parent.spawn(
+        Node {
+            width: Val::Auto,
+            height: Val::Auto,
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        }
+    ).with_children(|builder| {
+        builder.spawn(
+            widgets::PushButton::new(marker,
+                                     text,
+                                     Vec2::new(100.0,40.0),
+                                     true,
+                                     UiRect {
+                                         top: Val::Px(30.0),
+                                         right: Val::Px(5.0),
+                                         ..default()
+                                     })
+        );
+        builder.spawn(
+            widgets::PushButton::new((ButtonBack, widgets::CancelButton),
+                                     "返回",
+                                     Vec2::new(100.0,40.0),
+                                     true,
+                                     UiRect {
+                                         top: Val::Px(30.0),
+                                         left: Val::Px(5.0),
+                                         ..default()
+                                     })
+        );
+    });
+}
+
+/// 时间限制列表的表头和每一行使用相同的列宽，保证各列对齐
+fn limits_grid_node() -> Node {
+    Node {
+        display: Display::Grid,
+        width: Val::Px(1140.),
+        height: Val::Auto,
+        flex_shrink: 0.,
+        grid_template_columns: vec![GridTrack::flex(2.0),
+                                    GridTrack::flex(1.0),
+                                    GridTrack::flex(1.0),
+                                    GridTrack::flex(1.0),
+                                    GridTrack::flex(1.0),
+                                    GridTrack::flex(1.4),
+                                    GridTrack::flex(1.0)],
+        align_items: AlignItems::Center,
+        justify_items: JustifyItems::Center,
+        ..default()
+    }
+}
+
+/// 密码验证通过后显示每个玩家的时间限制设置
+fn limits_setup(commands: &mut Commands, fonts: &GameFonts, players: &Players, play_time: &PlayTime, hint: &str) {
+    // 导入了班级名单时可以按班级筛选玩家
+    let mut classes: Vec<&str> = players.0.iter().filter(|p| !p.class.is_empty()).map(class_name).collect();
+    classes.sort();
+    classes.dedup();
+    spawn_startup_root::<GuardianEntity>(commands)
+        .with_children(|parent| {
+            spawn_game_title(parent, fonts, 1., 20., 15., 20., true);
+            spawn_instructions(parent, "设置每个玩家的游戏时间（单位为分钟，0表示不限制）", fonts, 60.0);
+            if !classes.is_empty() {
+                let options = std::iter::once("全部班级".to_owned())
+                    .chain(players.0.iter().any(|p| p.class.is_empty()).then(|| NO_CLASS_NAME.to_owned()))
+                    .chain(classes.iter().map(|c| c.to_string()))
+                    .collect();
+                parent.spawn(Dropdown::new(ClassFilterDropdown, options, 0, Vec2::new(140., 32.), UiRect::top(Val::Px(10.))));
+            }
+            parent.spawn((
+                Node {
+                    margin: UiRect::top(Val::Px(10.)),
+                    ..limits_grid_node()
+                },
+                BackgroundColor(Color::NONE),
+            )).with_children(|builder| {
+                for title in ["玩家", "每天可玩", "连续可玩", "休息时间", "自动难度", "主题", "无障碍"] {
+                    spawn_info_text(builder, title, INFO_TEXT_COLOR, fonts.ui_font.clone(), NORMAL_FONT_SIZE);
+                }
+            });
+            parent.spawn((
+                LimitsList,
+                Node {
+                    width: Val::Px(1140.),
+                    max_height: Val::Px(LIMITS_LIST_HEIGHT),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(5.0),
+                    overflow: Overflow::scroll_y(),
+                    ..default()
+                },
+                ScrollPosition::default(),
+            )).with_children(|builder| {
+                for player in &players.0 {
+                    let limit = play_time.limit(&player.name);
+                    builder.spawn((
+                        LimitsRow { class: class_name(player).to_owned() },
+                        limits_grid_node(),
+                    )).with_children(|builder| {
+                        spawn_info_text(builder, &player.name, INFO_TEXT_COLOR, fonts.ui_font.clone(), NORMAL_FONT_SIZE);
+                        for (kind, value) in [(LimitKind::Daily, limit.daily_minutes),
+                                              (LimitKind::Session, limit.session_minutes),
+                                              (LimitKind::Break, limit.break_minutes)] {
+                            InputBox::new(
+                                builder,
+                                LimitField { player: player.name.clone(), kind },
+                                TextConfig {
+                                    text: value.to_string(),
+                                    font: fonts.info_font.clone(),
+                                    font_size: 16.0,
+                                    color: Color::WHITE,
+                                    shadow: false
+                                },
+                                "分钟",
+                                Vec2::new(100., 32.),
+                                UiRect::all(Val::Px(5.)),
+                                InputBoxOptions {
+                                    max_chars: Some(4),
+                                    validator: Some(Box::new(|value: &str| value.parse::<u32>().is_ok())),
+                                    ..default()
+                                });
+                        }
+                        builder.spawn(
+                            widgets::PushButton::new(DifficultyToggle { player: player.name.clone(), enabled: player.auto_difficulty },
+                                                     toggle_text(player.auto_difficulty),
+                                                     Vec2::new(80.0, 32.0),
+                                                     true,
+                                                     UiRect::all(Val::Px(5.)))
+                        );
+                        builder.spawn(
+                            widgets::PushButton::new(ThemeToggle { player: player.name.clone(), theme: player.theme.clone() },
+                                                     &theme_name(&player.theme),
+                                                     Vec2::new(120.0, 32.0),
+                                                     true,
+                                                     UiRect::all(Val::Px(5.)))
+                        );
+                        builder.spawn(
+                            widgets::PushButton::new(AccessibilityButton { player: player.name.clone(), settings: player.accessibility.clone() },
+                                                     "设置",
+                                                     Vec2::new(80.0, 32.0),
+                                                     true,
+                                                     UiRect::all(Val::Px(5.)))
+                        );
+                    });
+                }
+            });
+            // 班级名单和成绩报告都在数据目录下的classroom目录中
+            parent.spawn(Node {
+                flex_direction: FlexDirection::Row,
+                margin: UiRect::top(Val::Px(20.)),
+                ..default()
+            }).with_children(|builder| {
+                builder.spawn(
+                    widgets::PushButton::new(ButtonImportRoster,
+                                             "导入班级名单",
+                                             Vec2::new(160.0, 40.0),
+                                             true,
+                                             UiRect::right(Val::Px(5.)))
+                );
+                builder.spawn(
+                    widgets::PushButton::new(ButtonExportReports,
+                                             "导出成绩报告",
+                                             Vec2::new(160.0, 40.0),
+                                             true,
+                                             UiRect::left(Val::Px(5.)))
+                );
+            });
+            spawn_marked_text(parent, ClassroomHintText, hint, INFO_TEXT_COLOR, fonts.ui_font.clone(), NORMAL_FONT_SIZE);
+            spawn_buttons(parent, ButtonSave, "保存");
+        });
+}
+
+fn guardian_exit(mut commands: Commands, query: Query<Entity, With<GuardianEntity>>) {
+    commands.queue(blur_input_box);
+    cleanup_entities::<GuardianEntity>(commands, query);
+}
+
+fn on_confirm_button(
+    mut commands: Commands,
+    mut play_time: ResMut<PlayTime>,
+    mut reader: MessageReader<widgets::ButtonClicked>,
+    mut hint: Single<&mut Text, With<PinHintText>>,
+    query: Query<(), With<ButtonConfirm>>,
+    entities: Query<Entity, With<GuardianEntity>>,
+    pin: Single<&InputBox, With<PinText>>,
+    players: Res<Players>,
+    fonts: Res<GameFonts>,
+) {
+    if let Some(event) = reader.read().last()
+        && query.get(event.entity).is_ok() {
+        if pin.value.is_empty() {
+            **hint = Text::new("密码不能为空");
+            return;
+        }
+        if !play_time.has_pin() {
+            play_time.set_pin(&pin.value);
+            play_time.save();
+        } else if !play_time.check_pin(&pin.value) {
+            **hint = Text::new("密码不正确");
+            return;
+        }
+        commands.queue(blur_input_box);
+        cleanup_entities::<GuardianEntity>(commands.reborrow(), entities);
+        limits_setup(&mut commands, &fonts, &players, &play_time, "");
+    }
+}
+
+/// 从roster.csv导入学生后重新显示玩家列表
+fn on_import_roster_button(
+    mut commands: Commands,
+    mut reader: MessageReader<widgets::ButtonClicked>,
+    mut players: ResMut<Players>,
+    query: Query<(), With<ButtonImportRoster>>,
+    entities: Query<Entity, With<GuardianEntity>>,
+    play_time: Res<PlayTime>,
+    fonts: Res<GameFonts>,
+) {
+    if let Some(event) = reader.read().last()
+        && query.get(event.entity).is_ok() {
+        let hint = match import_roster(&mut players) {
+            Ok(summary) if summary.skipped > 0 => format!("新增{}名学生，更新{}名学生，超过{}人上限未导入{}名学生",
+                                                          summary.added, summary.updated, MAX_PLAYERS_COUNT, summary.skipped),
+            Ok(summary) => format!("新增{}名学生，更新{}名学生", summary.added, summary.updated),
+            Err(err) => err,
+        };
+        cleanup_entities::<GuardianEntity>(commands.reborrow(), entities);
+        limits_setup(&mut commands, &fonts, &players, &play_time, &hint);
+    }
+}
+
+fn on_export_reports_button(
+    mut reader: MessageReader<widgets::ButtonClicked>,
+    mut hint: Single<&mut Text, With<ClassroomHintText>>,
+    query: Query<(), With<ButtonExportReports>>,
+    players: Res<Players>,
+) {
+    if let Some(event) = reader.read().last()
+        && query.get(event.entity).is_ok() {
+        **hint = Text::new(match export_reports(&players) {
+            Ok(dir) => format!("成绩报告已保存到{}", dir.display()),
+            Err(err) => err,
+        });
+    }
+}
+
+/// 只显示选中班级的玩家，隐藏的玩家在保存时仍然保留原来的设置
+fn on_class_filter(
+    mut reader: MessageReader<widgets::DropdownSelected>,
+    dropdowns: Query<&Dropdown, With<ClassFilterDropdown>>,
+    mut rows: Query<(&LimitsRow, &mut Node)>,
+    mut list: Single<&mut ScrollPosition, With<LimitsList>>,
+) {
+    if let Some(event) = reader.read().last()
+        && let Ok(dropdown) = dropdowns.get(event.entity) {
+        let class = (event.index > 0).then(|| &dropdown.options[event.index]);
+        for (row, mut node) in &mut rows {
+            node.display = if class.is_none_or(|c| *c == row.class) { Display::Grid } else { Display::None };
+        }
+        list.y = 0.;
+    }
+}
+
+/// 用滚轮滚动玩家时间限制列表
+fn on_limits_scroll(
+    mut wheel_events: MessageReader<MouseWheel>,
+    list: Single<(&ComputedNode, &mut ScrollPosition), With<LimitsList>>,
+) {
+    let (node, mut scroll) = list.into_inner();
+    let max_scroll = ((node.content_size.y - node.size.y) * node.inverse_scale_factor()).max(0.);
+    for event in wheel_events.read() {
+        let dy = match event.unit {
+            MouseScrollUnit::Line => event.y * LIMITS_ROW_HEIGHT,
+            MouseScrollUnit::Pixel => event.y,
+        };
+        scroll.y = (scroll.y - dy).clamp(0., max_scroll);
+    }
+}
+
+fn on_difficulty_toggle(
+    mut reader: MessageReader<widgets::ButtonClicked>,
+    mut toggles: Query<(&mut DifficultyToggle, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for event in reader.read() {
+        if let Ok((mut toggle, children)) = toggles.get_mut(event.entity) {
+            toggle.enabled = !toggle.enabled;
+            if let Some(mut text) = children.first().and_then(|c| texts.get_mut(*c).ok()) {
+                **text = toggle_text(toggle.enabled).to_string();
+            }
+        }
+    }
+}
+
+fn on_theme_toggle(
+    mut reader: MessageReader<widgets::ButtonClicked>,
+    mut toggles: Query<(&mut ThemeToggle, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for event in reader.read() {
+        if let Ok((mut toggle, children)) = toggles.get_mut(event.entity) {
+            let themes = available_themes();
+            let next = themes.iter()
+                .position(|(id, _)| *id == toggle.theme)
+                .map_or(0, |i| (i + 1) % themes.len());
+            let (id, name) = &themes[next];
+            toggle.theme = id.clone();
+            if let Some(mut text) = children.first().and_then(|c| texts.get_mut(*c).ok()) {
+                **text = name.clone();
+            }
+        }
+    }
+}
+
+/// 点击玩家的无障碍设置按钮后打开设置对话框
+fn on_accessibility_button(
+    mut commands: Commands,
+    mut reader: MessageReader<widgets::ButtonClicked>,
+    buttons: Query<&AccessibilityButton>,
+    dialogs: Query<(), With<AccessibilityDialog>>,
+    fonts: Res<GameFonts>,
+) {
+    let Some(event) = reader.read().last() else {
+        return;
+    };
+    let Ok(button) = buttons.get(event.entity) else {
+        return;
+    };
+    if !dialogs.is_empty() {
+        return;
+    }
+    let dialog = ModelDialog::new(&mut commands, GameState::Guardian, 40.);
+    commands.entity(dialog.root).insert((
+        GlobalZIndex(10),
+        AccessibilityDialog { button: event.entity, settings: button.settings.clone() },
+    ));
+    commands.entity(dialog.container).with_children(|builder| {
+        spawn_info_text(builder, &format!("{}的无障碍设置", button.player), INFO_TEXT_COLOR,
+                        fonts.ui_font.clone(), 24.);
+        builder.spawn(Node {
+            display: Display::Grid,
+            grid_template_columns: vec![GridTrack::flex(1.0), GridTrack::flex(1.0)],
+            align_items: AlignItems::Center,
+            justify_items: JustifyItems::Center,
+            row_gap: Val::Px(5.0),
+            margin: UiRect::all(Val::Px(10.)),
+            ..default()
+        }).with_children(|builder| {
+            for option in [AccessibilityOption::Palette,
+                           AccessibilityOption::HighContrast,
+                           AccessibilityOption::LetterScale,
+                           AccessibilityOption::ReducedMotion,
+                           AccessibilityOption::DyslexiaFont] {
+                spawn_info_text(builder, option.label(), INFO_TEXT_COLOR, fonts.ui_font.clone(), NORMAL_FONT_SIZE);
+                builder.spawn(
+                    widgets::PushButton::new(option,
+                                             &option.value_text(&button.settings),
+                                             Vec2::new(100.0, 32.0),
+                                             true,
+                                             UiRect::all(Val::Px(5.)))
+                );
+            }
+        });
+        builder.spawn(
+            widgets::PushButton::new((ButtonAccessibilityDone, widgets::CancelButton),
+                                     "确定",
+                                     Vec2::new(100.0, 40.0),
+                                     true,
+                                     UiRect::all(Val::Px(10.)))
+        );
+    });
+}
+
+fn on_accessibility_option(
+    mut reader: MessageReader<widgets::ButtonClicked>,
+    mut dialog: Single<&mut AccessibilityDialog>,
+    options: Query<(&AccessibilityOption, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for event in reader.read() {
+        if let Ok((option, children)) = options.get(event.entity) {
+            option.toggle(&mut dialog.settings);
+            if let Some(mut text) = children.first().and_then(|c| texts.get_mut(*c).ok()) {
+                **text = option.value_text(&dialog.settings);
+            }
+        }
+    }
+}
+
+/// 关闭对话框，修改的设置在点击保存后才写入玩家数据
+fn on_accessibility_done(
+    mut commands: Commands,
+    mut reader: MessageReader<widgets::ButtonClicked>,
+    mut buttons: Query<&mut AccessibilityButton>,
+    query: Query<(), With<ButtonAccessibilityDone>>,
+    dialog: Single<(Entity, &AccessibilityDialog)>,
+) {
+    if let Some(event) = reader.read().last()
+        && query.get(event.entity).is_ok() {
+        let (entity, dialog) = dialog.into_inner();
+        if let Ok(mut button) = buttons.get_mut(dialog.button) {
+            button.settings = dialog.settings.clone();
+        }
+        commands.entity(entity).despawn();
+    }
+}
+
+fn on_save_button(
+    mut play_time: ResMut<PlayTime>,
+    mut players: ResMut<Players>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut reader: MessageReader<widgets::ButtonClicked>,
+    query: Query<(), With<ButtonSave>>,
+    fields: Query<(&InputBox, &LimitField)>,
+    toggles: Query<&DifficultyToggle>,
+    themes: Query<&ThemeToggle>,
+    accessibility: Query<&AccessibilityButton>,
+) {
+    if let Some(event) = reader.read().last()
+        && query.get(event.entity).is_ok() {
+        for (input, field) in &fields {
+            let mut limit: PlayTimeLimit = play_time.limit(&field.player);
+            let Ok(value) = input.value.trim().parse::<u32>() else {
+                continue;
+            };
+            match field.kind {
+                LimitKind::Daily => limit.daily_minutes = value,
+                LimitKind::Session => limit.session_minutes = value,
+                LimitKind::Break => limit.break_minutes = value,
+            }
+            play_time.limits.insert(field.player.clone(), limit);
+        }
+        play_time.save();
+        for toggle in &toggles {
+            if let Some(player) = players.0.iter_mut().find(|p| p.name == toggle.player) {
+                player.auto_difficulty = toggle.enabled;
+            }
+        }
+        for toggle in &themes {
+            if let Some(player) = players.0.iter_mut().find(|p| p.name == toggle.player) {
+                player.theme = toggle.theme.clone();
+            }
+        }
+        for button in &accessibility {
+            if let Some(player) = players.0.iter_mut().find(|p| p.name == button.player) {
+                player.accessibility = button.settings.clone();
+            }
+        }
+        save_game_users(&players);
+        next_state.set(GameState::Startup);
+    }
+}
+
+fn on_back_button(
+    mut next_state: ResMut<NextState<GameState>>,
+    mut reader: MessageReader<widgets::ButtonClicked>,
+    query: Query<(), With<ButtonBack>>,
+) {
+    if let Some(event) = reader.read().last()
+        && query.get(event.entity).is_ok() {
+        next_state.set(GameState::Startup);
+    }
+}
@@ -0,0 +1,316 @@
+use std::collections::HashMap;
+use bevy::color::Mix;
+use bevy::input_focus::InputFocus;
+use bevy::prelude::*;
+use crate::{widgets, GameFonts, GameState, Players};
+use crate::accessibility::Accessibility;
+use crate::keystats::{KeyStats, KeyStatsData, StatsPeriod, STATS_PERIODS};
+use crate::ui::*;
+use crate::widgets::Dropdown;
+
+// 键盘上每一行的按键及这一行向右缩进的距离
+const KEYBOARD_ROWS: [(&str, f32); 4] = [
+    ("`1234567890-=", 0.),
+    ("QWERTYUIOP[]\\", 30.),
+    ("ASDFGHJKL;'", 45.),
+    ("ZXCVBNM,./", 70.),
+];
+const KEY_SIZE: f32 = 52.;
+const KEY_GAP: f32 = 6.;
+const NO_DATA_COLOR: Color = Color::srgb_u8(60, 60, 72);
+const KEY_BORDER_COLOR: Color = Color::srgb_u8(76, 69, 113);
+// 错误率达到这个比例时显示为最差的颜色
+const WORST_ERROR_RATE: f32 = 0.5;
+
+pub fn heatmap_plugin(app: &mut App) {
+    app
+        .insert_resource(KeyStatsData::load())
+        .add_systems(OnEnter(GameState::Heatmap), heatmap_setup)
+        .add_systems(OnExit(GameState::Heatmap), cleanup_entities::<HeatmapEntity>)
+        .add_systems(Update, update_heatmap_keys.run_if(in_state(GameState::Heatmap)
+            .and(resource_changed::<HeatmapView>)))
+        .add_systems(Update, (on_heatmap_dropdown,
+                              update_key_details,
+                              on_back_button).run_if(in_state(GameState::Heatmap)));
+}
+
+/// 热力图中按键颜色代表的数据
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum HeatmapMetric {
+    #[default]
+    ErrorRate,
+    Reaction,
+}
+
+const HEATMAP_METRICS: [HeatmapMetric; 2] = [HeatmapMetric::ErrorRate, HeatmapMetric::Reaction];
+
+impl HeatmapMetric {
+    fn name(&self) -> &'static str {
+        match self {
+            HeatmapMetric::ErrorRate => "错误率",
+            HeatmapMetric::Reaction => "平均反应时间",
+        }
+    }
+
+    fn value(&self, stats: &KeyStats) -> Option<f32> {
+        match self {
+            HeatmapMetric::ErrorRate => stats.error_rate(),
+            HeatmapMetric::Reaction => stats.reaction_ms(),
+        }
+    }
+
+    fn value_text(&self, value: f32) -> String {
+        match self {
+            HeatmapMetric::ErrorRate => format!("{:.0}%", value * 100.),
+            HeatmapMetric::Reaction => format!("{:.0}", value),
+        }
+    }
+}
+
+/// 热力图显示的玩家、数据及时间范围，打开热力图之前插入
+#[derive(Resource)]
+pub struct HeatmapView {
+    pub player: String,
+    pub metric: HeatmapMetric,
+    pub period: StatsPeriod,
+}
+
+impl HeatmapView {
+    pub fn new(player: &str) -> Self {
+        HeatmapView {
+            player: player.to_owned(),
+            metric: HeatmapMetric::default(),
+            period: StatsPeriod::default(),
+        }
+    }
+}
+
+#[derive(Component, Default)]
+struct HeatmapEntity;
+
+#[derive(Component)]
+struct ButtonBack;
+
+/// 热力图界面中的下拉框
+#[derive(Component)]
+enum HeatmapDropdown {
+    Player,
+    Metric,
+    Period,
+}
+
+/// 键盘上的一个按键
+#[derive(Component)]
+struct HeatmapKey(char);
+
+/// 按键上显示的数值
+#[derive(Component)]
+struct HeatmapKeyValue(char);
+
+/// 图例中的颜色块，数值是颜色在health_colors中的序号
+#[derive(Component)]
+struct HeatmapLegend(usize);
+
+/// 鼠标所在按键的详细数据
+#[derive(Component)]
+struct HeatmapDetail;
+
+fn heatmap_setup(
+    mut commands: Commands,
+    fonts: Res<GameFonts>,
+    players: Res<Players>,
+    view: Res<HeatmapView>,
+) {
+    let names: Vec<String> = players.0.iter().map(|p| p.name.clone()).collect();
+    let selected = names.iter().position(|n| *n == view.player).unwrap_or(0);
+    spawn_startup_root::<HeatmapEntity>(&mut commands)
+        .with_children(|parent| {
+            spawn_game_title(parent, &fonts, 1., 20., 15., 20., true);
+            spawn_instructions(parent, "键盘热力图：每个键的颜色表示这个键的练习情况", &fonts, 40.0);
+            parent.spawn(Node {
+                align_items: AlignItems::Center,
+                margin: UiRect::top(Val::Px(20.)),
+                ..default()
+            }).with_children(|builder| {
+                builder.spawn(Dropdown::new(HeatmapDropdown::Player, names, selected, Vec2::new(180., 32.),
+                                            UiRect::horizontal(Val::Px(10.))));
+                builder.spawn(Dropdown::new(HeatmapDropdown::Metric,
+                                            HEATMAP_METRICS.iter().map(|m| m.name().to_owned()).collect(),
+                                            HEATMAP_METRICS.iter().position(|m| *m == view.metric).unwrap_or(0),
+                                            Vec2::new(180., 32.),
+                                            UiRect::horizontal(Val::Px(10.))));
+                builder.spawn(Dropdown::new(HeatmapDropdown::Period,
+                                            STATS_PERIODS.iter().map(|p| p.name().to_owned()).collect(),
+                                            STATS_PERIODS.iter().position(|p| *p == view.period).unwrap_or(0),
+                                            Vec2::new(180., 32.),
+                                            UiRect::horizontal(Val::Px(10.))));
+            });
+            parent.spawn(Node {
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(KEY_GAP),
+                margin: UiRect::top(Val::Px(30.)),
+                ..default()
+            }).with_children(|builder| {
+                for (keys, indent) in KEYBOARD_ROWS {
+                    builder.spawn(Node {
+                        column_gap: Val::Px(KEY_GAP),
+                        margin: UiRect::left(Val::Px(indent)),
+                        ..default()
+                    }).with_children(|builder| {
+                        for key in keys.chars() {
+                            spawn_key(builder, &fonts, key);
+                        }
+                    });
+                }
+            });
+            parent.spawn(Node {
+                align_items: AlignItems::Center,
+                margin: UiRect::vertical(Val::Px(20.)),
+                ..default()
+            }).with_children(|builder| {
+                for (index, label) in [(2, "好"), (1, "一般"), (0, "需要多练习")] {
+                    spawn_legend(builder, &fonts, HeatmapLegend(index), Color::NONE, label);
+                }
+                spawn_legend(builder, &fonts, (), NO_DATA_COLOR, "没有数据");
+            });
+            spawn_marked_text(parent, HeatmapDetail, "", INFO_TEXT_COLOR, fonts.ui_font.clone(), NORMAL_FONT_SIZE);
+            parent.spawn(widgets::PushButton::new((ButtonBack, widgets::CancelButton), "返回",
+                                                  Vec2::new(100.0, 40.0), true, UiRect::top(Val::Px(20.0))));
+        });
+}
+
+fn spawn_key(builder: &mut ChildSpawnerCommands, fonts: &GameFonts, key: char) {
+    builder.spawn((
+        Button,
+        HeatmapKey(key),
+        Node {
+            width: Val::Px(KEY_SIZE),
+            height: Val::Px(KEY_SIZE),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            border: UiRect::all(Val::Px(1.)),
+            border_radius: BorderRadius::all(Val::Px(6.)),
+            ..default()
+        },
+        BorderColor::all(KEY_BORDER_COLOR),
+        BackgroundColor(NO_DATA_COLOR),
+    )).with_children(|builder| {
+        spawn_info_text(builder, &key.to_string(), Color::WHITE, fonts.ui_font.clone(), NORMAL_FONT_SIZE);
+        spawn_marked_text(builder, HeatmapKeyValue(key), "", Color::WHITE, fonts.ui_font.clone(), 12.);
+    });
+}
+
+fn spawn_legend(builder: &mut ChildSpawnerCommands, fonts: &GameFonts, marker: impl Bundle, color: Color, label: &str) {
+    builder.spawn((
+        marker,
+        Node {
+            width: Val::Px(20.),
+            height: Val::Px(20.),
+            margin: UiRect::new(Val::Px(20.), Val::Px(6.), Val::ZERO, Val::ZERO),
+            border_radius: BorderRadius::all(Val::Px(4.)),
+            ..default()
+        },
+        BackgroundColor(color),
+    ));
+    spawn_info_text(builder, label, INFO_TEXT_COLOR, fonts.ui_font.clone(), INFO_FONT_SIZE);
+}
+
+/// 0表示最好，1表示最差，按照玩家的色盲配色从好到差渐变
+fn heat_color(colors: [Color; 3], t: f32) -> Color {
+    let [bad, middle, good] = colors;
+    if t < 0.5 { good.mix(&middle, t * 2.) } else { middle.mix(&bad, (t - 0.5) * 2.) }
+}
+
+/// 按照选择的数据和时间范围给每个按键上色
+fn update_heatmap_keys(
+    view: Res<HeatmapView>,
+    data: Res<KeyStatsData>,
+    players: Res<Players>,
+    mut keys: Query<(&HeatmapKey, &mut BackgroundColor)>,
+    mut values: Query<(&HeatmapKeyValue, &mut Text)>,
+    mut legends: Query<(&HeatmapLegend, &mut BackgroundColor), Without<HeatmapKey>>,
+) {
+    let summary = data.summary(&view.player, view.period);
+    let metric_values: HashMap<char, f32> = summary.iter()
+        .filter_map(|(key, stats)| view.metric.value(stats).map(|v| (*key, v)))
+        .collect();
+    // 反应时间按照所有按键中最快和最慢的相对位置上色
+    let (fastest, slowest) = metric_values.values()
+        .fold((f32::MAX, 0f32), |(min, max), v| (min.min(*v), max.max(*v)));
+    let colors = players.0.iter().find(|p| p.name == view.player)
+        .map(|p| p.accessibility.health_colors())
+        .unwrap_or_else(|| Accessibility::default().health_colors());
+    for (legend, mut background) in &mut legends {
+        *background = BackgroundColor(colors[legend.0]);
+    }
+    for (key, mut background) in &mut keys {
+        *background = BackgroundColor(match metric_values.get(&key.0) {
+            None => NO_DATA_COLOR,
+            Some(value) => {
+                let t = match view.metric {
+                    HeatmapMetric::ErrorRate => value / WORST_ERROR_RATE,
+                    HeatmapMetric::Reaction if slowest > fastest => (value - fastest) / (slowest - fastest),
+                    HeatmapMetric::Reaction => 0.,
+                };
+                heat_color(colors, t.clamp(0., 1.))
+            }
+        });
+    }
+    for (key, mut text) in &mut values {
+        **text = metric_values.get(&key.0).map(|v| view.metric.value_text(*v)).unwrap_or_default();
+    }
+}
+
+/// 显示鼠标所在或者有键盘焦点的按键的详细数据
+fn update_key_details(
+    view: Res<HeatmapView>,
+    data: Res<KeyStatsData>,
+    input_focus: Res<InputFocus>,
+    keys: Query<(Entity, &HeatmapKey, &Interaction)>,
+    mut detail: Single<&mut Text, With<HeatmapDetail>>,
+    mut shown: Local<Option<char>>,
+) {
+    let key = keys.iter().find(|(_, _, interaction)| **interaction != Interaction::None)
+        .or_else(|| input_focus.0.and_then(|focus| keys.get(focus).ok()))
+        .map(|(_, key, _)| key.0);
+    if key == *shown && !view.is_changed() {
+        return;
+    }
+    *shown = key;
+    let text = match key {
+        None => "把鼠标移到按键上可以查看详细数据".to_owned(),
+        Some(key) => {
+            let stats = data.summary(&view.player, view.period).get(&key).copied().unwrap_or_default();
+            let reaction = stats.reaction_ms().map_or("-".to_owned(), |ms| format!("{:.0}毫秒", ms));
+            format!("{} 键：击中 {}  漏掉 {}  按错 {}  平均反应 {}", key, stats.hits, stats.misses, stats.wrong, reaction)
+        }
+    };
+    **detail = Text::new(text);
+}
+
+fn on_heatmap_dropdown(
+    mut view: ResMut<HeatmapView>,
+    mut reader: MessageReader<widgets::DropdownSelected>,
+    dropdowns: Query<(&HeatmapDropdown, &Dropdown)>,
+) {
+    for event in reader.read() {
+        match dropdowns.get(event.entity) {
+            Ok((HeatmapDropdown::Player, dropdown)) => view.player = dropdown.options[event.index].clone(),
+            Ok((HeatmapDropdown::Metric, _)) => view.metric = HEATMAP_METRICS[event.index],
+            Ok((HeatmapDropdown::Period, _)) => view.period = STATS_PERIODS[event.index],
+            Err(_) => (),
+        }
+    }
+}
+
+fn on_back_button(
+    mut next_state: ResMut<NextState<GameState>>,
+    mut reader: MessageReader<widgets::ButtonClicked>,
+    query: Query<(), With<ButtonBack>>,
+) {
+    if let Some(event) = reader.read().last()
+        && query.get(event.entity).is_ok() {
+        next_state.set(GameState::Startup);
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use bevy::prelude::*;
+use chrono::{Days, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use crate::{get_app_data_dir, GAME_APP_NAME};
+
+const KEY_STATS_FILE: &str = "keystats.json";
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+// 需要按Shift键输入的符号及它们所在的按键
+const SHIFTED_SYMBOLS: [(char, char); 21] = [
+    ('~', '`'), ('!', '1'), ('@', '2'), ('#', '3'), ('$', '4'), ('%', '5'), ('^', '6'),
+    ('&', '7'), ('*', '8'), ('(', '9'), (')', '0'), ('_', '-'), ('+', '='), ('{', '['),
+    ('}', ']'), ('|', '\\'), (':', ';'), ('"', '\''), ('<', ','), ('>', '.'), ('?', '/'),
+];
+
+/// 字符所在的按键，大小写字母和Shift符号都算作同一个键
+pub fn key_of(letter: char) -> char {
+    SHIFTED_SYMBOLS.iter().find(|(shifted, _)| *shifted == letter)
+        .map_or(letter.to_ascii_uppercase(), |(_, key)| *key)
+}
+
+/// 一个按键的练习数据
+#[derive(Deserialize, Serialize, Clone, Copy, Default)]
+pub struct KeyStats {
+    // 击落和漏掉的带有这个字符的敌机
+    pub hits: u32,
+    pub misses: u32,
+    // 没有对应目标时按下这个键的次数
+    pub wrong: u32,
+    // 击落敌机的反应时间总和，用于计算平均值
+    pub reaction_secs: f32,
+}
+
+impl KeyStats {
+    fn add(&mut self, other: &KeyStats) {
+        self.hits += other.hits;
+        self.misses += other.misses;
+        self.wrong += other.wrong;
+        self.reaction_secs += other.reaction_secs;
+    }
+
+    /// 漏掉和按错占全部次数的比例，没有数据时返回None
+    pub fn error_rate(&self) -> Option<f32> {
+        let total = self.hits + self.misses + self.wrong;
+        (total > 0).then(|| (self.misses + self.wrong) as f32 / total as f32)
+    }
+
+    /// 平均反应时间，单位为毫秒，没有击落过敌机时返回None
+    pub fn reaction_ms(&self) -> Option<f32> {
+        (self.hits > 0).then(|| self.reaction_secs * 1000. / self.hits as f32)
+    }
+}
+
+/// 统计按键数据的时间范围
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum StatsPeriod {
+    Today,
+    #[default]
+    Week,
+    Month,
+    All,
+}
+
+pub const STATS_PERIODS: [StatsPeriod; 4] = [StatsPeriod::Today, StatsPeriod::Week, StatsPeriod::Month, StatsPeriod::All];
+
+impl StatsPeriod {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StatsPeriod::Today => "今天",
+            StatsPeriod::Week => "最近7天",
+            StatsPeriod::Month => "最近30天",
+            StatsPeriod::All => "全部",
+        }
+    }
+
+    /// 范围内最早的日期，全部数据时返回None
+    fn first_day(&self, today: NaiveDate) -> Option<NaiveDate> {
+        let days = match self {
+            StatsPeriod::Today => 0,
+            StatsPeriod::Week => 6,
+            StatsPeriod::Month => 29,
+            StatsPeriod::All => return None,
+        };
+        today.checked_sub_days(Days::new(days))
+    }
+}
+
+/// 每个玩家每天每个按键的练习数据
+#[derive(Resource, Deserialize, Serialize, Default)]
+pub struct KeyStatsData(HashMap<String, BTreeMap<String, HashMap<char, KeyStats>>>);
+
+impl KeyStatsData {
+    pub fn load() -> KeyStatsData {
+        let mut data_file = get_app_data_dir(GAME_APP_NAME);
+        data_file.push(KEY_STATS_FILE);
+        if !data_file.exists() {
+            return KeyStatsData::default();
+        }
+        fs::read_to_string(&data_file)
+            .and_then(|data| serde_json::from_str::<KeyStatsData>(&data).map_err(|err| err.into()))
+            .unwrap_or_else(|err| {
+                error!("Failed to parse key stats: {}", err);
+                KeyStatsData::default()
+            })
+    }
+
+    pub fn save(&self) {
+        if let Ok(json) = serde_json::to_string(self) {
+            let mut data_file = get_app_data_dir(GAME_APP_NAME);
+            data_file.push(KEY_STATS_FILE);
+            if let Err(e) = fs::write(&data_file, json.as_bytes()) {
+                error!("Failed to save key stats: {}", e);
+            }
+        }
+    }
+
+    /// 玩家今天在letter所在按键上的数据
+    pub fn record(&mut self, player: &str, letter: char) -> &mut KeyStats {
+        let date = Local::now().format(DATE_FORMAT).to_string();
+        self.0.entry(player.to_owned()).or_default()
+            .entry(date).or_default()
+            .entry(key_of(letter)).or_default()
+    }
+
+    /// 玩家在一段时间内每个按键的累计数据
+    pub fn summary(&self, player: &str, period: StatsPeriod) -> HashMap<char, KeyStats> {
+        let first_day = period.first_day(Local::now().date_naive());
+        let mut summary: HashMap<char, KeyStats> = HashMap::new();
+        let Some(days) = self.0.get(player) else {
+            return summary;
+        };
+        for (date, keys) in days {
+            let in_period = first_day.is_none_or(|first| {
+                NaiveDate::parse_from_str(date, DATE_FORMAT).is_ok_and(|day| day >= first)
+            });
+            if !in_period {
+                continue;
+            }
+            for (key, stats) in keys {
+                summary.entry(*key).or_default().add(stats);
+            }
+        }
+        summary
+    }
+}
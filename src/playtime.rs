@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::fs;
+use bevy::prelude::*;
+use chrono::{Local, Utc};
+use serde::{Deserialize, Serialize};
+use crate::{get_app_data_dir, GAME_APP_NAME};
+
+const PLAY_TIME_DATA_FILE: &str = "playtime.json";
+
+/// 玩家的游戏时间限制，单位为分钟，0表示不限制。默认都不限制，由家长自行开启
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+pub struct PlayTimeLimit {
+    // 每天累计可以玩的时间
+    pub daily_minutes: u32,
+    // 连续游戏的最长时间
+    pub session_minutes: u32,
+    // 连续游戏后强制休息的时间
+    pub break_minutes: u32,
+}
+
+/// 玩家的游戏时间记录
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct PlayTimeRecord {
+    // 记录对应的日期
+    pub date: String,
+    // 当天已经玩的秒数
+    pub daily_secs: f32,
+    // 本次连续游戏的秒数
+    pub session_secs: f32,
+    // 最后一次游戏的时间戳
+    pub last_played: i64,
+    // 强制休息结束的时间戳
+    pub rest_until: i64,
+}
+
+/// 需要休息的原因
+#[derive(PartialEq, Clone, Copy)]
+pub enum RestReason {
+    // 连续游戏时间过长
+    Session,
+    // 当天的游戏时间已经用完
+    Daily,
+}
+
+#[derive(Resource, Deserialize, Serialize, Default)]
+pub struct PlayTime {
+    // 旧版本保存的明文密码，读取后转换为pin_hash
+    #[serde(default, skip_serializing)]
+    pin: String,
+    // 进入家长设置需要的密码，只保存加盐后的哈希值
+    #[serde(default)]
+    pin_salt: String,
+    #[serde(default)]
+    pin_hash: String,
+    pub limits: HashMap<String, PlayTimeLimit>,
+    pub records: HashMap<String, PlayTimeRecord>,
+    #[serde(skip)]
+    today: Today,
+}
+
+/// 缓存的当天日期，每帧累加游戏时间时不用重新格式化日期，过了午夜才重新计算
+#[derive(Default)]
+struct Today {
+    date: String,
+    // 当天结束的时间戳
+    ends_at: i64,
+}
+
+impl Today {
+    fn date(&mut self, now: i64) -> &str {
+        if now >= self.ends_at {
+            let local = Local::now();
+            self.date = local.format("%Y-%m-%d").to_string();
+            // 无法确定午夜的时间（比如夏令时切换）时一分钟后再检查
+            self.ends_at = local.date_naive().succ_opt()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+                .map_or(now + 60, |midnight| midnight.timestamp());
+        }
+        &self.date
+    }
+}
+
+pub fn now_timestamp() -> i64 {
+    Utc::now().timestamp()
+}
+
+fn hash_pin(salt: &str, pin: &str) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(pin.as_bytes());
+    hasher.finalize()
+}
+
+impl PlayTime {
+    pub fn load() -> PlayTime {
+        let mut data_file = get_app_data_dir(GAME_APP_NAME);
+        data_file.push(PLAY_TIME_DATA_FILE);
+        if !data_file.exists() {
+            return PlayTime::default();
+        }
+        let mut play_time = fs::read_to_string(&data_file)
+            .and_then(|data| serde_json::from_str::<PlayTime>(&data).map_err(|err| err.into()))
+            .unwrap_or_else(|err| {
+                error!("Failed to parse play time data: {}", err);
+                PlayTime::default()
+            });
+        if !play_time.pin.is_empty() {
+            let pin = std::mem::take(&mut play_time.pin);
+            play_time.set_pin(&pin);
+            play_time.save();
+        }
+        play_time
+    }
+
+    pub fn save(&self) {
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            let mut data_file = get_app_data_dir(GAME_APP_NAME);
+            data_file.push(PLAY_TIME_DATA_FILE);
+            if let Err(e) = fs::write(&data_file, json.as_bytes()) {
+                error!("Failed to save play time data: {}", e);
+            }
+        }
+    }
+
+    /// 是否已经设置了家长密码
+    pub fn has_pin(&self) -> bool {
+        !self.pin_hash.is_empty()
+    }
+
+    /// 设置家长密码，每次使用新的随机盐
+    pub fn set_pin(&mut self, pin: &str) {
+        self.pin_salt = rand::random::<[u8; 16]>().iter().map(|b| format!("{:02x}", b)).collect();
+        self.pin_hash = hash_pin(&self.pin_salt, pin).to_hex().to_string();
+    }
+
+    /// 检查输入的家长密码是否正确
+    pub fn check_pin(&self, pin: &str) -> bool {
+        // Hash的比较是常数时间的
+        blake3::Hash::from_hex(&self.pin_hash).is_ok_and(|hash| hash == hash_pin(&self.pin_salt, pin))
+    }
+
+    pub fn limit(&self, player: &str) -> PlayTimeLimit {
+        self.limits.get(player).copied().unwrap_or_default()
+    }
+
+    /// 获取玩家的记录，跨天或者休息足够久时重置对应的计时
+    pub fn record(&mut self, player: &str) -> &mut PlayTimeRecord {
+        let limit = self.limit(player);
+        let now = now_timestamp();
+        let date = self.today.date(now);
+        let record = self.records.entry(player.to_owned()).or_default();
+        if record.date != date {
+            record.date = date.to_owned();
+            record.daily_secs = 0.;
+        }
+        let rested = record.rest_until != 0 && now >= record.rest_until;
+        // 不需要休息时不按照空闲时间重置连续游戏的计时，否则每次获取记录都会清零
+        let idle = limit.break_minutes > 0 && now - record.last_played >= limit.break_minutes as i64 * 60;
+        if rested || idle {
+            record.session_secs = 0.;
+            record.rest_until = 0;
+        }
+        record
+    }
+
+    /// 累加玩家的游戏时间，返回是否达到了需要休息的条件
+    pub fn accumulate(&mut self, player: &str, secs: f32) -> Option<RestReason> {
+        let limit = self.limit(player);
+        let record = self.record(player);
+        record.daily_secs += secs;
+        record.session_secs += secs;
+        record.last_played = now_timestamp();
+
+        let reason = Self::check(&limit, record);
+        if reason == Some(RestReason::Session) {
+            record.rest_until = now_timestamp() + limit.break_minutes as i64 * 60;
+        }
+        reason
+    }
+
+    /// 判断玩家当前是否需要休息
+    pub fn rest_reason(&mut self, player: &str) -> Option<RestReason> {
+        let limit = self.limit(player);
+        let record = self.record(player);
+        if record.rest_until > now_timestamp() {
+            return Some(RestReason::Session);
+        }
+        Self::check(&limit, record)
+    }
+
+    /// 距离可以继续游戏还剩余的秒数
+    pub fn rest_remaining(&mut self, player: &str) -> i64 {
+        let record = self.record(player);
+        (record.rest_until - now_timestamp()).max(0)
+    }
+
+    fn check(limit: &PlayTimeLimit, record: &PlayTimeRecord) -> Option<RestReason> {
+        if limit.daily_minutes > 0 && record.daily_secs >= limit.daily_minutes as f32 * 60. {
+            Some(RestReason::Daily)
+        } else if limit.session_minutes > 0 && record.session_secs >= limit.session_minutes as f32 * 60. {
+            Some(RestReason::Session)
+        } else {
+            None
+        }
+    }
+}
@@ -1,16 +1,8 @@
-use std::fs::File;
-use std::io::Write;
 use bevy::prelude::*;
 use super::*;
 use ui::*;
 use widgets::TextConfig;
-use crate::widgets::InputBox;
-
-const PLAYER_AVATARS: [&str; 28] = [
-    "whale", "cat", "cool", "donatello", "dragon", "swordsman", "robot",
-    "elephant", "ghost", "hero", "hero-boy", "hero-girl", "hero-girl2", "sun",
-    "kitty", "monkey", "monkey-cool", "panda", "panda-sleep", "assasin", "spierman",
-    "sea-turtle", "snake", "tiger", "angel", "rabbit", "smiling", "dog"];
+use crate::widgets::{blur_input_box, InputBox, InputBoxOptions};
 
 pub fn new_player_plugin(app: &mut App) {
     app
@@ -27,6 +19,8 @@ struct NewPlayerEntity;
 #[derive(Component)]
 struct ButtonCreate;
 
+const MAX_PLAYER_NAME_CHARS: usize = 12;
+
 #[derive(Component)]
 struct ButtonCancel;
 
@@ -39,8 +33,15 @@ struct PlayerNameText;
 #[derive(Resource, Default)]
 struct SelectedAvatar(Option<Entity>, Option<String>);
 
-fn new_player_setup(mut commands: Commands, fonts: Res<GameFonts>, asset_server: Res<AssetServer>) {
+fn new_player_setup(mut commands: Commands, fonts: Res<GameFonts>, asset_server: Res<AssetServer>, players: Res<Players>) {
     commands.insert_resource(SelectedAvatar::default());
+    // 名称不能为空，也不能和已有玩家重名
+    let names: Vec<String> = players.0.iter().map(|p| p.name.clone()).collect();
+    let options = InputBoxOptions {
+        max_chars: Some(MAX_PLAYER_NAME_CHARS),
+        validator: Some(Box::new(move |name: &str| !name.trim().is_empty() && !names.iter().any(|n| n == name))),
+        ..default()
+    };
     spawn_startup_root::<NewPlayerEntity>(&mut commands)
         .with_children(|parent| {
             spawn_game_title(parent, &fonts, 1., 20., 15., 20., true);
@@ -57,7 +58,8 @@ fn new_player_setup(mut commands: Commands, fonts: Res<GameFonts>, asset_server:
                 },
                 "在此输入名称",
                 Vec2::new(300., 32.),
-                UiRect::all(Val::Px(10.)));
+                UiRect::all(Val::Px(10.)),
+                options);
             spawn_instructions(parent, "2. 选择一个喜欢的头像代表你自己", &fonts, 20.0);
             parent.spawn((
                     Node {
@@ -108,7 +110,7 @@ fn new_player_setup(mut commands: Commands, fonts: Res<GameFonts>, asset_server:
                                              })
                     );
                 builder.spawn(
-                    widgets::PushButton::new(ButtonCancel,
+                    widgets::PushButton::new((ButtonCancel, widgets::CancelButton),
                                              "取消",
                                              Vec2::new(100.0,40.0),
                                              true,
@@ -124,6 +126,7 @@ fn new_player_setup(mut commands: Commands, fonts: Res<GameFonts>, asset_server:
 
 fn new_player_exit(mut commands: Commands, query: Query<Entity, With<NewPlayerEntity>>) {
     commands.remove_resource::<SelectedAvatar>();
+    commands.queue(blur_input_box);
     cleanup_entities::<NewPlayerEntity>(commands, query);
 }
 
@@ -157,19 +160,20 @@ fn on_create_button(
 ) {
     for event in reader.read() {
         if query.get(event.entity).is_ok() {
-            if !player_name.value.is_empty() && let Some(ref avatar) = selected.1 {
+            if player_name.is_valid() && let Some(ref avatar) = selected.1 {
                 players.0.push(Player {
                     name: player_name.value.clone(),
                     avatar: avatar.clone(),
                     score: 0,
-                    level: 1
+                    level: 1,
+                    auto_difficulty: false,
+                    lesson: 0,
+                    theme: String::new(),
+                    accessibility: Default::default(),
+                    class: String::new(),
+                    stats: Default::default(),
                 });
-                let json = serde_json::to_string_pretty(&players.0).unwrap();
-                if let Ok(mut file) = File::create(PLAYERS_DATA_FILE) {
-                    if let Err(e) = file.write_all(json.as_bytes()) {
-                        eprintln!("Couldn't write to file: {}", e);
-                    }
-                }
+                save_game_users(&players);
                 next_state.set(GameState::Startup);
             }
         }
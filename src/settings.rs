@@ -0,0 +1,477 @@
+use std::fs;
+use bevy::audio::Volume;
+use bevy::dev_tools::fps_overlay::{FpsOverlayConfig, FpsOverlayPlugin};
+use bevy::prelude::*;
+use bevy::window::{MonitorSelection, WindowMode, WindowResized};
+use serde::{Deserialize, Serialize};
+use crate::{get_app_data_dir, widgets, GameFonts, GameState, GAME_APP_NAME};
+use crate::gaming::common::{SpeedFactor, MAX_SPEED_CHANGES};
+use crate::texts::UiText;
+use crate::ui::*;
+use crate::widgets::{Checkbox, Dropdown, Slider};
+
+const USER_SETTINGS_FILE: &str = "settings.json";
+
+// 界面按照这个窗口大小设计，窗口变化时按比例缩放
+const UI_REFERENCE_WIDTH: f32 = 1280.;
+const UI_REFERENCE_HEIGHT: f32 = 720.;
+const MIN_UI_SCALE: f32 = 0.5;
+const MAX_UI_SCALE: f32 = 2.;
+const UI_SCALE_STEP: f32 = 0.1;
+// 打开按错键扣分时每次扣除的分数
+const WRONG_KEY_PENALTY: u32 = 1;
+
+pub fn settings_plugin(app: &mut App) {
+    app
+        .insert_resource(UserSettings::load())
+        .init_resource::<DisplayModeOverride>()
+        .add_plugins(FpsOverlayPlugin {
+            config: FpsOverlayConfig {
+                text_config: TextFont {
+                    font_size: 16.,
+                    ..default()
+                },
+                enabled: false,
+                ..default()
+            },
+        })
+        .add_systems(PreUpdate, update_ui_scale.run_if(on_message::<WindowResized>
+            .or(resource_changed::<UserSettings>)))
+        .add_systems(PreUpdate, apply_user_settings.run_if(resource_changed::<UserSettings>
+            .or(resource_changed::<DisplayModeOverride>)))
+        .add_systems(Update, adjust_ui_scale.run_if(not(in_state(GameState::Gaming))))
+        .add_systems(OnEnter(GameState::Settings), settings_setup)
+        .add_systems(OnExit(GameState::Settings), cleanup_entities::<SettingsEntity>)
+        .add_systems(Update, (on_settings_slider,
+                              on_settings_checkbox,
+                              on_settings_dropdown,
+                              on_save_button,
+                              on_back_button).run_if(in_state(GameState::Settings)));
+}
+
+/// 界面语言，影响窗口标题以及开始界面和设置界面的文字
+#[derive(Deserialize, Serialize, Clone, Copy, Default, PartialEq, Debug)]
+pub enum Language {
+    #[default]
+    Chinese,
+    English,
+}
+
+const LANGUAGES: [Language; 2] = [Language::Chinese, Language::English];
+
+impl Language {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::Chinese => "简体中文",
+            Language::English => "English",
+        }
+    }
+
+    fn window_title(&self) -> &'static str {
+        match self {
+            Language::Chinese => crate::GAME_APP_TITLE,
+            Language::English => "Super Typing",
+        }
+    }
+}
+
+/// 按键后导弹攻击哪一个字符相同的目标
+#[derive(Deserialize, Serialize, Clone, Copy, Default, PartialEq, Debug)]
+pub enum TargetingPolicy {
+    // 离战斗机最近的目标
+    #[default]
+    Nearest,
+    // 最早出现的目标
+    Oldest,
+    // 所有字符相同的目标，一次按键可以击落多架敌机
+    All,
+}
+
+const TARGETING_POLICIES: [TargetingPolicy; 3] = [TargetingPolicy::Nearest, TargetingPolicy::Oldest, TargetingPolicy::All];
+
+impl TargetingPolicy {
+    fn name(&self, language: Language) -> &'static str {
+        match self {
+            TargetingPolicy::Nearest => UiText::TargetNearest,
+            TargetingPolicy::Oldest => UiText::TargetOldest,
+            TargetingPolicy::All => UiText::TargetAll,
+        }.get(language)
+    }
+}
+
+/// 玩家可以修改的全局选项，保存在数据目录中
+#[derive(Resource, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct UserSettings {
+    // 在窗口自适应缩放的基础上，用户额外设置的界面缩放比例
+    pub ui_scale: f32,
+    // 音量，0到1之间
+    pub volume: f32,
+    pub fullscreen: bool,
+    // 每局游戏开始时的手动加速次数
+    pub speed_changes: i32,
+    pub language: Language,
+    // 显示帧率
+    pub show_fps: bool,
+    // 显示连击倍数
+    pub show_streak: bool,
+    // 显示当前难度
+    pub show_difficulty: bool,
+    pub targeting: TargetingPolicy,
+    // 按错键扣除的分数，0表示不扣分
+    pub wrong_key_penalty: u32,
+}
+
+impl Default for UserSettings {
+    fn default() -> Self {
+        UserSettings {
+            ui_scale: 1.,
+            volume: 1.,
+            fullscreen: false,
+            speed_changes: 0,
+            language: Language::Chinese,
+            show_fps: false,
+            show_streak: true,
+            show_difficulty: true,
+            targeting: TargetingPolicy::Nearest,
+            wrong_key_penalty: 0,
+        }
+    }
+}
+
+/// 命令行指定的全屏或窗口模式，只在本次运行中有效，不保存到设置文件
+#[derive(Resource, Default)]
+pub struct DisplayModeOverride(pub Option<bool>);
+
+impl UserSettings {
+    pub fn load() -> UserSettings {
+        let mut data_file = get_app_data_dir(GAME_APP_NAME);
+        data_file.push(USER_SETTINGS_FILE);
+        if !data_file.exists() {
+            return UserSettings::default();
+        }
+        fs::read_to_string(&data_file)
+            .and_then(|data| serde_json::from_str::<UserSettings>(&data).map_err(|err| err.into()))
+            .unwrap_or_else(|err| {
+                error!("Failed to parse user settings: {}", err);
+                UserSettings::default()
+            })
+    }
+
+    pub fn save(&self) {
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            let mut data_file = get_app_data_dir(GAME_APP_NAME);
+            data_file.push(USER_SETTINGS_FILE);
+            if let Err(e) = fs::write(&data_file, json.as_bytes()) {
+                error!("Failed to save user settings: {}", e);
+            }
+        }
+    }
+}
+
+/// 按照窗口大小和用户设置计算界面的缩放比例，所有Val::Px尺寸和界面字体都会随之缩放
+fn update_ui_scale(
+    mut ui_scale: ResMut<UiScale>,
+    settings: Res<UserSettings>,
+    window: Single<&Window>,
+) {
+    let fit = (window.width() / UI_REFERENCE_WIDTH).min(window.height() / UI_REFERENCE_HEIGHT);
+    ui_scale.0 = (fit * settings.ui_scale).clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+}
+
+/// Ctrl加等号、减号调整界面缩放，Ctrl加0恢复默认大小。游戏中不能调整，以免航道位置变化
+fn adjust_ui_scale(
+    mut settings: ResMut<UserSettings>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+) {
+    if !keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+    let scale = if keyboard_input.just_pressed(KeyCode::Equal) {
+        settings.ui_scale + UI_SCALE_STEP
+    } else if keyboard_input.just_pressed(KeyCode::Minus) {
+        settings.ui_scale - UI_SCALE_STEP
+    } else if keyboard_input.just_pressed(KeyCode::Digit0) {
+        1.
+    } else {
+        return;
+    };
+    settings.ui_scale = scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+    // 只保存缩放比例，设置界面中还没保存的修改和命令行指定的显示模式不写入文件
+    let mut saved = UserSettings::load();
+    saved.ui_scale = settings.ui_scale;
+    saved.save();
+}
+
+/// 把音量、窗口模式、语言和帧率显示的设置应用到游戏
+fn apply_user_settings(
+    mut global_volume: ResMut<GlobalVolume>,
+    mut fps_overlay: ResMut<FpsOverlayConfig>,
+    settings: Res<UserSettings>,
+    display_override: Res<DisplayModeOverride>,
+    mut window: Single<&mut Window>,
+) {
+    global_volume.volume = Volume::Linear(settings.volume);
+    fps_overlay.enabled = settings.show_fps;
+    let mode = if display_override.0.unwrap_or(settings.fullscreen) {
+        WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+    } else {
+        WindowMode::Windowed
+    };
+    if window.mode != mode {
+        window.mode = mode;
+    }
+    if window.title != settings.language.window_title() {
+        window.title = settings.language.window_title().to_owned();
+    }
+}
+
+#[derive(Component, Default)]
+struct SettingsEntity;
+
+#[derive(Component)]
+struct ButtonSave;
+
+#[derive(Component)]
+struct ButtonBack;
+
+/// 设置界面中的滑动条
+#[derive(Component, Clone, Copy, PartialEq)]
+enum SettingSlider {
+    Volume,
+    Speed,
+}
+
+impl SettingSlider {
+    fn value_text(&self, value: f32) -> String {
+        match self {
+            SettingSlider::Volume => format!("{}%", value.round()),
+            SettingSlider::Speed => format!("x{:.2}", SpeedFactor::from_changes(value as i32).speed_factor),
+        }
+    }
+}
+
+/// 滑动条旁边显示当前数值的文字
+#[derive(Component)]
+struct SettingValueText(SettingSlider);
+
+/// 设置界面中的下拉框
+#[derive(Component)]
+enum SettingDropdown {
+    DisplayMode,
+    Language,
+    Targeting,
+}
+
+/// 设置界面中的复选框，包括游戏界面上可以隐藏的信息和计分规则
+#[derive(Component, Clone, Copy)]
+enum SettingCheckbox {
+    Fps,
+    Streak,
+    Difficulty,
+    WrongKeyPenalty,
+}
+
+impl SettingCheckbox {
+    fn label(&self, language: Language) -> &'static str {
+        match self {
+            SettingCheckbox::Fps => UiText::ShowFps,
+            SettingCheckbox::Streak => UiText::ShowStreak,
+            SettingCheckbox::Difficulty => UiText::ShowDifficulty,
+            SettingCheckbox::WrongKeyPenalty => UiText::WrongKeyPenalty,
+        }.get(language)
+    }
+
+    fn value(&self, settings: &UserSettings) -> bool {
+        match self {
+            SettingCheckbox::Fps => settings.show_fps,
+            SettingCheckbox::Streak => settings.show_streak,
+            SettingCheckbox::Difficulty => settings.show_difficulty,
+            SettingCheckbox::WrongKeyPenalty => settings.wrong_key_penalty > 0,
+        }
+    }
+
+    fn set(&self, settings: &mut UserSettings, value: bool) {
+        match self {
+            SettingCheckbox::Fps => settings.show_fps = value,
+            SettingCheckbox::Streak => settings.show_streak = value,
+            SettingCheckbox::Difficulty => settings.show_difficulty = value,
+            SettingCheckbox::WrongKeyPenalty => settings.wrong_key_penalty = if value { WRONG_KEY_PENALTY } else { 0 },
+        }
+    }
+}
+
+fn spawn_slider_row(builder: &mut ChildSpawnerCommands, fonts: &GameFonts, option: SettingSlider,
+                    max: f32, step: f32, value: f32) {
+    builder.spawn(Node {
+        align_items: AlignItems::Center,
+        justify_self: JustifySelf::Start,
+        ..default()
+    }).with_children(|builder| {
+        builder.spawn(Slider::new(option, 0., max, step, value, 240., UiRect::all(Val::Px(10.))));
+        spawn_marked_text(builder, SettingValueText(option), &option.value_text(value), INFO_TEXT_COLOR,
+                          fonts.ui_font.clone(), NORMAL_FONT_SIZE);
+    });
+}
+
+fn settings_setup(mut commands: Commands, fonts: Res<GameFonts>, settings: Res<UserSettings>) {
+    spawn_settings_screen(&mut commands, &fonts, &settings);
+}
+
+fn spawn_settings_screen(commands: &mut Commands, fonts: &GameFonts, settings: &UserSettings) {
+    let language = settings.language;
+    spawn_startup_root::<SettingsEntity>(commands)
+        .with_children(|parent| {
+            spawn_game_title(parent, fonts, 1., 20., 15., 20., true);
+            spawn_instructions(parent, UiText::SettingsHint.get(language), fonts, 40.0);
+            parent.spawn(Node {
+                display: Display::Grid,
+                width: Val::Px(600.),
+                margin: UiRect::top(Val::Px(20.)),
+                grid_template_columns: vec![GridTrack::flex(1.0), GridTrack::flex(2.0)],
+                align_items: AlignItems::Center,
+                justify_items: JustifyItems::Start,
+                row_gap: Val::Px(10.0),
+                ..default()
+            }).with_children(|builder| {
+                spawn_info_text(builder, UiText::Volume.get(language), INFO_TEXT_COLOR, fonts.ui_font.clone(), NORMAL_FONT_SIZE);
+                spawn_slider_row(builder, fonts, SettingSlider::Volume, 100., 5., settings.volume * 100.);
+                spawn_info_text(builder, UiText::DefaultSpeed.get(language), INFO_TEXT_COLOR, fonts.ui_font.clone(), NORMAL_FONT_SIZE);
+                spawn_slider_row(builder, fonts, SettingSlider::Speed, MAX_SPEED_CHANGES as f32, 1.,
+                                 settings.speed_changes as f32);
+                spawn_info_text(builder, UiText::DisplayMode.get(language), INFO_TEXT_COLOR, fonts.ui_font.clone(), NORMAL_FONT_SIZE);
+                builder.spawn(Dropdown::new(SettingDropdown::DisplayMode,
+                                            vec![UiText::Windowed.get(language).to_owned(),
+                                                 UiText::Fullscreen.get(language).to_owned()],
+                                            settings.fullscreen as usize,
+                                            Vec2::new(160., 32.),
+                                            UiRect::all(Val::Px(10.))));
+                spawn_info_text(builder, UiText::Language.get(language), INFO_TEXT_COLOR, fonts.ui_font.clone(), NORMAL_FONT_SIZE);
+                builder.spawn(Dropdown::new(SettingDropdown::Language,
+                                            LANGUAGES.iter().map(|l| l.name().to_owned()).collect(),
+                                            LANGUAGES.iter().position(|l| *l == settings.language).unwrap_or(0),
+                                            Vec2::new(160., 32.),
+                                            UiRect::all(Val::Px(10.))));
+                spawn_info_text(builder, UiText::Targeting.get(language), INFO_TEXT_COLOR, fonts.ui_font.clone(), NORMAL_FONT_SIZE);
+                builder.spawn(Dropdown::new(SettingDropdown::Targeting,
+                                            TARGETING_POLICIES.iter().map(|t| t.name(language).to_owned()).collect(),
+                                            TARGETING_POLICIES.iter().position(|t| *t == settings.targeting).unwrap_or(0),
+                                            Vec2::new(160., 32.),
+                                            UiRect::all(Val::Px(10.))));
+                for option in [SettingCheckbox::Fps, SettingCheckbox::Streak, SettingCheckbox::Difficulty] {
+                    spawn_info_text(builder, if matches!(option, SettingCheckbox::Fps) { UiText::HudInfo.get(language) } else { "" },
+                                    INFO_TEXT_COLOR, fonts.ui_font.clone(), NORMAL_FONT_SIZE);
+                    builder.spawn(Checkbox::new(option, option.label(language), option.value(settings),
+                                                UiRect::all(Val::Px(10.))));
+                }
+                spawn_info_text(builder, UiText::Scoring.get(language), INFO_TEXT_COLOR, fonts.ui_font.clone(), NORMAL_FONT_SIZE);
+                let option = SettingCheckbox::WrongKeyPenalty;
+                builder.spawn(Checkbox::new(option, option.label(language), option.value(settings), UiRect::all(Val::Px(10.))));
+            });
+            parent.spawn(Node {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                margin: UiRect::top(Val::Px(30.)),
+                ..default()
+            }).with_children(|builder| {
+                builder.spawn(widgets::PushButton::new(ButtonSave, UiText::Save.get(language), Vec2::new(100.0, 40.0), true,
+                                                       UiRect::right(Val::Px(5.0))));
+                builder.spawn(widgets::PushButton::new((ButtonBack, widgets::CancelButton), UiText::Back.get(language),
+                                                       Vec2::new(100.0, 40.0), true, UiRect::left(Val::Px(5.0))));
+            });
+        });
+}
+
+/// 设置修改后立即生效，点击保存时才写入文件
+fn on_settings_slider(
+    mut settings: ResMut<UserSettings>,
+    mut reader: MessageReader<widgets::SliderChanged>,
+    sliders: Query<&SettingSlider>,
+    mut texts: Query<(&mut Text, &SettingValueText)>,
+) {
+    for event in reader.read() {
+        let Ok(option) = sliders.get(event.entity) else {
+            continue;
+        };
+        match option {
+            SettingSlider::Volume => settings.volume = event.value / 100.,
+            SettingSlider::Speed => settings.speed_changes = event.value as i32,
+        }
+        for (mut text, value_text) in &mut texts {
+            if value_text.0 == *option {
+                **text = option.value_text(event.value);
+            }
+        }
+    }
+}
+
+fn on_settings_checkbox(
+    mut settings: ResMut<UserSettings>,
+    mut reader: MessageReader<widgets::CheckboxToggled>,
+    options: Query<&SettingCheckbox>,
+) {
+    for event in reader.read() {
+        if let Ok(option) = options.get(event.entity) {
+            option.set(&mut settings, event.checked);
+        }
+    }
+}
+
+/// 切换语言后重建设置界面，让界面文字立即换成新的语言
+fn on_settings_dropdown(
+    mut commands: Commands,
+    mut settings: ResMut<UserSettings>,
+    mut display_override: ResMut<DisplayModeOverride>,
+    mut reader: MessageReader<widgets::DropdownSelected>,
+    dropdowns: Query<&SettingDropdown>,
+    entities: Query<Entity, With<SettingsEntity>>,
+    fonts: Res<GameFonts>,
+) {
+    let language = settings.language;
+    for event in reader.read() {
+        match dropdowns.get(event.entity) {
+            Ok(SettingDropdown::DisplayMode) => {
+                // 在设置界面中选择了显示模式后不再使用命令行指定的模式
+                settings.fullscreen = event.index == 1;
+                display_override.0 = None;
+            }
+            Ok(SettingDropdown::Language) => settings.language = LANGUAGES[event.index],
+            Ok(SettingDropdown::Targeting) => settings.targeting = TARGETING_POLICIES[event.index],
+            Err(_) => (),
+        }
+    }
+    if settings.language != language {
+        for entity in &entities {
+            commands.entity(entity).despawn();
+        }
+        spawn_settings_screen(&mut commands, &fonts, &settings);
+    }
+}
+
+fn on_save_button(
+    mut next_state: ResMut<NextState<GameState>>,
+    mut reader: MessageReader<widgets::ButtonClicked>,
+    query: Query<(), With<ButtonSave>>,
+    settings: Res<UserSettings>,
+) {
+    if let Some(event) = reader.read().last()
+        && query.get(event.entity).is_ok() {
+        settings.save();
+        next_state.set(GameState::Startup);
+    }
+}
+
+/// 不保存返回时恢复文件中的设置
+fn on_back_button(
+    mut next_state: ResMut<NextState<GameState>>,
+    mut reader: MessageReader<widgets::ButtonClicked>,
+    query: Query<(), With<ButtonBack>>,
+    mut settings: ResMut<UserSettings>,
+) {
+    if let Some(event) = reader.read().last()
+        && query.get(event.entity).is_ok() {
+        *settings = UserSettings::load();
+        next_state.set(GameState::Startup);
+    }
+}
@@ -4,15 +4,28 @@ use super::*;
 use ui::*;
 use widgets;
 use crate::gaming::common::HEALTH_MAX_VALUE;
-use crate::widgets::{ListItem, ListView, TextConfig};
+use crate::gaming::resume::{load_saved_round, remove_saved_round, ResumeRound};
+use crate::classroom::{class_name, NO_CLASS_NAME};
+use crate::cli::QuickStart;
+use crate::settings::{Language, UserSettings};
+use crate::texts::{level_text, resume_text, score_text, UiText};
+use crate::widgets::{Dialog, DialogResult, Dropdown, InputBox, InputBoxOptions, ListItem, ListView, ListViewMarker, SortKey, TextConfig};
 
 pub fn startup_plugin(app: &mut App) {
     app
-        .add_systems(OnEnter(GameState::Startup), startup_setup)
-        .add_systems(OnExit(GameState::Startup), cleanup_entities::<StartupEntity>)
+        .add_systems(OnEnter(GameState::Startup), (startup_setup.run_if(not(resource_exists::<QuickStart>)),
+                                                    quick_start_game.run_if(resource_exists::<QuickStart>)).chain())
+        .add_systems(OnExit(GameState::Startup), (cleanup_entities::<StartupEntity>, clear_pending_player))
         .add_systems(Update, on_create_user_button.run_if(in_state(GameState::Startup)))
         .add_systems(Update, on_exit_game_button.run_if(in_state(GameState::Startup)))
-        .add_systems(Update, on_player_selected.run_if(in_state(GameState::Startup)));
+        .add_systems(Update, on_guardian_button.run_if(in_state(GameState::Startup)))
+        .add_systems(Update, on_settings_button.run_if(in_state(GameState::Startup)))
+        .add_systems(Update, on_heatmap_button.run_if(in_state(GameState::Startup)))
+        .add_systems(Update, on_player_selected.run_if(in_state(GameState::Startup)))
+        .add_systems(Update, (on_player_filter, on_class_filter, on_sort_players_button).run_if(in_state(GameState::Startup)))
+        .add_systems(Update, on_game_mode_button.run_if(in_state(GameState::Startup)))
+        .add_plugins(widgets::dialog_plugin::<ResumeChoice>)
+        .add_systems(Update, on_resume_choice.run_if(resource_exists::<PendingPlayer>));
 }
 
 #[derive(Component, Default)]
@@ -21,39 +34,102 @@ struct StartupEntity;
 #[derive(Component)]
 struct ListViewPlayer;
 
+/// 过滤玩家列表的输入框
+#[derive(Component)]
+struct PlayerFilterText;
+
+/// 只显示一个班级中玩家的下拉框，第一项表示显示所有班级
+#[derive(Component)]
+struct ClassFilterDropdown;
+
+/// 按照某一列排序玩家列表的按钮，数值是排序使用的SortKey序号
+#[derive(Component)]
+struct ButtonSortPlayers(usize);
+
+const PLAYER_SORT_COLUMNS: [UiText; 3] = [UiText::SortName, UiText::SortScore, UiText::SortLevel];
+
 #[derive(Component)]
 struct ButtonCreateUser;
 
 #[derive(Component)]
 struct ButtonExitGame;
 
-fn startup_setup(mut commands: Commands, players: Res<Players>, fonts: Res<GameFonts>, asset_server: Res<AssetServer>) {
+#[derive(Component)]
+struct ButtonGuardian;
+
+#[derive(Component)]
+struct ButtonSettings;
+
+/// 有中途退出的游戏时的选择
+#[derive(Clone, Copy)]
+enum ResumeChoice {
+    Restart,
+    Resume,
+}
+
+#[derive(Component)]
+struct ButtonHeatmap;
+
+/// 切换闯关游戏和打字课程的按钮
+#[derive(Component)]
+struct ButtonGameMode(GameMode);
+
+fn game_mode_text(mode: GameMode, language: Language) -> &'static str {
+    match mode {
+        GameMode::Arcade => UiText::ModeArcade,
+        GameMode::Lesson => UiText::ModeLesson,
+    }.get(language)
+}
+
+/// 等待选择是否继续上次游戏的玩家
+#[derive(Resource)]
+struct PendingPlayer(Player);
+
+fn startup_setup(
+    mut commands: Commands,
+    players: Res<Players>,
+    fonts: Res<GameFonts>,
+    asset_server: Res<AssetServer>,
+    game_player: Option<Res<GamePlayer>>,
+    settings: Res<UserSettings>,
+) {
+    // 保持上一次选择的游戏模式
+    let mode = game_player.map(|p| p.mode).unwrap_or_default();
     spawn_startup_root::<StartupEntity>(&mut commands)
         .with_children(|builder| {
             spawn_game_title(builder, &fonts, 1., 20., 15., 20., true);
             if players.0.is_empty() {
-                default_screen_setup(builder, fonts, asset_server);
+                default_screen_setup(builder, fonts, asset_server, settings.language);
             } else {
-                player_list_setup(builder, players, fonts, asset_server);
+                player_list_setup(builder, players, fonts, asset_server, mode, settings.language);
             }
         });
 }
 
-fn default_screen_setup(builder: &mut ChildSpawnerCommands, fonts: Res<GameFonts>, asset_server: Res<AssetServer>) {
+fn default_screen_setup(builder: &mut ChildSpawnerCommands, fonts: Res<GameFonts>, asset_server: Res<AssetServer>,
+                        language: Language) {
     // 首次开始游戏时，提示建立自己的账户
-    spawn_instructions(builder, "为了开始游戏，首先需要创建一个自己的账户。", &fonts, 80.0);
+    spawn_instructions(builder, UiText::CreateAccountHint.get(language), &fonts, 80.0);
     // 创建新账户按钮
     builder.spawn(
         widgets::PushButton::new(ButtonCreateUser,
-                                 "创建新的账号",
+                                 UiText::CreateAccount.get(language),
                                  Vec2::new(500.0,50.0),
                                  true,
                                  UiRect::top(Val::Px(30.0))
         ));
+    // 游戏设置按钮
+    builder.spawn(
+        widgets::PushButton::new(ButtonSettings,
+                                 UiText::GameSettings.get(language),
+                                 Vec2::new(500.0,50.0),
+                                 true,
+                                 UiRect::top(Val::Px(20.0))
+        ));
     // 退出游戏按钮
     builder.spawn(
         widgets::PushButton::new(ButtonExitGame,
-                                 "退出游戏",
+                                 UiText::ExitGame.get(language),
                                  Vec2::new(500.0,50.0),
                                  true,
                                  UiRect::top(Val::Px(20.0))
@@ -83,7 +159,7 @@ fn default_screen_setup(builder: &mut ChildSpawnerCommands, fonts: Res<GameFonts
                 justify_content: JustifyContent::Center,
                 ..default()
             }).with_children(|builder| {
-            spawn_info_text(builder, " 游 戏 快 速 入 门 ", INFO_TEXT_COLOR,
+            spawn_info_text(builder, UiText::QuickGuide.get(language), INFO_TEXT_COLOR,
                             fonts.normal_font.clone(), INFO_FONT_SIZE + 4.0);
         });
         builder.spawn((
@@ -103,7 +179,7 @@ fn default_screen_setup(builder: &mut ChildSpawnerCommands, fonts: Res<GameFonts
                 padding: UiRect::all(Val::Px(15.0)),
                 ..default()
             }).with_children(|builder| {
-            spawn_info_text(builder, "超级简单！攻击你的小飞机带有字母或符号，按对应的按键就能消灭它！游戏中你会遇到：",
+            spawn_info_text(builder, UiText::GuideIntro.get(language),
                             INFO_TEXT_COLOR, fonts.normal_font.clone(), INFO_FONT_SIZE);
         });
         builder.spawn(
@@ -129,36 +205,36 @@ fn default_screen_setup(builder: &mut ChildSpawnerCommands, fonts: Res<GameFonts
                 spawn_image_node(builder, &asset_server, "images/plane_desc.png", Vec2::splat(60.), 10.0, 5.0);
                 spawn_item_desc_node(builder)
                     .with_children(|builder| {
-                        spawn_info_text(builder, "敌机", Color::srgb_u8(251, 188, 8),
+                        spawn_info_text(builder, UiText::Enemy.get(language), Color::srgb_u8(251, 188, 8),
                                         fonts.title_font.clone(), INFO_FONT_SIZE+2.0);
-                        spawn_info_text(builder, "小飞机接近后就会发射飞弹攻击你，按下对应的字母击落它！",
+                        spawn_info_text(builder, UiText::EnemyDesc.get(language),
                                         INFO_TEXT_COLOR, fonts.normal_font.clone(), INFO_FONT_SIZE-2.0);
                     });
 
                 spawn_image_node(builder, &asset_server, "images/bomb.png", Vec2::splat(45.), 10.0, 10.0);
                 spawn_item_desc_node(builder)
                     .with_children(|builder| {
-                        spawn_info_text(builder, "炸弹", Color::srgb_u8(234, 67, 53),
+                        spawn_info_text(builder, UiText::Bomb.get(language), Color::srgb_u8(234, 67, 53),
                                         fonts.title_font.clone(), INFO_FONT_SIZE+2.0);
-                        spawn_info_text(builder, "炸弹可以将当前所有的敌机一次性全部摧毁，相当于你的大招！",
+                        spawn_info_text(builder, UiText::BombDesc.get(language),
                                         INFO_TEXT_COLOR, fonts.normal_font.clone(), INFO_FONT_SIZE-2.0);
                     });
 
                 spawn_image_node(builder, &asset_server, "images/first-aid-kit.png", Vec2::splat(45.), 10.0, 10.0);
                 spawn_item_desc_node(builder)
                     .with_children(|builder| {
-                        spawn_info_text(builder, "补血剂", Color::srgb_u8(52, 168, 82),
+                        spawn_info_text(builder, UiText::HealthPack.get(language), Color::srgb_u8(52, 168, 82),
                                         fonts.title_font.clone(), INFO_FONT_SIZE+2.0);
-                        spawn_info_text(builder, "补血剂可以恢复你的损伤，提高生命值，在战斗中坚持的更久！",
+                        spawn_info_text(builder, UiText::HealthPackDesc.get(language),
                                         INFO_TEXT_COLOR, fonts.normal_font.clone(), INFO_FONT_SIZE-2.0);
                     });
 
                 spawn_image_node(builder, &asset_server, "images/shield.png", Vec2::splat(45.), 10.0, 10.0);
                 spawn_item_desc_node(builder)
                     .with_children(|builder| {
-                        spawn_info_text(builder, "护盾", Color::srgb_u8(66, 133, 243),
+                        spawn_info_text(builder, UiText::Shield.get(language), Color::srgb_u8(66, 133, 243),
                                         fonts.title_font.clone(), INFO_FONT_SIZE+2.0);
-                        spawn_info_text(builder, "护盾赋予你长达25秒坚不可摧的保护，期间所有的攻击对你无效。",
+                        spawn_info_text(builder, UiText::ShieldDesc.get(language),
                                         INFO_TEXT_COLOR, fonts.normal_font.clone(), INFO_FONT_SIZE - 2.0);
                     });
             });
@@ -166,14 +242,63 @@ fn default_screen_setup(builder: &mut ChildSpawnerCommands, fonts: Res<GameFonts
     });
 }
 
-fn player_list_setup(builder: &mut ChildSpawnerCommands, players: Res<Players>, fonts: Res<GameFonts>, asset_server: Res<AssetServer>) {
-    spawn_instructions(builder, "欢迎回来，选择你的账户以继续游戏", &fonts, 80.0);
+fn player_list_setup(
+    builder: &mut ChildSpawnerCommands,
+    players: Res<Players>,
+    fonts: Res<GameFonts>,
+    asset_server: Res<AssetServer>,
+    mode: GameMode,
+    language: Language,
+) {
+    spawn_instructions(builder, UiText::WelcomeBack.get(language), &fonts, 80.0);
+    // 导入了班级名单时按班级分组显示玩家
+    let mut classes: Vec<&str> = players.0.iter().filter(|p| !p.class.is_empty()).map(class_name).collect();
+    classes.sort();
+    classes.dedup();
+    let classroom = !classes.is_empty();
+    builder.spawn(Node {
+        flex_direction: FlexDirection::Row,
+        align_items: AlignItems::Center,
+        margin: UiRect::top(Val::Px(10.)),
+        ..default()
+    }).with_children(|builder| {
+        InputBox::new(
+            builder,
+            PlayerFilterText,
+            TextConfig {
+                text: "".to_string(),
+                font: fonts.info_font.clone(),
+                font_size: 16.0,
+                color: Color::WHITE,
+                shadow: false
+            },
+            UiText::FindPlayer.get(language),
+            Vec2::new(if classroom { 240. } else { 300. }, 32.),
+            UiRect::right(Val::Px(20.)),
+            InputBoxOptions::default());
+        if classroom {
+            let options = std::iter::once(UiText::AllClasses.get(language).to_owned())
+                .chain(players.0.iter().any(|p| p.class.is_empty()).then(|| NO_CLASS_NAME.to_owned()))
+                .chain(classes.iter().map(|c| c.to_string()))
+                .collect();
+            builder.spawn(Dropdown::new(ClassFilterDropdown, options, 0, Vec2::new(140., 32.), UiRect::right(Val::Px(15.))));
+        }
+        for (key, title) in PLAYER_SORT_COLUMNS.iter().enumerate() {
+            builder.spawn(
+                widgets::PushButton::new(ButtonSortPlayers(key),
+                                         title.get(language),
+                                         Vec2::new(100.0, 32.0),
+                                         true,
+                                         UiRect::horizontal(Val::Px(5.)))
+            );
+        }
+    });
     builder.spawn((
         Node {
             width: Val::Px(780.),
             height: Val::Px(300.),
             border: UiRect::all(Val::Px(2.)),
-            margin: UiRect::top(Val::Px(15.)),
+            margin: UiRect::top(Val::Px(10.)),
             padding: UiRect::all(Val::Px(20.)),
             border_radius: BorderRadius::all(Val::Px(8.0)),
             justify_content: JustifyContent::Center,
@@ -193,27 +318,73 @@ fn player_list_setup(builder: &mut ChildSpawnerCommands, players: Res<Players>,
                                      color: Color::srgb_u8(188, 190, 196),
                                      shadow: false
                                  },
-                                 vec![GridTrack::min_content(),
-                                      GridTrack::flex(3.0),
-                                      GridTrack::flex(2.0),
-                                      GridTrack::flex(1.0)],
-                                 RepeatedGridTrack::flex(players.0.len() as u16, 1.0),
+                                 if classroom {
+                                     vec![GridTrack::min_content(),
+                                          GridTrack::flex(3.0),
+                                          GridTrack::flex(2.0),
+                                          GridTrack::flex(2.0),
+                                          GridTrack::flex(1.0)]
+                                 } else {
+                                     vec![GridTrack::min_content(),
+                                          GridTrack::flex(3.0),
+                                          GridTrack::flex(2.0),
+                                          GridTrack::flex(1.0)]
+                                 },
+                                 icon_size + 4.,
                                  Some((asset_server.load("images/fighter-jet.png"), Vec2::splat(icon_size))),
                                  None,
                                  None);
         for player in &players.0 {
+            // 超过星级图片数量的级别直接显示级别数字
+            let level = if player.level > LEVEL_STAR_IMAGES {
+                ListItem::Text(level_text(language, player.level))
+            } else {
+                let (star_image, stars) = level_star_image(player.level);
+                ListItem::Image(asset_server.load(star_image), Vec2::new((icon_size-4.)*(stars as f32), icon_size-4.))
+            };
+            let mut items = vec![
+                ListItem::Image(asset_server.load(format!("avatars/{}.png", player.avatar)), Vec2::splat(icon_size)),
+                ListItem::Text(player.name.clone()),
+            ];
+            let group = if classroom {
+                items.push(ListItem::Text(class_name(player).to_owned()));
+                class_name(player).to_owned()
+            } else {
+                String::new()
+            };
+            items.extend([ListItem::Text(score_text(language, player.score)), level]);
             list.append(builder.commands_mut(),
                         player.name.clone(),
+                        group,
+                        items,
                         vec![
-                            ListItem::Image(asset_server.load(format!("avatars/{}.png", player.avatar)),
-                                            Vec2::splat(icon_size)),
-                            ListItem::Text(player.name.clone()),
-                            ListItem::Text(format!("{}分", player.score)),
-                            ListItem::Image(asset_server.load(format!("images/star-{}.png", player.level)),
-                                            Vec2::new((icon_size-4.)*(player.level as f32), icon_size-4.)),
+                            SortKey::Text(player.name.clone()),
+                            SortKey::Number(player.score as i64),
+                            SortKey::Number(player.level as i64),
                         ]);
         }
     });
+    builder.spawn(Node {
+        flex_direction: FlexDirection::Row,
+        align_items: AlignItems::Center,
+        margin: UiRect::top(Val::Px(15.0)),
+        ..default()
+    }).with_children(|builder| {
+        builder.spawn(
+            widgets::PushButton::new(ButtonGameMode(mode),
+                                     game_mode_text(mode, language),
+                                     Vec2::new(300.0,40.0),
+                                     true,
+                                     UiRect::right(Val::Px(10.0))
+            ));
+        builder.spawn(
+            widgets::PushButton::new(ButtonHeatmap,
+                                     UiText::Heatmap.get(language),
+                                     Vec2::new(300.0,40.0),
+                                     true,
+                                     UiRect::left(Val::Px(10.0))
+            ));
+    });
     builder.spawn((
         Node {
             display: Display::Grid,
@@ -236,7 +407,7 @@ fn player_list_setup(builder: &mut ChildSpawnerCommands, players: Res<Players>,
             },
             BackgroundColor(Color::srgb_u8(76, 69, 113)),
             ));
-        spawn_instructions(builder, "或者", &fonts, 0.0);
+        spawn_instructions(builder, UiText::Or.get(language), &fonts, 0.0);
         builder.spawn((
             Node {
                 width: Val::Percent(100.),
@@ -249,14 +420,28 @@ fn player_list_setup(builder: &mut ChildSpawnerCommands, players: Res<Players>,
     info!("total players: {}", players.0.len());
     builder.spawn(
         widgets::PushButton::new(ButtonCreateUser,
-                                 "创建新的账号",
+                                 UiText::CreateAccount.get(language),
                                  Vec2::new(500.0,50.0),
                                  players.0.len() < MAX_PLAYERS_COUNT,
                                  UiRect::top(Val::Px(20.0))
         ));
+    builder.spawn(
+        widgets::PushButton::new(ButtonGuardian,
+                                 UiText::Guardian.get(language),
+                                 Vec2::new(500.0,50.0),
+                                 true,
+                                 UiRect::top(Val::Px(20.0))
+        ));
+    builder.spawn(
+        widgets::PushButton::new(ButtonSettings,
+                                 UiText::GameSettings.get(language),
+                                 Vec2::new(500.0,50.0),
+                                 true,
+                                 UiRect::top(Val::Px(20.0))
+        ));
     builder.spawn(
         widgets::PushButton::new(ButtonExitGame,
-                                 "退出游戏",
+                                 UiText::ExitGame.get(language),
                                  Vec2::new(500.0,50.0),
                                  true,
                                  UiRect::top(Val::Px(20.0))
@@ -288,6 +473,48 @@ fn on_create_user_button(
     }
 }
 
+fn on_guardian_button(
+    mut next_state: ResMut<NextState<GameState>>,
+    mut reader: MessageReader<widgets::ButtonClicked>,
+    query: Query<(), With<ButtonGuardian>>,
+) {
+    if let Some(event) = reader.read().last()
+        && query.get(event.entity).is_ok() {
+        next_state.set(GameState::Guardian);
+    }
+}
+
+fn on_settings_button(
+    mut next_state: ResMut<NextState<GameState>>,
+    mut reader: MessageReader<widgets::ButtonClicked>,
+    query: Query<(), With<ButtonSettings>>,
+) {
+    if let Some(event) = reader.read().last()
+        && query.get(event.entity).is_ok() {
+        next_state.set(GameState::Settings);
+    }
+}
+
+/// 打开键盘热力图，默认显示上一次游戏的玩家
+fn on_heatmap_button(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut reader: MessageReader<widgets::ButtonClicked>,
+    query: Query<(), With<ButtonHeatmap>>,
+    players: Res<Players>,
+    game_player: Option<Res<GamePlayer>>,
+) {
+    if let Some(event) = reader.read().last()
+        && query.get(event.entity).is_ok()
+        && let Some(first) = players.0.first() {
+        let player = game_player.map(|p| p.player.name.clone())
+            .filter(|name| players.0.iter().any(|p| p.name == *name))
+            .unwrap_or_else(|| first.name.clone());
+        commands.insert_resource(heatmap::HeatmapView::new(&player));
+        next_state.set(GameState::Heatmap);
+    }
+}
+
 fn on_exit_game_button(
     mut reader: MessageReader<widgets::ButtonClicked>,
     mut exit: MessageWriter<AppExit>,
@@ -299,21 +526,173 @@ fn on_exit_game_button(
     }
 }
 
+fn on_game_mode_button(
+    mut reader: MessageReader<widgets::ButtonClicked>,
+    mut buttons: Query<(&mut ButtonGameMode, &Children)>,
+    mut texts: Query<&mut Text>,
+    settings: Res<UserSettings>,
+) {
+    if let Some(event) = reader.read().last()
+        && let Ok((mut button, children)) = buttons.get_mut(event.entity) {
+        button.0 = match button.0 {
+            GameMode::Arcade => GameMode::Lesson,
+            GameMode::Lesson => GameMode::Arcade,
+        };
+        if let Some(mut text) = children.first().and_then(|c| texts.get_mut(*c).ok()) {
+            **text = game_mode_text(button.0, settings.language).to_string();
+        }
+    }
+}
+
+fn on_player_filter(
+    filter: Query<&InputBox, (With<PlayerFilterText>, Changed<InputBox>)>,
+    mut listview: Single<&mut ListViewMarker, With<ListViewPlayer>>,
+) {
+    if let Ok(input) = filter.single() {
+        listview.set_filter(&input.value);
+    }
+}
+
+fn on_class_filter(
+    mut reader: MessageReader<widgets::DropdownSelected>,
+    dropdowns: Query<&Dropdown, With<ClassFilterDropdown>>,
+    mut listview: Single<&mut ListViewMarker, With<ListViewPlayer>>,
+) {
+    if let Some(event) = reader.read().last()
+        && let Ok(dropdown) = dropdowns.get(event.entity) {
+        let group = (event.index > 0).then(|| dropdown.options[event.index].clone());
+        listview.set_group(group);
+    }
+}
+
+/// 点击排序按钮按照该列排序，再次点击切换升序和降序
+fn on_sort_players_button(
+    mut reader: MessageReader<widgets::ButtonClicked>,
+    buttons: Query<(Entity, &ButtonSortPlayers, &Children)>,
+    mut texts: Query<&mut Text>,
+    mut listview: Single<&mut ListViewMarker, With<ListViewPlayer>>,
+    settings: Res<UserSettings>,
+) {
+    if let Some(event) = reader.read().last()
+        && let Ok((_, button, _)) = buttons.get(event.entity) {
+        // 名称默认按升序排列，分数和级别默认从高到低
+        let descending = match listview.sort() {
+            Some((key, descending)) if key == button.0 => !descending,
+            _ => button.0 != 0,
+        };
+        listview.set_sort(button.0, descending);
+        for (entity, button, children) in &buttons {
+            let title = PLAYER_SORT_COLUMNS[button.0].get(settings.language);
+            let text = if entity != event.entity {
+                title.to_string()
+            } else if descending {
+                format!("{} ↓", title)
+            } else {
+                format!("{} ↑", title)
+            };
+            if let Some(mut t) = children.first().and_then(|c| texts.get_mut(*c).ok()) {
+                **t = text;
+            }
+        }
+    }
+}
+
 fn on_player_selected(
     mut commands: Commands,
     mut next_state: ResMut<NextState<GameState>>,
     mut reader: MessageReader<widgets::ListViewSelectionChanged>,
     query: Query<(), With<ListViewPlayer>>,
-    players: Res<Players>
+    mode: Single<&ButtonGameMode>,
+    pending: Option<Res<PendingPlayer>>,
+    players: Res<Players>,
+    fonts: Res<GameFonts>,
+    asset_server: Res<AssetServer>,
+    settings: Res<UserSettings>,
 ) {
     if let Some(event) = reader.read().last()
-        && query.get(event.entity).is_ok() {
+        && query.get(event.entity).is_ok()
+        && pending.is_none() {
         info!("Player {} selected to continue game", event.value);
-        commands.insert_resource(GamePlayer {
-            player: players.get(&event.value).clone(),
-            health: HEALTH_MAX_VALUE,
-            ..default()
-        });
+        let player = players.get(&event.value).clone();
+        if mode.0 == GameMode::Lesson {
+            start_game(&mut commands, player, GameMode::Lesson, false);
+            next_state.set(GameState::Gaming);
+            return;
+        }
+        if load_saved_round(&player.name, player.level).is_some() {
+            // 有中途退出时保存的游戏，询问玩家是否继续
+            resume_dialog_setup(&mut commands, &player, &fonts, &asset_server, settings.language);
+            commands.insert_resource(PendingPlayer(player));
+            return;
+        }
+        start_game(&mut commands, player, GameMode::Arcade, false);
+        next_state.set(GameState::Gaming)
+    }
+}
+
+fn clear_pending_player(mut commands: Commands) {
+    commands.remove_resource::<PendingPlayer>();
+}
+
+/// 命令行指定了玩家时不显示开始界面，直接进入游戏，返回开始界面时再正常显示
+fn quick_start_game(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    quick_start: Res<QuickStart>,
+    players: Res<Players>,
+    settings: Res<GameSettings>,
+) {
+    commands.remove_resource::<QuickStart>();
+    let mut player = players.get(&quick_start.player).clone();
+    // 指定的级别只在这次运行中使用，不会写回玩家数据
+    if let Some(level) = quick_start.level {
+        match quick_start.mode {
+            GameMode::Arcade => player.level = level.min(settings.max_level()),
+            GameMode::Lesson => player.lesson = level - 1,
+        }
+    }
+    info!("Player {} started from command line", player.name);
+    start_game(&mut commands, player, quick_start.mode, quick_start.level.is_some());
+    next_state.set(GameState::Gaming);
+}
+
+fn start_game(commands: &mut Commands, player: Player, mode: GameMode, jumped: bool) {
+    commands.insert_resource(theme::Theme::load(&player.theme));
+    commands.insert_resource(player.accessibility.clone());
+    commands.insert_resource(GamePlayer {
+        player,
+        health: HEALTH_MAX_VALUE,
+        mode,
+        jumped,
+        ..default()
+    });
+}
+
+fn resume_dialog_setup(commands: &mut Commands, player: &Player, fonts: &GameFonts, asset_server: &AssetServer,
+                       language: Language) {
+    Dialog::new("Welcome Back", &resume_text(language, &player.name))
+        .fonts(fonts.normal_font.clone(), fonts.ui_font.clone())
+        .image(asset_server.load(format!("avatars/{}.png", player.avatar)))
+        .cancel_button(UiText::RestartRound.get(language), ResumeChoice::Restart)
+        .default_button(UiText::ResumeRound.get(language), ResumeChoice::Resume)
+        .spawn(commands, GameState::Startup);
+}
+
+fn on_resume_choice(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut reader: MessageReader<DialogResult<ResumeChoice>>,
+    pending: Res<PendingPlayer>,
+) {
+    if let Some(result) = reader.read().last() {
+        let player = pending.0.clone();
+        if let ResumeChoice::Resume = result.value
+            && let Some(round) = load_saved_round(&player.name, player.level) {
+            commands.insert_resource(ResumeRound(round));
+        }
+        remove_saved_round(&player.name);
+        commands.remove_resource::<PendingPlayer>();
+        start_game(&mut commands, player, GameMode::Arcade, false);
         next_state.set(GameState::Gaming)
     }
 }
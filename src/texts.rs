@@ -0,0 +1,137 @@
+use crate::settings::Language;
+
+/// 开始界面和设置界面上按照界面语言显示的文字
+#[derive(Clone, Copy)]
+pub enum UiText {
+    CreateAccountHint,
+    CreateAccount,
+    GameSettings,
+    ExitGame,
+    QuickGuide,
+    GuideIntro,
+    Enemy,
+    EnemyDesc,
+    Bomb,
+    BombDesc,
+    HealthPack,
+    HealthPackDesc,
+    Shield,
+    ShieldDesc,
+    WelcomeBack,
+    FindPlayer,
+    AllClasses,
+    SortName,
+    SortScore,
+    SortLevel,
+    ModeArcade,
+    ModeLesson,
+    Heatmap,
+    Or,
+    Guardian,
+    RestartRound,
+    ResumeRound,
+    SettingsHint,
+    Volume,
+    DefaultSpeed,
+    DisplayMode,
+    Windowed,
+    Fullscreen,
+    Language,
+    Targeting,
+    TargetNearest,
+    TargetOldest,
+    TargetAll,
+    HudInfo,
+    ShowFps,
+    ShowStreak,
+    ShowDifficulty,
+    WrongKeyPenalty,
+    Scoring,
+    Save,
+    Back,
+}
+
+impl UiText {
+    pub fn get(self, language: Language) -> &'static str {
+        let (chinese, english) = match self {
+            UiText::CreateAccountHint => ("为了开始游戏，首先需要创建一个自己的账户。", "To start playing, first create your own account."),
+            UiText::CreateAccount => ("创建新的账号", "Create New Account"),
+            UiText::GameSettings => ("游戏设置", "Settings"),
+            UiText::ExitGame => ("退出游戏", "Exit Game"),
+            UiText::QuickGuide => (" 游 戏 快 速 入 门 ", " Quick Start Guide "),
+            UiText::GuideIntro => ("超级简单！攻击你的小飞机带有字母或符号，按对应的按键就能消灭它！游戏中你会遇到：",
+                                   "Super easy! Enemy planes carry letters or symbols, press the matching key to destroy them! In the game you will meet:"),
+            UiText::Enemy => ("敌机", "Enemy"),
+            UiText::EnemyDesc => ("小飞机接近后就会发射飞弹攻击你，按下对应的字母击落它！",
+                                  "Enemy planes fire at you when they get close, press the matching letter to shoot them down!"),
+            UiText::Bomb => ("炸弹", "Bomb"),
+            UiText::BombDesc => ("炸弹可以将当前所有的敌机一次性全部摧毁，相当于你的大招！",
+                                 "A bomb destroys all enemy planes on the screen at once, it is your super move!"),
+            UiText::HealthPack => ("补血剂", "Health Pack"),
+            UiText::HealthPackDesc => ("补血剂可以恢复你的损伤，提高生命值，在战斗中坚持的更久！",
+                                       "A health pack repairs your damage and restores health so you can fight longer!"),
+            UiText::Shield => ("护盾", "Shield"),
+            UiText::ShieldDesc => ("护盾赋予你长达25秒坚不可摧的保护，期间所有的攻击对你无效。",
+                                   "A shield protects you for 25 seconds, no attack can hurt you in the meantime."),
+            UiText::WelcomeBack => ("欢迎回来，选择你的账户以继续游戏", "Welcome back, choose your account to continue"),
+            UiText::FindPlayer => ("输入名称查找玩家", "Type a name to find a player"),
+            UiText::AllClasses => ("全部班级", "All Classes"),
+            UiText::SortName => ("名称", "Name"),
+            UiText::SortScore => ("分数", "Score"),
+            UiText::SortLevel => ("级别", "Level"),
+            UiText::ModeArcade => ("模式：闯关游戏", "Mode: Arcade"),
+            UiText::ModeLesson => ("模式：打字课程", "Mode: Lessons"),
+            UiText::Heatmap => ("键盘热力图", "Keyboard Heatmap"),
+            UiText::Or => ("或者", "or"),
+            UiText::Guardian => ("家长设置", "Parental Settings"),
+            UiText::RestartRound => ("重新开始", "Start Over"),
+            UiText::ResumeRound => ("继续上次游戏", "Resume Game"),
+            UiText::SettingsHint => ("游戏设置（按Ctrl加+、-键可以调整界面大小）", "Settings (press Ctrl with + or - to resize the interface)"),
+            UiText::Volume => ("音量", "Volume"),
+            UiText::DefaultSpeed => ("默认速度", "Default Speed"),
+            UiText::DisplayMode => ("显示模式", "Display Mode"),
+            UiText::Windowed => ("窗口", "Windowed"),
+            UiText::Fullscreen => ("全屏", "Fullscreen"),
+            UiText::Language => ("语言", "Language"),
+            UiText::Targeting => ("导弹目标", "Missile Target"),
+            UiText::TargetNearest => ("最近的目标", "Nearest"),
+            UiText::TargetOldest => ("最早出现的目标", "Oldest"),
+            UiText::TargetAll => ("所有相同字符", "All Matching"),
+            UiText::HudInfo => ("界面信息", "HUD"),
+            UiText::ShowFps => ("显示帧率", "Show FPS"),
+            UiText::ShowStreak => ("显示连击倍数", "Show Combo"),
+            UiText::ShowDifficulty => ("显示当前难度", "Show Difficulty"),
+            UiText::WrongKeyPenalty => ("按错键扣分", "Wrong-Key Penalty"),
+            UiText::Scoring => ("计分", "Scoring"),
+            UiText::Save => ("保存", "Save"),
+            UiText::Back => ("返回", "Back"),
+        };
+        match language {
+            Language::Chinese => chinese,
+            Language::English => english,
+        }
+    }
+}
+
+/// 玩家列表中超过星级图片数量的级别
+pub fn level_text(language: Language, level: u32) -> String {
+    match language {
+        Language::Chinese => format!("{}级", level),
+        Language::English => format!("Lv {}", level),
+    }
+}
+
+pub fn score_text(language: Language, score: u32) -> String {
+    match language {
+        Language::Chinese => format!("{}分", score),
+        Language::English => format!("{} pts", score),
+    }
+}
+
+/// 询问玩家是否继续上次没有打完的游戏
+pub fn resume_text(language: Language, name: &str) -> String {
+    match language {
+        Language::Chinese => format!("{}，上次的游戏还没有打完，要从离开的地方继续吗？", name),
+        Language::English => format!("{}, your last game is not finished yet. Continue where you left off?", name),
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::Deserialize;
+use crate::{get_app_data_dir, ExplosionTexture, GameFonts, GAME_APP_NAME};
+
+/// 主题目录的资源来源名称，主题中的文件使用 themes://主题目录/文件 的路径加载
+pub const THEME_ASSET_SOURCE: &str = "themes";
+const THEMES_DIR: &str = "themes";
+const THEME_MANIFEST_FILE: &str = "theme.json";
+const BUILTIN_THEME_NAME: &str = "默认";
+
+/// 主题中可以替换的资源
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub enum ThemeAsset {
+    #[default]
+    FighterJet1,
+    FighterJet2,
+    FighterJet3,
+    FighterJet4,
+    FighterJet5,
+    Aircraft1,
+    Aircraft2,
+    Aircraft3,
+    Warship,
+    Missile,
+    MissileAdvanced,
+    Flame,
+    FlameGun,
+    FlameCannon,
+    Explosion,
+    SpaceStar,
+    Bomb,
+    Shield,
+    HealthPack,
+    SlowMotion,
+    DoubleScore,
+    AutoAim,
+    ExtraLife,
+    EquipmentEffect,
+    ShieldEffect,
+    HealthEffect,
+    CounterIcon,
+    LetterFont,
+}
+
+// 资源在主题描述文件中的名称及内置主题使用的文件
+const THEME_ASSETS: [(ThemeAsset, &str, &str); 28] = [
+    (ThemeAsset::FighterJet1, "fighter_jet_1", "images/fighter_jet_1.png"),
+    (ThemeAsset::FighterJet2, "fighter_jet_2", "images/fighter_jet_2.png"),
+    (ThemeAsset::FighterJet3, "fighter_jet_3", "images/fighter_jet_3.png"),
+    (ThemeAsset::FighterJet4, "fighter_jet_4", "images/fighter_jet_4.png"),
+    (ThemeAsset::FighterJet5, "fighter_jet_5", "images/fighter_jet_5.png"),
+    (ThemeAsset::Aircraft1, "aircraft_1", "images/aircraft_1.png"),
+    (ThemeAsset::Aircraft2, "aircraft_2", "images/aircraft_2.png"),
+    (ThemeAsset::Aircraft3, "aircraft_3", "images/aircraft_3.png"),
+    (ThemeAsset::Warship, "warship", "images/space-warship.png"),
+    (ThemeAsset::Missile, "missile", "images/missile.png"),
+    (ThemeAsset::MissileAdvanced, "missile_advanced", "images/missile-adv.png"),
+    (ThemeAsset::Flame, "flame", "images/flame.png"),
+    (ThemeAsset::FlameGun, "flame_gun", "images/flame_gun.png"),
+    (ThemeAsset::FlameCannon, "flame_cannon", "images/flame_cannon.png"),
+    (ThemeAsset::Explosion, "explosion", "images/explosion.png"),
+    (ThemeAsset::SpaceStar, "space_star", "images/space_star.png"),
+    (ThemeAsset::Bomb, "bomb", "images/bomb.png"),
+    (ThemeAsset::Shield, "shield", "images/shield.png"),
+    (ThemeAsset::HealthPack, "health_pack", "images/first-aid-kit.png"),
+    (ThemeAsset::SlowMotion, "slow_motion", "images/enhance.png"),
+    (ThemeAsset::DoubleScore, "double_score", "images/star-1.png"),
+    (ThemeAsset::AutoAim, "auto_aim", "images/missile-adv.png"),
+    (ThemeAsset::ExtraLife, "extra_life", "images/fighter-jet.png"),
+    (ThemeAsset::EquipmentEffect, "equipment_effect", "images/enhance.png"),
+    (ThemeAsset::ShieldEffect, "shield_effect", "images/shield_activated.png"),
+    (ThemeAsset::HealthEffect, "health_effect", "images/health_pack_apply.png"),
+    (ThemeAsset::CounterIcon, "counter_icon", "images/fighter-jet.png"),
+    (ThemeAsset::LetterFont, "letter_font", "fonts/letter-bold.ttf"),
+];
+
+impl ThemeAsset {
+    /// 玩家级别对应的战斗机，更高的级别使用最后一种
+    pub fn fighter_jet(level: u32) -> ThemeAsset {
+        match level {
+            0 | 1 => ThemeAsset::FighterJet1,
+            2 => ThemeAsset::FighterJet2,
+            3 => ThemeAsset::FighterJet3,
+            4 => ThemeAsset::FighterJet4,
+            _ => ThemeAsset::FighterJet5,
+        }
+    }
+
+    pub fn aircraft(sprite: i32) -> ThemeAsset {
+        match sprite {
+            2 => ThemeAsset::Aircraft2,
+            3 => ThemeAsset::Aircraft3,
+            _ => ThemeAsset::Aircraft1,
+        }
+    }
+
+    fn from_key(key: &str) -> Option<ThemeAsset> {
+        THEME_ASSETS.iter().find(|(_, k, _)| *k == key).map(|(asset, _, _)| *asset)
+    }
+
+    fn builtin_path(&self) -> &'static str {
+        THEME_ASSETS.iter().find(|(asset, _, _)| asset == self).map(|(_, _, path)| *path).unwrap()
+    }
+}
+
+/// 爆炸动画图片中每一帧的大小及排列，帧按行依次播放
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct ExplosionGrid {
+    pub frame_width: u32,
+    pub frame_height: u32,
+    pub columns: u32,
+    pub rows: u32,
+}
+
+impl Default for ExplosionGrid {
+    // 内置的爆炸图片
+    fn default() -> Self {
+        ExplosionGrid { frame_width: 150, frame_height: 129, columns: 3, rows: 3 }
+    }
+}
+
+impl ExplosionGrid {
+    pub fn layout(&self) -> TextureAtlasLayout {
+        TextureAtlasLayout::from_grid(UVec2::new(self.frame_width, self.frame_height), self.columns, self.rows, None, None)
+    }
+
+    /// 动画的总帧数
+    pub fn frames(&self) -> usize {
+        (self.columns * self.rows) as usize
+    }
+
+    fn is_valid(&self) -> bool {
+        self.frame_width > 0 && self.frame_height > 0 && self.frames() > 0
+    }
+}
+
+/// 主题目录中的描述文件，文件路径都相对于主题目录
+#[derive(Deserialize)]
+struct ThemeManifest {
+    name: String,
+    // 游戏背景图片，内置主题没有背景图片
+    #[serde(default)]
+    background: Option<String>,
+    #[serde(default)]
+    assets: HashMap<String, String>,
+    // 替换爆炸图片时必须说明帧的排列
+    #[serde(default)]
+    explosion_grid: Option<ExplosionGrid>,
+}
+
+/// 当前玩家使用的主题，没有替换的资源使用内置主题的文件
+#[derive(Resource, Default, Clone)]
+pub struct Theme {
+    background: Option<String>,
+    assets: HashMap<ThemeAsset, String>,
+    explosion_grid: ExplosionGrid,
+}
+
+/// 主题中的文件只能是主题目录下的相对路径，不能是绝对路径或者用..访问主题目录以外的文件
+fn is_theme_relative(file: &str) -> bool {
+    let path = Path::new(file);
+    path.components().next().is_some() && path.components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+/// 存放主题的目录，每个主题是其中的一个子目录
+pub fn themes_dir() -> PathBuf {
+    let mut dir = get_app_data_dir(GAME_APP_NAME);
+    dir.push(THEMES_DIR);
+    if let Err(e) = fs::create_dir_all(&dir) {
+        error!("Failed to create themes directory: {}", e);
+    }
+    dir
+}
+
+fn read_manifest(id: &str) -> Option<ThemeManifest> {
+    let mut manifest_file = themes_dir();
+    manifest_file.push(id);
+    manifest_file.push(THEME_MANIFEST_FILE);
+    fs::read_to_string(&manifest_file)
+        .and_then(|data| serde_json::from_str::<ThemeManifest>(&data).map_err(|err| err.into()))
+        .map_err(|err| error!("Failed to parse theme {}: {}", id, err))
+        .ok()
+}
+
+/// 所有可以选择的主题，第一个是内置主题，返回主题目录及主题名称
+pub fn available_themes() -> Vec<(String, String)> {
+    let mut themes = vec![(String::new(), BUILTIN_THEME_NAME.to_owned())];
+    let Ok(entries) = fs::read_dir(themes_dir()) else {
+        return themes;
+    };
+    let mut ids: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join(THEME_MANIFEST_FILE).exists())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    ids.sort();
+    for id in ids {
+        if let Some(manifest) = read_manifest(&id) {
+            themes.push((id, manifest.name));
+        }
+    }
+    themes
+}
+
+/// 主题的显示名称，找不到主题时显示为内置主题
+pub fn theme_name(id: &str) -> String {
+    if id.is_empty() {
+        return BUILTIN_THEME_NAME.to_owned();
+    }
+    read_manifest(id).map(|m| m.name).unwrap_or_else(|| BUILTIN_THEME_NAME.to_owned())
+}
+
+impl Theme {
+    /// 读取主题目录中的描述文件，不认识的资源名称、不存在或者在主题目录以外的文件都会被忽略
+    pub fn load(id: &str) -> Theme {
+        if id.is_empty() {
+            return Theme::default();
+        }
+        let Some(manifest) = read_manifest(id) else {
+            return Theme::default();
+        };
+        let dir = themes_dir().join(id);
+        let resolve = |file: &str| -> Option<String> {
+            if !is_theme_relative(file) {
+                warn!("Theme {} refers to file {} outside its directory", id, file);
+                None
+            } else if dir.join(file).exists() {
+                Some(format!("{}://{}/{}", THEME_ASSET_SOURCE, id, file))
+            } else {
+                warn!("Theme {} is missing file {}", id, file);
+                None
+            }
+        };
+        let mut assets = HashMap::new();
+        for (key, file) in &manifest.assets {
+            let Some(asset) = ThemeAsset::from_key(key) else {
+                warn!("Unknown asset {} in theme {}", key, id);
+                continue;
+            };
+            if let Some(path) = resolve(file) {
+                assets.insert(asset, path);
+            }
+        }
+        // 爆炸图片的帧排列和内置图片不同时动画会错乱，没有说明排列时不替换
+        let explosion_grid = manifest.explosion_grid.filter(ExplosionGrid::is_valid);
+        if assets.contains_key(&ThemeAsset::Explosion) && explosion_grid.is_none() {
+            warn!("Theme {} replaces explosion without a valid explosion_grid", id);
+            assets.remove(&ThemeAsset::Explosion);
+        }
+        Theme {
+            background: manifest.background.as_deref().and_then(resolve),
+            assets,
+            explosion_grid: explosion_grid.unwrap_or_default(),
+        }
+    }
+
+    pub fn path(&self, asset: ThemeAsset) -> String {
+        self.assets.get(&asset).cloned().unwrap_or_else(|| asset.builtin_path().to_owned())
+    }
+
+    pub fn background(&self) -> Option<&str> {
+        self.background.as_deref()
+    }
+}
+
+/// 按照当前主题加载资源
+#[derive(SystemParam)]
+pub struct ThemeAssets<'w> {
+    pub asset_server: Res<'w, AssetServer>,
+    pub theme: Res<'w, Theme>,
+}
+
+impl ThemeAssets<'_> {
+    pub fn load<A: Asset>(&self, asset: ThemeAsset) -> Handle<A> {
+        self.asset_server.load(self.theme.path(asset))
+    }
+}
+
+/// 进入游戏时换成主题中的字符字体和爆炸图片
+pub fn apply_theme(
+    mut fonts: ResMut<GameFonts>,
+    mut explosion: ResMut<ExplosionTexture>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    assets: ThemeAssets,
+) {
+    fonts.letter_font = assets.load(ThemeAsset::LetterFont);
+    explosion.texture = assets.load(ThemeAsset::Explosion);
+    explosion.layout = layouts.add(assets.theme.explosion_grid.layout());
+    explosion.frames = assets.theme.explosion_grid.frames();
+}
@@ -1,15 +1,25 @@
 use std::sync::OnceLock;
+use bevy::input::mouse::MouseWheel;
+use bevy::input_focus::{InputFocus, InputFocusVisible};
 use bevy::prelude::*;
 
 mod button;
 mod input;
 mod listview;
 mod dialog;
+mod slider;
+mod checkbox;
+mod dropdown;
+mod navigation;
 
 pub use button::*;
 pub use input::*;
 pub use listview::*;
 pub use dialog::*;
+pub use slider::*;
+pub use checkbox::*;
+pub use dropdown::*;
+pub use navigation::*;
 
 pub const UI_FONT_SIZE: f32 = 18.0;
 
@@ -65,15 +75,42 @@ pub fn widgets_plugin(app: &mut App) {
     app
         .add_message::<ButtonClicked>()
         .add_message::<ListViewSelectionChanged>()
+        .add_message::<SliderChanged>()
+        .add_message::<CheckboxToggled>()
+        .add_message::<DropdownSelected>()
+        .init_resource::<InputFocusVisible>()
         .add_systems(Update, (button_interaction_system,
                               button_style_selected_system,
                               button_style_unselected_system))
-        .add_systems(Update, (input_box_handle_focus.run_if(|q: Query<(), With<InputBox>>| !q.is_empty()),
+        .add_systems(Update, (input_box_handle_focus,
                               (input_box_blink_cursor,
                                input_box_ime_events,
                                input_box_keyboard_events)
-                                  .run_if(resource_exists::<InputFocused>)))
-        .add_systems(Update, (listview_interaction_system.run_if(|q: Query<(), With<ListViewMarker>>| !q.is_empty()),
-                              listview_cursor_move_system.run_if((|q: Query<(), With<ListViewMarker>>| !q.is_empty())
-                                  .and(on_message::<CursorMoved>))));
+                                  .run_if(resource_exists::<InputFocused>),
+                              input_box_render_system).chain()
+            .run_if(|q: Query<(), With<InputBox>>| !q.is_empty()))
+        .add_systems(Update, (listview_layout_system,
+                              listview_interaction_system,
+                              listview_scroll_system.run_if(on_message::<MouseWheel>),
+                              listview_cursor_move_system.run_if(on_message::<CursorMoved>.or(on_message::<MouseWheel>)))
+            .chain()
+            .run_if(|q: Query<(), With<ListViewMarker>>| !q.is_empty()))
+        .add_systems(Update, (slider_interaction_system, slider_update_system).chain()
+            .run_if(|q: Query<(), With<Slider>>| !q.is_empty()))
+        .add_systems(Update, (checkbox_interaction_system, checkbox_update_system).chain()
+            .run_if(|q: Query<(), With<Checkbox>>| !q.is_empty()))
+        .add_systems(Update, dropdown_click_system.after(button_interaction_system)
+            .run_if(|q: Query<(), With<Dropdown>>| !q.is_empty()))
+        // 输入框有焦点时空格和回车用于输入，不能点击按钮
+        .add_systems(Update, (button_keyboard_system,
+                              checkbox_keyboard_system,
+                              listview_keyboard_system,
+                              slider_keyboard_system)
+            .run_if(not(resource_exists::<InputFocused>))
+            .before(keyboard_navigation_system))
+        .add_systems(Update, (keyboard_navigation_system,
+                              cancel_button_system,
+                              hide_focus_ring_system.run_if(on_message::<CursorMoved>),
+                              focus_ring_system.run_if(resource_changed::<InputFocus>
+                                  .or(resource_changed::<InputFocusVisible>))).chain());
 }
@@ -47,7 +47,7 @@ pub struct ButtonConfig {
 }
 
 impl Button {
-    pub fn new<C: Component>(marker: C,
+    pub fn new<C: Bundle>(marker: C,
                              text: TextConfig,
                              size: Vec2,
                              enabled: bool,
@@ -97,7 +97,7 @@ impl Button {
 pub struct PushButton;
 
 impl PushButton {
-    pub fn new<C: Component>(marker: C, text: &str, size: Vec2, enabled: bool, margin: UiRect) -> impl Bundle {
+    pub fn new<C: Bundle>(marker: C, text: &str, size: Vec2, enabled: bool, margin: UiRect) -> impl Bundle {
         Button::new(
             marker,
             TextConfig {
@@ -252,6 +252,20 @@ pub fn button_interaction_system(
     }
 }
 
+/// 按钮获得焦点时，按回车键或空格键相当于点击
+pub fn button_keyboard_system(
+    input_focus: Res<InputFocus>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    buttons: Query<&Enabled, With<ButtonConfig>>,
+    mut writer: MessageWriter<ButtonClicked>,
+) {
+    if keyboard.any_just_released([KeyCode::Enter, KeyCode::Space])
+        && let Some(entity) = input_focus.0
+        && buttons.get(entity).is_ok_and(|enabled| enabled.0) {
+        writer.write(ButtonClicked { entity });
+    }
+}
+
 pub fn button_style_selected_system(
     mut query: Query<(Entity, &mut BackgroundColor, &ButtonConfig, Option<&Selected>),
         (With<bevy::prelude::Button>, Changed<Selected>)>
@@ -0,0 +1,126 @@
+use bevy::input_focus::InputFocus;
+use bevy::prelude::*;
+use super::UI_FONT_SIZE;
+
+const CHECKBOX_SIZE: f32 = 22.0;
+const CHECKBOX_MARK_SIZE: f32 = 12.0;
+const CHECKBOX_BORDER_COLOR: Color = Color::srgb_u8(240, 240, 240);
+const CHECKBOX_HOVERED_COLOR: Color = Color::srgb_u8(161, 67, 246);
+const CHECKBOX_MARK_COLOR: Color = Color::srgb_u8(147, 51, 234);
+
+/// 带文字说明的复选框，点击方框或文字都可以切换选中状态
+#[derive(Component)]
+pub struct Checkbox {
+    pub checked: bool,
+}
+
+#[derive(Component)]
+pub struct CheckboxMark;
+
+#[derive(Message)]
+pub struct CheckboxToggled {
+    pub entity: Entity,
+    pub checked: bool,
+}
+
+impl Checkbox {
+    pub fn new<C: Component>(marker: C, text: &str, checked: bool, margin: UiRect) -> impl Bundle {
+        (
+            Node {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                margin,
+                ..default()
+            },
+            bevy::ui::prelude::Button,
+            Checkbox { checked },
+            marker,
+            children![
+                (
+                    Node {
+                        width: Val::Px(CHECKBOX_SIZE),
+                        height: Val::Px(CHECKBOX_SIZE),
+                        border: UiRect::all(Val::Px(2.)),
+                        border_radius: BorderRadius::all(Val::Px(4.)),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    BorderColor::all(CHECKBOX_BORDER_COLOR),
+                    children![(
+                        Node {
+                            width: Val::Px(CHECKBOX_MARK_SIZE),
+                            height: Val::Px(CHECKBOX_MARK_SIZE),
+                            border_radius: BorderRadius::all(Val::Px(2.)),
+                            ..default()
+                        },
+                        BackgroundColor(CHECKBOX_MARK_COLOR),
+                        if checked { Visibility::Inherited } else { Visibility::Hidden },
+                        CheckboxMark,
+                    )]
+                ),
+                (
+                    Text::new(text),
+                    TextFont {
+                        font: super::UI_BUTTON_FONT.get().unwrap().clone(),
+                        font_size: UI_FONT_SIZE,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                    Node {
+                        margin: UiRect::left(Val::Px(8.)),
+                        ..default()
+                    },
+                )
+            ],
+        )
+    }
+}
+
+pub fn checkbox_interaction_system(
+    mut checkboxes: Query<(Entity, &Interaction, &mut Checkbox, &Children), Changed<Interaction>>,
+    mut borders: Query<&mut BorderColor>,
+    mut writer: MessageWriter<CheckboxToggled>,
+) {
+    for (entity, interaction, mut checkbox, children) in &mut checkboxes {
+        if let Ok(mut border) = borders.get_mut(children[0]) {
+            *border = BorderColor::all(if *interaction == Interaction::None {
+                CHECKBOX_BORDER_COLOR
+            } else {
+                CHECKBOX_HOVERED_COLOR
+            });
+        }
+        if *interaction == Interaction::Pressed {
+            checkbox.checked = !checkbox.checked;
+            writer.write(CheckboxToggled { entity, checked: checkbox.checked });
+        }
+    }
+}
+
+/// 复选框获得焦点时，按回车键或空格键切换选中状态
+pub fn checkbox_keyboard_system(
+    input_focus: Res<InputFocus>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut checkboxes: Query<&mut Checkbox>,
+    mut writer: MessageWriter<CheckboxToggled>,
+) {
+    if keyboard.any_just_released([KeyCode::Enter, KeyCode::Space])
+        && let Some(entity) = input_focus.0
+        && let Ok(mut checkbox) = checkboxes.get_mut(entity) {
+        checkbox.checked = !checkbox.checked;
+        writer.write(CheckboxToggled { entity, checked: checkbox.checked });
+    }
+}
+
+pub fn checkbox_update_system(
+    checkboxes: Query<(&Checkbox, &Children), Changed<Checkbox>>,
+    children_query: Query<&Children>,
+    mut marks: Query<&mut Visibility, With<CheckboxMark>>,
+) {
+    for (checkbox, children) in &checkboxes {
+        if let Some(mark) = children_query.get(children[0]).ok().and_then(|c| c.first())
+            && let Ok(mut visibility) = marks.get_mut(*mark) {
+            *visibility = if checkbox.checked { Visibility::Inherited } else { Visibility::Hidden };
+        }
+    }
+}
@@ -1,49 +1,278 @@
+use bevy::input_focus::InputFocus;
 use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+use super::{button_interaction_system, ButtonClicked, CancelButton, PushButton};
+
+const BACKDROP_COLOR: Color = Color::srgba(0., 0., 0., 0.6);
+const DIALOG_TITLE_COLOR: Color = Color::srgb_u8(135, 201, 22);
+const DIALOG_TEXT_COLOR: Color = Color::srgb_u8(188, 190, 196);
+const DIALOG_NOTE_COLOR: Color = Color::srgb_u8(255, 100, 100);
+const DIALOG_IMAGE_SIZE: f32 = 96.0;
+const DIALOG_BUTTON_SIZE: Vec2 = Vec2::new(160.0, 40.0);
+
+/// 对话框的根节点，打开对话框时键盘焦点只在对话框内移动
+#[derive(Component)]
+pub struct ModelDialogRoot;
 
 pub struct ModelDialog {
     pub root: Entity,
     pub container: Entity,
 }
 
+fn dialog_frame(width: f32) -> impl Bundle {
+    (
+        Node {
+            width: Val::Percent(width),
+            height: Val::Auto,
+            align_self: AlignSelf::Center,
+            justify_self: JustifySelf::Center,
+            border: UiRect::all(Val::Px(2.)),
+            border_radius: BorderRadius::all(Val::Px(5.0)),
+            ..default()
+        },
+        BorderColor::all(Color::srgb_u8(43, 44, 47)),
+        BackgroundColor(Color::NONE),
+    )
+}
+
+fn spawn_container(commands: &mut Commands, frame: Entity) -> Entity {
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Start,
+                border: UiRect::all(Val::Px(3.)),
+                border_radius: BorderRadius::all(Val::Px(5.0)),
+                padding: UiRect::all(Val::Px(10.)),
+                ..default()
+            },
+            BorderColor::all(Color::srgb_u8(76, 69, 113)),
+            BackgroundColor(Color::srgb_u8(43, 44, 47)),
+            ChildOf(frame),
+        )).id()
+}
+
 impl ModelDialog {
     pub fn new<S: States>(commands: &mut Commands, state: S, width: f32) -> Self {
         let root = commands
             .spawn((
                 DespawnOnExit(state),
-                Node {
-                    width: Val::Percent(width),
-                    height: Val::Auto,
-                    align_self: AlignSelf::Center,
-                    justify_self: JustifySelf::Center,
-                    border: UiRect::all(Val::Px(2.)),
-                    border_radius: BorderRadius::all(Val::Px(5.0)),
-                    ..default()
-                },
-                BorderColor::all(Color::srgb_u8(43, 44, 47)),
-                BackgroundColor(Color::NONE),
+                ModelDialogRoot,
+                dialog_frame(width),
             )).id();
+        let container = spawn_container(commands, root);
+        Self { root, container }
+    }
 
-        // 子节点
-        let container = commands
+    /// 对话框下面铺一层半透明的背景，遮住整个窗口，下面的界面不能再点击
+    pub fn with_backdrop<S: States>(commands: &mut Commands, state: S, width: f32) -> Self {
+        let root = commands
             .spawn((
+                DespawnOnExit(state),
+                ModelDialogRoot,
                 Node {
+                    position_type: PositionType::Absolute,
                     width: Val::Percent(100.),
                     height: Val::Percent(100.),
-                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
                     align_items: AlignItems::Center,
-                    justify_content: JustifyContent::Start,
-                    border: UiRect::all(Val::Px(3.)),
-                    border_radius: BorderRadius::all(Val::Px(5.0)),
-                    padding: UiRect::all(Val::Px(10.)),
                     ..default()
                 },
-                BorderColor::all(Color::srgb_u8(76, 69, 113)),
-                BackgroundColor(Color::srgb_u8(43, 44, 47)),
+                BackgroundColor(BACKDROP_COLOR),
+                FocusPolicy::Block,
+                GlobalZIndex(10),
             )).id();
-
-        // 关联父子关系
-        commands.entity(root).add_child(container);
-        
+        let frame = commands.spawn((dialog_frame(width), ChildOf(root))).id();
+        let container = spawn_container(commands, frame);
         Self { root, container }
     }
-}
\ No newline at end of file
+}
+
+/// 对话框按钮对应的选项
+#[derive(Component)]
+struct DialogChoice<T> {
+    dialog: Entity,
+    value: T,
+}
+
+/// 点击对话框中的按钮后发出，value是按钮对应的选项，对话框随后关闭
+#[derive(Message)]
+pub struct DialogResult<T> {
+    pub value: T,
+}
+
+/// 对话框说明文字下面的提示文字，打开对话框后可以修改
+#[derive(Component)]
+pub struct DialogNote;
+
+struct DialogButton<T> {
+    text: String,
+    value: T,
+    default: bool,
+    cancel: bool,
+}
+
+/// 由标题、说明文字、图片和一排按钮组成的对话框，选项类型T需要先用dialog_plugin注册
+pub struct Dialog<T> {
+    title: String,
+    text: String,
+    image: Option<Handle<Image>>,
+    fonts: Option<(Handle<Font>, Handle<Font>)>,
+    note: Option<String>,
+    buttons: Vec<DialogButton<T>>,
+}
+
+impl<T: Clone + Send + Sync + 'static> Dialog<T> {
+    pub fn new(title: &str, text: &str) -> Self {
+        Self {
+            title: title.to_owned(),
+            text: text.to_owned(),
+            image: None,
+            fonts: None,
+            note: None,
+            buttons: Vec::new(),
+        }
+    }
+
+    pub fn image(mut self, image: Handle<Image>) -> Self {
+        self.image = Some(image);
+        self
+    }
+
+    /// 标题和说明文字的字体，默认都使用按钮字体
+    pub fn fonts(mut self, title: Handle<Font>, text: Handle<Font>) -> Self {
+        self.fonts = Some((title, text));
+        self
+    }
+
+    /// 在说明文字下面显示一行提示，用DialogNote查询后可以更新内容
+    pub fn note(mut self, text: &str) -> Self {
+        self.note = Some(text.to_owned());
+        self
+    }
+
+    pub fn button(mut self, text: &str, value: T) -> Self {
+        self.buttons.push(DialogButton { text: text.to_owned(), value, default: false, cancel: false });
+        self
+    }
+
+    /// 默认按钮，打开对话框时获得键盘焦点，直接按回车键就会选择
+    pub fn default_button(mut self, text: &str, value: T) -> Self {
+        self.buttons.push(DialogButton { text: text.to_owned(), value, default: true, cancel: false });
+        self
+    }
+
+    /// 取消按钮，按Esc键相当于点击
+    pub fn cancel_button(mut self, text: &str, value: T) -> Self {
+        self.buttons.push(DialogButton { text: text.to_owned(), value, default: false, cancel: true });
+        self
+    }
+
+    /// 显示对话框，离开state时自动关闭，返回对话框的根节点
+    pub fn spawn<S: States>(self, commands: &mut Commands, state: S) -> Entity {
+        let dialog = ModelDialog::with_backdrop(commands, state, 60.);
+        let (title_font, text_font) = self.fonts.unwrap_or_else(|| {
+            let font = super::UI_BUTTON_FONT.get().unwrap().clone();
+            (font.clone(), font)
+        });
+        let mut default_button = None;
+        commands.entity(dialog.container).with_children(|builder| {
+            builder.spawn((
+                Text::new(self.title),
+                TextFont { font: title_font, font_size: 48., ..default() },
+                TextColor(DIALOG_TITLE_COLOR),
+            ));
+            builder.spawn(
+                Node {
+                    width: Val::Percent(90.),
+                    height: Val::Auto,
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    margin: UiRect::all(Val::Px(20.0)),
+                    ..default()
+                }).with_children(|builder| {
+                if let Some(image) = self.image {
+                    builder.spawn((
+                        ImageNode::new(image),
+                        Node {
+                            width: Val::Px(DIALOG_IMAGE_SIZE),
+                            height: Val::Px(DIALOG_IMAGE_SIZE),
+                            margin: UiRect::horizontal(Val::Px(30.)),
+                            ..default()
+                        },
+                    ));
+                }
+                builder.spawn((
+                    Text::new(self.text),
+                    TextFont { font: text_font.clone(), font_size: 28., ..default() },
+                    TextColor(DIALOG_TEXT_COLOR),
+                ));
+            });
+            if let Some(note) = self.note {
+                builder.spawn((
+                    DialogNote,
+                    Text::new(note),
+                    TextFont { font: text_font, font_size: 28., ..default() },
+                    TextColor(DIALOG_NOTE_COLOR),
+                ));
+            }
+            builder.spawn(
+                Node {
+                    width: Val::Percent(90.),
+                    height: Val::Auto,
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    margin: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                }).with_children(|builder| {
+                for button in self.buttons {
+                    let mut entity = builder.spawn(
+                        PushButton::new(DialogChoice { dialog: dialog.root, value: button.value },
+                                        &button.text,
+                                        DIALOG_BUTTON_SIZE,
+                                        true,
+                                        UiRect::horizontal(Val::Px(10.0))));
+                    if button.cancel {
+                        entity.insert(CancelButton);
+                    }
+                    if button.default {
+                        default_button = Some(entity.id());
+                    }
+                }
+            });
+        });
+        if let Some(button) = default_button {
+            commands.queue(move |world: &mut World| world.resource_mut::<InputFocus>().set(button));
+        }
+        dialog.root
+    }
+}
+
+/// 点击对话框按钮时发出DialogResult并关闭对话框
+fn dialog_result_system<T: Clone + Send + Sync + 'static>(
+    mut commands: Commands,
+    mut reader: MessageReader<ButtonClicked>,
+    choices: Query<&DialogChoice<T>>,
+    mut writer: MessageWriter<DialogResult<T>>,
+) {
+    for event in reader.read() {
+        if let Ok(choice) = choices.get(event.entity) {
+            writer.write(DialogResult { value: choice.value.clone() });
+            commands.entity(choice.dialog).try_despawn();
+        }
+    }
+}
+
+/// 注册选项类型为T的对话框
+pub fn dialog_plugin<T: Clone + Send + Sync + 'static>(app: &mut App) {
+    app
+        .add_message::<DialogResult<T>>()
+        .add_systems(Update, dialog_result_system::<T>
+            .after(button_interaction_system)
+            .run_if(|q: Query<(), With<DialogChoice<T>>>| !q.is_empty()));
+}
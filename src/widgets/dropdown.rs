@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+use super::{ButtonClicked, ButtonConfig, Enabled, UI_FONT_SIZE};
+
+const DROPDOWN_NORMAL_COLOR: Color = Color::srgb_u8(43, 44, 47);
+const DROPDOWN_BORDER_COLOR: Color = Color::srgb_u8(76, 69, 113);
+const DROPDOWN_HOVERED_COLOR: Color = Color::srgb_u8(161, 67, 246);
+const DROPDOWN_PRESSED_COLOR: Color = Color::srgb_u8(133, 35, 222);
+
+/// 下拉选择框，点击后在下方展开所有选项
+#[derive(Component)]
+pub struct Dropdown {
+    pub options: Vec<String>,
+    pub selected: usize,
+    size: Vec2,
+    // 展开的选项列表
+    list: Option<Entity>,
+}
+
+/// 下拉列表中的一个选项
+#[derive(Component)]
+pub struct DropdownOption {
+    dropdown: Entity,
+    index: usize,
+}
+
+#[derive(Message)]
+pub struct DropdownSelected {
+    pub entity: Entity,
+    pub index: usize,
+}
+
+fn dropdown_button_config(size: Vec2) -> ButtonConfig {
+    ButtonConfig {
+        normal: DROPDOWN_NORMAL_COLOR,
+        hovered: DROPDOWN_HOVERED_COLOR,
+        pressed: DROPDOWN_PRESSED_COLOR,
+        border: DROPDOWN_BORDER_COLOR,
+        width: size.x,
+        height: size.y
+    }
+}
+
+fn option_text(text: String) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font: super::UI_BUTTON_FONT.get().unwrap().clone(),
+            font_size: UI_FONT_SIZE,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+    )
+}
+
+impl Dropdown {
+    pub fn new<C: Component>(marker: C, options: Vec<String>, selected: usize, size: Vec2, margin: UiRect) -> impl Bundle {
+        let selected = selected.min(options.len().saturating_sub(1));
+        let text = options.get(selected).cloned().unwrap_or_default();
+        (
+            bevy::ui::prelude::Button,
+            marker,
+            Node {
+                width: Val::Px(size.x),
+                height: Val::Px(size.y),
+                border: UiRect::all(Val::Px(1.)),
+                border_radius: BorderRadius::all(Val::Px(5.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                margin,
+                ..default()
+            },
+            BorderColor::all(DROPDOWN_BORDER_COLOR),
+            BackgroundColor(DROPDOWN_NORMAL_COLOR),
+            dropdown_button_config(size),
+            Enabled(true),
+            Dropdown { options, selected, size, list: None },
+            children![option_text(text)],
+        )
+    }
+}
+
+/// 点击下拉框时展开或收起选项列表，点击选项时选中该项
+pub fn dropdown_click_system(
+    mut commands: Commands,
+    mut reader: MessageReader<ButtonClicked>,
+    mut dropdowns: Query<(&mut Dropdown, &Children)>,
+    options: Query<&DropdownOption>,
+    mut texts: Query<&mut Text>,
+    mut writer: MessageWriter<DropdownSelected>,
+) {
+    for event in reader.read() {
+        if let Ok((mut dropdown, _)) = dropdowns.get_mut(event.entity) {
+            if let Some(list) = dropdown.list.take() {
+                commands.entity(list).despawn();
+                continue;
+            }
+            let size = dropdown.size;
+            let list = commands.spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Percent(100.),
+                    left: Val::Px(-1.),
+                    width: Val::Px(size.x),
+                    flex_direction: FlexDirection::Column,
+                    border: UiRect::all(Val::Px(1.)),
+                    ..default()
+                },
+                BorderColor::all(DROPDOWN_BORDER_COLOR),
+                BackgroundColor(DROPDOWN_NORMAL_COLOR),
+                GlobalZIndex(20),
+                ChildOf(event.entity),
+            )).with_children(|builder| {
+                for (index, text) in dropdown.options.iter().enumerate() {
+                    builder.spawn((
+                        bevy::ui::prelude::Button,
+                        DropdownOption { dropdown: event.entity, index },
+                        Node {
+                            width: Val::Percent(100.),
+                            height: Val::Px(size.y),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        BorderColor::all(DROPDOWN_NORMAL_COLOR),
+                        BackgroundColor(DROPDOWN_NORMAL_COLOR),
+                        ButtonConfig { border: DROPDOWN_NORMAL_COLOR, ..dropdown_button_config(size) },
+                        Enabled(true),
+                        children![option_text(text.clone())],
+                    ));
+                }
+            }).id();
+            dropdown.list = Some(list);
+        } else if let Ok(option) = options.get(event.entity)
+            && let Ok((mut dropdown, children)) = dropdowns.get_mut(option.dropdown) {
+            if let Some(list) = dropdown.list.take() {
+                commands.entity(list).despawn();
+            }
+            dropdown.selected = option.index;
+            if let Ok(mut text) = texts.get_mut(children[0]) {
+                **text = dropdown.options[option.index].clone();
+            }
+            writer.write(DropdownSelected { entity: option.dropdown, index: option.index });
+        }
+    }
+}
@@ -1,40 +1,76 @@
 use bevy::input::keyboard::{Key, KeyboardInput};
 use bevy::prelude::*;
+use bevy::text::TextLayoutInfo;
 use super::TextConfig;
 
 #[derive(Resource)]
-pub struct InputFocused(Entity);
+pub struct InputFocused(pub Entity);
 
 #[derive(Component)]
 pub struct InputBoxBorder(Vec2);
 
+/// 检查输入内容是否有效，无效时输入框底边显示为红色
+pub type InputValidator = Box<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// 输入框的可选设置
+#[derive(Default)]
+pub struct InputBoxOptions {
+    // 最多可以输入的字符数
+    pub max_chars: Option<usize>,
+    pub validator: Option<InputValidator>,
+    // 显示为圆点而不是实际输入的内容，用于输入密码
+    pub masked: bool,
+}
+
 #[derive(Component)]
 pub struct InputBox {
     pub text_color: Color,
-    pub hint_text: String,
+    // 没有内容并且没有输入焦点时显示的提示文字
+    pub placeholder: String,
     pub value: String,
-    pub cursor: Entity
+    pub cursor: Entity,
+    // 依次为光标前、光标左侧选中、光标右侧选中、光标后的文字
+    segments: [Entity; 4],
+    // 光标位置及选择起点，都是字符序号
+    caret: usize,
+    anchor: Option<usize>,
+    // 输入法正在组字的内容
+    preedit: String,
+    max_chars: Option<usize>,
+    validator: Option<InputValidator>,
+    masked: bool,
 }
 
 const INPUT_BOX_SIDE_COLOR: Color = Color::srgb_u8(240, 240, 240);
 
 const INPUT_BOX_BOTTOM_COLOR: Color = Color::srgb_u8(0, 105, 186);
 
+const INPUT_BOX_INVALID_COLOR: Color = Color::srgb_u8(234, 67, 53);
+
 const HINT_TEXT_COLOR: Color = Color::srgb_u8(90, 90, 90);
 
+const SELECTION_COLOR: Color = Color::srgb_u8(0, 105, 186);
+
+// 密码输入框中代替每个字符显示的圆点
+const MASK_CHAR: char = '•';
+
 #[derive(Component)]
 pub struct CursorMarker {
     timer: Timer
 }
 
+fn byte_index(text: &str, chars: usize) -> usize {
+    text.char_indices().nth(chars).map_or(text.len(), |(i, _)| i)
+}
+
 impl InputBox {
     pub fn new<C: Component>(builder: &mut ChildSpawnerCommands,
-                               marker: C,
-                               text: TextConfig,
-                               hint: &str,
-                               size: Vec2,
-                               margin: UiRect) {
-        let use_hint = text.text.is_empty();
+                             marker: C,
+                             text: TextConfig,
+                             placeholder: &str,
+                             size: Vec2,
+                             margin: UiRect,
+                             options: InputBoxOptions) {
         builder.spawn((
             // 构建底边蓝色，其它边白色的文本输入框外观
             Node {
@@ -44,6 +80,7 @@ impl InputBox {
                 padding: UiRect::left(Val::Px(6.)),
                 align_items: AlignItems::Center,
                 justify_content: JustifyContent::Start,
+                overflow: Overflow::clip(),
                 border: UiRect {
                     left: Val::Px(0.5),
                     right: Val::Px(0.5),
@@ -61,23 +98,34 @@ impl InputBox {
                 bottom: INPUT_BOX_BOTTOM_COLOR,
             })
         ).with_children(|builder| {
-            builder.spawn(
+            let mut row = builder.spawn((
                 Node {
                     flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    flex_grow: 1.,
+                    height: Val::Percent(100.),
                     ..default()
-                }).with_children(|builder| {
-                let input_text = builder.spawn((
-                    Text::new(if use_hint { hint.to_owned() } else { text.text.clone() }),
+                },
+                marker
+            ));
+            let row_id = row.id();
+            let mut segments = [Entity::PLACEHOLDER; 4];
+            let mut cursor = Entity::PLACEHOLDER;
+            row.with_children(|builder| {
+                let spawn_segment = |builder: &mut ChildSpawnerCommands, selected: bool| builder.spawn((
+                    Text::new(""),
                     TextFont {
                         font: text.font.clone(),
                         font_size: text.font_size,
                         ..default()
                     },
-                    TextColor(if use_hint { HINT_TEXT_COLOR } else { text.color }),
+                    TextColor(text.color),
                     text.to_shadow(),
-                    marker
+                    BackgroundColor(if selected { SELECTION_COLOR } else { Color::NONE }),
                 )).id();
-                let cursor = builder.spawn((
+                segments[0] = spawn_segment(builder, false);
+                segments[1] = spawn_segment(builder, true);
+                cursor = builder.spawn((
                     Node {
                         width: Val::Px(2.0),
                         height: Val::Px(20.0),
@@ -89,97 +137,272 @@ impl InputBox {
                         timer: Timer::from_seconds(0.5, TimerMode::Repeating),
                     }
                 )).id();
-                builder.commands_mut().entity(input_text).insert(InputBox {
-                    text_color: text.color,
-                    hint_text: hint.to_owned(),
-                    value: "".to_owned(),
-                    cursor
-                });
+                segments[2] = spawn_segment(builder, true);
+                segments[3] = spawn_segment(builder, false);
+            });
+            let value: String = match options.max_chars {
+                Some(max) => text.text.chars().take(max).collect(),
+                None => text.text.clone(),
+            };
+            builder.commands_mut().entity(row_id).insert(InputBox {
+                text_color: text.color,
+                placeholder: placeholder.to_owned(),
+                caret: value.chars().count(),
+                value,
+                cursor,
+                segments,
+                anchor: None,
+                preedit: String::new(),
+                max_chars: options.max_chars,
+                validator: options.validator,
+                masked: options.masked,
             });
         });
     }
+
+    pub fn is_valid(&self) -> bool {
+        self.validator.as_ref().is_none_or(|validate| validate(&self.value))
+    }
+
+    /// 选中文字的起止字符序号
+    fn selection(&self) -> Option<(usize, usize)> {
+        self.anchor
+            .filter(|anchor| *anchor != self.caret)
+            .map(|anchor| (anchor.min(self.caret), anchor.max(self.caret)))
+    }
+
+    /// 选中的文字，密码输入框的内容不能复制
+    fn selected_text(&self) -> Option<String> {
+        if self.masked {
+            return None;
+        }
+        self.selection().map(|(start, end)| self.value.chars().skip(start).take(end - start).collect())
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        let Some((start, end)) = self.selection() else {
+            return false;
+        };
+        let range = byte_index(&self.value, start)..byte_index(&self.value, end);
+        self.value.replace_range(range, "");
+        self.caret = start;
+        self.anchor = None;
+        true
+    }
+
+    /// 在光标处插入文字，替换选中的内容，超出最大字符数的部分被丢弃
+    fn insert(&mut self, text: &str) {
+        self.delete_selection();
+        let room = self.max_chars.map_or(usize::MAX, |max| max.saturating_sub(self.value.chars().count()));
+        let inserted: String = text.chars().filter(|c| is_printable_char(*c)).take(room).collect();
+        let index = byte_index(&self.value, self.caret);
+        self.value.insert_str(index, &inserted);
+        self.caret += inserted.chars().count();
+    }
+
+    fn backspace(&mut self) {
+        if !self.delete_selection() && self.caret > 0 {
+            self.anchor = Some(self.caret - 1);
+            self.delete_selection();
+        }
+    }
+
+    fn delete(&mut self) {
+        if !self.delete_selection() && self.caret < self.value.chars().count() {
+            self.anchor = Some(self.caret + 1);
+            self.delete_selection();
+        }
+    }
+
+    /// 移动光标，按住Shift键时扩展选择范围
+    fn move_caret(&mut self, caret: usize, select: bool) {
+        if select {
+            self.anchor.get_or_insert(self.caret);
+        } else {
+            self.anchor = None;
+        }
+        self.caret = caret.min(self.value.chars().count());
+    }
+
+    fn select_all(&mut self) {
+        self.anchor = Some(0);
+        self.caret = self.value.chars().count();
+    }
+
+    /// 显示在四段文字中的内容，组字时把组字内容显示在光标前，密码输入框每个字符都显示为圆点
+    fn segment_texts(&self) -> [String; 4] {
+        let mask = |text: &str| -> Vec<char> {
+            if self.masked {
+                vec![MASK_CHAR; text.chars().count()]
+            } else {
+                text.chars().collect()
+            }
+        };
+        let chars = mask(&self.value);
+        let part = |start: usize, end: usize| chars[start..end].iter().collect::<String>();
+        let (start, end) = self.selection().unwrap_or((self.caret, self.caret));
+        let before = part(0, start) + &mask(&self.preedit).iter().collect::<String>();
+        if self.caret == end {
+            [before, part(start, end), String::new(), part(end, chars.len())]
+        } else {
+            [before, String::new(), part(start, end), part(end, chars.len())]
+        }
+    }
 }
 
-fn get_border_position(entity: Entity,
-                       query_parent: Query<&ChildOf>,
-                       input_border_query: Query<(&UiGlobalTransform, &InputBoxBorder), With<InputBoxBorder>>) -> (Vec2, Vec2) {
-    let border_entity = query_parent.get(entity).ok().and_then(|p| query_parent.get(p.0).ok()).map(|p| p.0);
-    let (transform, border) = input_border_query.get(border_entity.unwrap()).unwrap();
-    (transform.translation, border.0)
+/// 让输入框获得输入焦点，显示光标并打开输入法
+pub fn focus_input_box(world: &mut World, entity: Entity) {
+    match world.get_resource::<InputFocused>() {
+        // 如果本身就在输入状态则什么也不做
+        Some(focused) if focused.0 == entity => return,
+        Some(_) => blur_input_box(world),
+        None => (),
+    }
+    let Some(input_box) = world.get::<InputBox>(entity) else {
+        return;
+    };
+    let cursor = input_box.cursor;
+    let position = world.get::<UiGlobalTransform>(entity).map(|t| t.translation).unwrap_or_default();
+    if let Some(mut visibility) = world.get_mut::<Visibility>(cursor) {
+        *visibility = Visibility::Visible;
+    }
+    world.insert_resource(InputFocused(entity));
+    // 通知输入框重新显示内容
+    if let Some(mut input_box) = world.get_mut::<InputBox>(entity) {
+        input_box.set_changed();
+    }
+
+    // toggle IME
+    let mut windows = world.query::<&mut Window>();
+    if let Ok(mut window) = windows.single_mut(world) {
+        window.ime_position = position / window.scale_factor();
+        window.ime_enabled = true;
+    }
+}
+
+/// 输入框失去输入焦点，没有内容时重新显示提示文字
+pub fn blur_input_box(world: &mut World) {
+    let Some(InputFocused(entity)) = world.remove_resource::<InputFocused>() else {
+        return;
+    };
+    if let Some(mut input_box) = world.get_mut::<InputBox>(entity) {
+        input_box.anchor = None;
+        input_box.preedit.clear();
+        let cursor = input_box.cursor;
+        if let Some(mut visibility) = world.get_mut::<Visibility>(cursor) {
+            *visibility = Visibility::Hidden;
+        }
+    }
+    let mut windows = world.query::<&mut Window>();
+    if let Ok(mut window) = windows.single_mut(world) {
+        window.ime_enabled = false;
+    }
 }
 
 fn is_cursor_in_border(cursor_pos: Vec2,
                        factor: f32,
                        entity: Entity,
-                       query_parent: Query<&ChildOf>,
-                       input_border_query: Query<(&UiGlobalTransform, &InputBoxBorder), With<InputBoxBorder>>) -> bool {
-    let (translation, border_size) = get_border_position(entity, query_parent, input_border_query);
-    let half_size = border_size / 2.0;
-    let translation = translation / factor;
+                       query_parent: &Query<&ChildOf>,
+                       input_border_query: &Query<(&UiGlobalTransform, &InputBoxBorder)>) -> bool {
+    let Some((transform, border)) = query_parent.get(entity).ok()
+        .and_then(|p| input_border_query.get(p.parent()).ok()) else {
+        return false;
+    };
+    let half_size = border.0 / 2.0;
+    let translation = transform.translation / factor;
     cursor_pos.x >= translation.x - half_size.x &&
         cursor_pos.x <= translation.x + half_size.x &&
         cursor_pos.y >= translation.y - half_size.y &&
         cursor_pos.y <= translation.y + half_size.y
 }
 
+/// 按照点击位置计算光标应该在第几个字符前面，x为物理像素坐标
+fn caret_at(input_box: &InputBox,
+            x: f32,
+            segments: &Query<(&Text, &TextLayoutInfo, &UiGlobalTransform, &ComputedNode)>) -> usize {
+    let mut offset = 0;
+    for segment in input_box.segments {
+        let Ok((text, layout, transform, node)) = segments.get(segment) else {
+            continue;
+        };
+        let left = transform.translation.x - node.size.x / 2.;
+        for glyph in &layout.glyphs {
+            if x < left + glyph.position.x {
+                return offset + text.0[..glyph.byte_index].chars().count();
+            }
+        }
+        offset += text.0.chars().count();
+    }
+    input_box.value.chars().count()
+}
+
 pub fn input_box_handle_focus(
     mut commands: Commands,
     mouse_button_input: Res<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
     query_parent: Query<&ChildOf>,
-    input_border_query: Query<(&UiGlobalTransform, &InputBoxBorder), With<InputBoxBorder>>,
-    mut input_box_query: Query<
-        (
-            Entity,
-            &mut Text,
-            &mut TextColor,
-            &InputBox
-        ),
-        With<InputBox>
-    >,
-    mut cursor_query: Query<&mut Visibility, With<CursorMarker>>,
+    input_border_query: Query<(&UiGlobalTransform, &InputBoxBorder)>,
+    mut input_box_query: Query<(Entity, &mut InputBox)>,
+    segments: Query<(&Text, &TextLayoutInfo, &UiGlobalTransform, &ComputedNode)>,
     input_focused: Option<Res<InputFocused>>,
-    mut window: Single<&mut Window>,
+    window: Single<&Window>,
 ) {
     if mouse_button_input.just_pressed(MouseButton::Left) {
         let cursor_pos = window.cursor_position().unwrap_or_default();
-        for (entity, mut text, mut color,  input_box) in &mut input_box_query {
-            if is_cursor_in_border(cursor_pos, window.scale_factor(), entity, query_parent, input_border_query) {
-                // 如果本身就在输入状态则什么也不做
-                if input_focused.is_none() || input_focused.as_ref().unwrap().0 != entity {
-                    *text = Text::new(input_box.value.clone());
-                    *color = TextColor(input_box.text_color);
-                    let mut visibility = cursor_query.get_mut(input_box.cursor).unwrap();
-                    *visibility = Visibility::Visible;
-                    commands.insert_resource(InputFocused(entity));
-
-                    // toggle IME
-                    window.ime_position = window.cursor_position().unwrap();
-                    window.ime_enabled = true;
-                }
-            } else {
-                if text.0.is_empty() {
-                    *text = Text::new(input_box.hint_text.clone());
-                    *color = TextColor(HINT_TEXT_COLOR);
-                }
-                let mut visibility = cursor_query.get_mut(input_box.cursor).unwrap();
-                *visibility = Visibility::Hidden;
-                if let Some(ref e) = input_focused && e.0 == entity {
-                    commands.remove_resource::<InputFocused>();
-                    window.ime_enabled = false;
-                }
+        let clicked = input_box_query.iter().map(|(entity, _)| entity).find(|entity|
+            is_cursor_in_border(cursor_pos, window.scale_factor(), *entity, &query_parent, &input_border_query));
+        if let Some(entity) = clicked {
+            // 提示文字或组字内容不是输入的值，不能按照它们计算光标位置
+            let focused = input_focused.is_some_and(|f| f.0 == entity);
+            let (_, mut input_box) = input_box_query.get_mut(entity).unwrap();
+            if (focused || !input_box.value.is_empty()) && input_box.preedit.is_empty() {
+                let caret = caret_at(&input_box, cursor_pos.x * window.scale_factor(), &segments);
+                let select = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+                input_box.move_caret(caret, select && focused);
             }
+            commands.queue(move |world: &mut World| focus_input_box(world, entity));
+        } else if input_focused.is_some() {
+            commands.queue(blur_input_box);
+        }
+    }
+}
+
+/// 按照输入框的状态更新显示的文字、选中范围和底边颜色
+pub fn input_box_render_system(
+    input_boxes: Query<(Entity, &InputBox, &ChildOf), Changed<InputBox>>,
+    input_focused: Option<Res<InputFocused>>,
+    mut texts: Query<(&mut Text, &mut TextColor)>,
+    mut borders: Query<&mut BorderColor, With<InputBoxBorder>>,
+) {
+    for (entity, input_box, parent) in &input_boxes {
+        let focused = input_focused.as_ref().is_some_and(|f| f.0 == entity);
+        let (contents, color) = if !focused && input_box.value.is_empty() {
+            ([input_box.placeholder.clone(), String::new(), String::new(), String::new()], HINT_TEXT_COLOR)
+        } else {
+            (input_box.segment_texts(), input_box.text_color)
+        };
+        for (segment, content) in input_box.segments.iter().zip(contents) {
+            if let Ok((mut text, mut text_color)) = texts.get_mut(*segment) {
+                text.0 = content;
+                text_color.0 = color;
+            }
+        }
+        if let Ok(mut border) = borders.get_mut(parent.parent()) {
+            border.bottom = if input_box.is_valid() { INPUT_BOX_BOTTOM_COLOR } else { INPUT_BOX_INVALID_COLOR };
         }
     }
 }
 
 pub fn input_box_blink_cursor(
     focused: Res<InputFocused>,
-    input_query: Query<&InputBox, With<InputBox>>,
+    input_query: Query<&InputBox>,
     mut cursor_query: Query<(Entity, &mut Visibility, &mut CursorMarker)>,
     time: Res<Time>,
 ) {
     // InputFocused里面保存了当前有输入焦点的InputBox，其cursor成员记录了对应的光标Node
-    let current = input_query.get(focused.0).unwrap().cursor;
+    let Ok(current) = input_query.get(focused.0).map(|input_box| input_box.cursor) else {
+        return;
+    };
     for (entity, mut visibility, mut cursor)
         in cursor_query.iter_mut() {
         if cursor.timer.tick(time.delta()).just_finished() {
@@ -196,72 +419,99 @@ pub fn input_box_blink_cursor(
 
 pub fn input_box_ime_events(
     mut events: MessageReader<Ime>,
-    mut texts: Query<(Entity, &mut Text, &TextFont, &mut InputBox), With<InputBox>>,
+    mut input_boxes: Query<&mut InputBox>,
     focused: Res<InputFocused>,
-    cursors: Query<&UiGlobalTransform, With<CursorMarker>>,
-    input_borders: Query<(&UiGlobalTransform, &InputBoxBorder), With<InputBoxBorder>>,
-    parents: Query<&ChildOf>,
-    window: Single<&Window>,
 ) {
-    let (entity, mut text, font, mut input_box) =
-        texts.get_mut(focused.0).unwrap();
-    // 分别获取输入文本框和光标的位置信息
-    let (border_translation, border_size) = get_border_position(entity, parents, input_borders);
-    let cursor_translation = cursors.get(input_box.cursor).unwrap().translation;
-    // 根据光标位置判断是否还可以接受更多字符
-    let accept_more =
-        cursor_translation.x + font.font_size
-            < border_translation.x + border_size.x * window.scale_factor() / 2.0;
+    let Ok(mut input_box) = input_boxes.get_mut(focused.0) else {
+        return;
+    };
     for event in events.read() {
         match event {
             Ime::Preedit { value, cursor, .. } if !cursor.is_none() => {
-                if accept_more {
-                    *text = Text::new(format!("{}{value}", input_box.value));
-                }
+                input_box.preedit = value.clone();
+            }
+            Ime::Preedit { .. } => {
+                input_box.preedit.clear();
             }
             Ime::Commit { value, .. } => {
-                if accept_more {
-                    input_box.value += value;
-                    *text = Text::new(input_box.value.clone());
-                }
+                input_box.preedit.clear();
+                input_box.insert(value);
             }
             _ => (),
         }
     }
 }
 
+fn clipboard_text() -> Option<String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .inspect_err(|err| warn!("Failed to read clipboard: {}", err))
+        .ok()
+}
+
+fn set_clipboard_text(text: String) {
+    if let Err(err) = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)) {
+        warn!("Failed to write clipboard: {}", err);
+    }
+}
+
 pub fn input_box_keyboard_events(
     mut events: MessageReader<KeyboardInput>,
-    mut texts: Query<(Entity, &mut Text, &TextFont, &mut InputBox), With<InputBox>>,
+    mut input_boxes: Query<&mut InputBox>,
+    keyboard: Res<ButtonInput<KeyCode>>,
     focused: Res<InputFocused>,
-    cursors: Query<&UiGlobalTransform, With<CursorMarker>>,
-    input_borders: Query<(&UiGlobalTransform, &InputBoxBorder), With<InputBoxBorder>>,
-    parents: Query<&ChildOf>,
-    window: Single<&Window>
 ) {
-    let (entity, mut text, font, mut input_box) =
-        texts.get_mut(focused.0).unwrap();
-    // 分别获取输入文本框和光标的位置信息
-    let (border_translation, border_size) = get_border_position(entity, parents, input_borders);
-    let cursor_translation = cursors.get(input_box.cursor).unwrap().translation;
-    // 根据光标位置判断是否还可以接受更多字符
-    let accept_more =
-        cursor_translation.x + font.font_size
-            < border_translation.x + border_size.x * window.scale_factor() / 2.0;
+    let Ok(mut input_box) = input_boxes.get_mut(focused.0) else {
+        return;
+    };
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    // macOS上使用Command键
+    let command = keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight,
+                                        KeyCode::SuperLeft, KeyCode::SuperRight]);
     for event in events.read() {
         if !event.state.is_pressed() {
             continue;
         }
 
+        let caret = input_box.caret;
+        let len = input_box.value.chars().count();
         match (&event.logical_key, &event.text) {
-            (Key::Backspace, _) => {
-                text.pop();
-                input_box.value.pop();
+            (Key::Backspace, _) => input_box.backspace(),
+            (Key::Delete, _) => input_box.delete(),
+            (Key::ArrowLeft, _) => {
+                // 有选中内容时不按Shift键，光标移到选中内容的开头
+                let target = match input_box.selection() {
+                    Some((start, _)) if !shift => start,
+                    _ => caret.saturating_sub(1),
+                };
+                input_box.move_caret(target, shift);
+            }
+            (Key::ArrowRight, _) => {
+                let target = match input_box.selection() {
+                    Some((_, end)) if !shift => end,
+                    _ => caret + 1,
+                };
+                input_box.move_caret(target, shift);
             }
-            (_, Some(inserted_text)) => {
-                if accept_more && inserted_text.chars().all(is_printable_char) {
-                    text.push_str(inserted_text);
-                    input_box.value.push_str(inserted_text);
+            (Key::Home, _) => input_box.move_caret(0, shift),
+            (Key::End, _) => input_box.move_caret(len, shift),
+            (Key::Character(c), _) if command => match c.to_lowercase().as_str() {
+                "a" => input_box.select_all(),
+                "c" => if let Some(text) = input_box.selected_text() {
+                    set_clipboard_text(text);
+                },
+                "x" => if let Some(text) = input_box.selected_text() {
+                    set_clipboard_text(text);
+                    input_box.delete_selection();
+                },
+                "v" => if let Some(text) = clipboard_text() {
+                    input_box.insert(&text);
+                },
+                _ => continue,
+            },
+            (_, Some(inserted_text)) if !command => {
+                if inserted_text.chars().all(is_printable_char) {
+                    input_box.insert(inserted_text);
                 }
             }
             _ => continue,
@@ -275,4 +525,4 @@ fn is_printable_char(chr: char) -> bool {
         || ('\u{100000}'..='\u{10fffd}').contains(&chr);
 
     !is_in_private_use_area && !chr.is_ascii_control()
-}
\ No newline at end of file
+}
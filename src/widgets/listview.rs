@@ -1,11 +1,13 @@
 use std::cmp::PartialEq;
 use bevy::color::Color;
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
 use bevy::input_focus::InputFocus;
 use bevy::math::Vec2;
 use bevy::prelude::*;
 use crate::widgets::TextConfig;
 
 const ENTRY_HOVERED_COLOR: Color = Color::srgb_u8(161, 67, 246);
+const ROW_GAP: f32 = 10.0;
 
 pub enum ListItem {
     Image(Handle<Image>, Vec2),
@@ -26,9 +28,21 @@ enum ListItemType {
 #[derive(Component)]
 pub struct ListItemMarker(ListItemType);
 
+/// 列表项用于排序的值，数字按大小排序，文字按字典序排序
+#[derive(Clone, PartialEq, PartialOrd)]
+pub enum SortKey {
+    Number(i64),
+    Text(String),
+}
+
 pub struct ListEntry {
     pub value: String,
-    pub entities: Vec<Entity>
+    pub entities: Vec<Entity>,
+    pub keys: Vec<SortKey>,
+    // 列表项所属的分组，同一分组的列表项排列在一起
+    pub group: String,
+    // 用于过滤的文字，包括所有文字列的内容
+    search: String,
 }
 
 #[derive(Component, Default)]
@@ -36,6 +50,70 @@ pub struct ListViewMarker {
     pub entries: Vec<ListEntry>,
     pub hovered: Option<usize>,
     pub text_config: TextConfig,
+    row_height: f32,
+    // 过滤和排序后显示的列表项序号
+    order: Vec<usize>,
+    // 排序使用的SortKey序号及是否降序
+    sort: Option<(usize, bool)>,
+    filter: String,
+    // 只显示这个分组中的列表项
+    group: Option<String>,
+    dirty: bool,
+}
+
+impl ListViewMarker {
+    /// 当前排序使用的SortKey序号及是否降序
+    pub fn sort(&self) -> Option<(usize, bool)> {
+        self.sort
+    }
+
+    /// 按照第key个SortKey排序，descending为true时从大到小
+    pub fn set_sort(&mut self, key: usize, descending: bool) {
+        self.sort = Some((key, descending));
+        self.dirty = true;
+    }
+
+    /// 只显示包含filter文字的列表项，不区分大小写
+    pub fn set_filter(&mut self, filter: &str) {
+        let filter = filter.trim().to_lowercase();
+        if filter != self.filter {
+            self.filter = filter;
+            self.dirty = true;
+        }
+    }
+
+    /// 只显示group分组中的列表项，None表示显示所有分组
+    pub fn set_group(&mut self, group: Option<String>) {
+        if group != self.group {
+            self.group = group;
+            self.dirty = true;
+        }
+    }
+
+    fn update_order(&mut self) {
+        let mut order: Vec<usize> = (0..self.entries.len())
+            .filter(|i| self.entries[*i].search.contains(&self.filter))
+            .filter(|i| self.group.as_ref().is_none_or(|g| *g == self.entries[*i].group))
+            .collect();
+        // 先按分组排列，分组内再按排序条件排列
+        order.sort_by(|a, b| {
+            let (a, b) = (&self.entries[*a], &self.entries[*b]);
+            a.group.cmp(&b.group).then_with(|| match self.sort {
+                Some((key, descending)) => {
+                    let ordering = a.keys.get(key).partial_cmp(&b.keys.get(key))
+                        .unwrap_or(std::cmp::Ordering::Equal);
+                    if descending { ordering.reverse() } else { ordering }
+                }
+                None => std::cmp::Ordering::Equal,
+            })
+        });
+        self.order = order;
+    }
+
+    /// 行高加上行间距，物理像素
+    fn row_pitch(&self, node: &ComputedNode) -> f32 {
+        (self.row_height + ROW_GAP) / node.inverse_scale_factor()
+    }
 }
 
 pub struct ListView {
@@ -49,14 +127,14 @@ impl ListView {
                              marker: C,
                              text: TextConfig,
                              columns: Vec<RepeatedGridTrack>,
-                             rows: Vec<RepeatedGridTrack>,
+                             row_height: f32,
                              indicator: Option<(Handle<Image>, Vec2)>,
                              size: Option<Vec2>,
                              margin: Option<UiRect>) -> ListView {
         let (width, height) = if let Some(Vec2{x, y }) = size {
             (Val::Px(x), Val::Px(y))
         } else {
-            (Val::Percent(100.), Val::Auto)
+            (Val::Percent(100.), Val::Percent(100.))
         };
         let columns = if indicator.is_some() {
             let mut cols: Vec<RepeatedGridTrack> = vec![GridTrack::min_content()];
@@ -72,16 +150,20 @@ impl ListView {
                 height,
                 margin: margin.unwrap_or_default(),
                 grid_template_columns: columns,
-                grid_template_rows: rows,
-                row_gap: px(10),
+                // 每行高度固定，按照位置就可以算出鼠标在哪一行
+                grid_auto_rows: vec![GridTrack::px(row_height)],
+                align_content: AlignContent::Start,
+                row_gap: px(ROW_GAP),
                 column_gap: px(10),
+                overflow: Overflow::scroll_y(),
                 ..default()
             },
             Button,
+            ScrollPosition::default(),
             ListViewMarker {
-                entries: Vec::new(),
-                hovered: None,
                 text_config: text.clone(),
+                row_height,
+                ..default()
             },
             marker,
             BackgroundColor(Color::NONE),
@@ -94,12 +176,22 @@ impl ListView {
         }
     }
 
-    pub fn append(&self, commands: &mut Commands, value: String, items: Vec<ListItem>) {
+    /// 增加一个列表项，group是所属的分组（不分组时为空），keys是排序时使用的值
+    pub fn append(&self, commands: &mut Commands, value: String, group: String, items: Vec<ListItem>,
+                  keys: Vec<SortKey>) {
         let entity = self.entity;
         let text_config = self.text_config.clone();
+        let search = items.iter()
+            .filter_map(|item| if let ListItem::Text(text) = item { Some(text.to_lowercase()) } else { None })
+            .chain(std::iter::once(value.to_lowercase()))
+            .collect::<Vec<_>>()
+            .join("\n");
         let mut entry = ListEntry {
             value,
-            entities: Vec::new()
+            entities: Vec::new(),
+            keys,
+            group,
+            search,
         };
         // 如果设定了选择指示器图标，则增加一个列定义
         let items = if let Some(ref indicator) = self.indicator {
@@ -161,6 +253,7 @@ impl ListView {
 
             if let Some(mut listview) = world.get_mut::<ListViewMarker>(entity) {
                 listview.entries.push(entry);
+                listview.dirty = true;
             };
         });
     }
@@ -230,70 +323,193 @@ pub fn listview_interaction_system(
     }
 }
 
+/// 切换高亮显示的列表项，同时显示或隐藏选择指示器
+fn highlight_entry(
+    listview: &mut ListViewMarker,
+    hovered: Option<usize>,
+    item_query: &mut Query<(&mut ListItemMarker, &mut Visibility, Option<&mut TextColor>)>,
+) {
+    if listview.hovered == hovered {
+        return;
+    }
+    // 如果之前有 hovered，就先重置
+    if let Some(last_hovered) = listview.hovered {
+        for e in listview.entries[last_hovered].entities.iter() {
+            let (marker, mut vis, color) = item_query.get_mut(*e).unwrap();
+            if let Some(mut color) = color {
+                *color = TextColor(listview.text_config.color);
+            }
+            if marker.0 == ListItemType::Indicator {
+                *vis = Visibility::Hidden;
+            }
+        }
+    }
+
+    // 如果现在有新的 hovered，就设置
+    if let Some(current) = hovered {
+        for e in listview.entries[current].entities.iter() {
+            let (marker, mut vis, color) = item_query.get_mut(*e).unwrap();
+            if let Some(mut color) = color {
+                *color = TextColor(ENTRY_HOVERED_COLOR);
+            }
+            if marker.0 == ListItemType::Indicator {
+                *vis = Visibility::Visible;
+            }
+        }
+    }
+
+    // 更新状态
+    listview.hovered = hovered;
+}
+
+/// 按照鼠标位置计算所在的行，不需要遍历所有列表项
+fn entry_at(listview: &ListViewMarker, pos: Vec2, transform: &UiGlobalTransform, node: &ComputedNode,
+            scroll: &ScrollPosition) -> Option<usize> {
+    let half_size = node.size / 2.;
+    let local = pos - transform.translation;
+    if local.x.abs() > half_size.x || local.y.abs() > half_size.y {
+        return None;
+    }
+    let top = local.y + half_size.y - node.border.min_inset.y - node.padding.min_inset.y;
+    let y = top + scroll.y / node.inverse_scale_factor();
+    if y < 0. {
+        return None;
+    }
+    let pitch = listview.row_pitch(node);
+    // 落在行间距中不算选中任何一行
+    if y % pitch > pitch - ROW_GAP / node.inverse_scale_factor() {
+        return None;
+    }
+    listview.order.get((y / pitch) as usize).copied()
+}
+
 pub fn listview_cursor_move_system(
     input_focus: Res<InputFocus>,
-    mut cursor_moved_events: MessageReader<CursorMoved>,
-    mut listview_query: Query<&mut ListViewMarker>,
-    mut item_query: Query<
-        (
-            &UiGlobalTransform,
-            &ComputedNode,
-            &mut ListItemMarker,
-            &mut Visibility,
-            Option<&mut TextColor>,
-        ),
-        With<ListItemMarker>>,
+    mut listview_query: Query<(&mut ListViewMarker, &UiGlobalTransform, &ComputedNode, &ScrollPosition)>,
+    mut item_query: Query<(&mut ListItemMarker, &mut Visibility, Option<&mut TextColor>)>,
     window: Single<&Window>
 ) {
     if let Some(entity) = input_focus.0
-        && let Ok(mut listview) = listview_query.get_mut(entity) {
-        if let Some(event) = cursor_moved_events.read().last() {
-            let pos = event.position * window.scale_factor();
-            let mut hovered_entry: Option<usize> = None;
-            for (index, entry) in listview.entries.iter().enumerate() {
-                let (transform, node, _, _, _) =
-                    item_query.get(entry.entities[1]).unwrap();
+        && let Ok((mut listview, transform, node, scroll)) = listview_query.get_mut(entity)
+        && let Some(position) = window.cursor_position() {
+        let pos = position * window.scale_factor();
+        let hovered_entry = entry_at(&listview, pos, transform, node, scroll);
+        highlight_entry(&mut listview, hovered_entry, &mut item_query);
+    }
+}
 
-                let y1 = transform.translation.y - node.size.y / 2.;
-                let y2 = transform.translation.y + node.size.y / 2.;
-                if pos.y > y1 && pos.y < y2 {
-                    hovered_entry = Some(index);
-                    break;
-                }
+/// 能滚动的最大距离，逻辑像素
+fn max_scroll(node: &ComputedNode) -> f32 {
+    ((node.content_size.y - node.size.y) * node.inverse_scale_factor()).max(0.)
+}
+
+/// 鼠标在列表上时用滚轮滚动列表
+pub fn listview_scroll_system(
+    mut wheel_events: MessageReader<MouseWheel>,
+    mut listview_query: Query<(&ListViewMarker, &Interaction, &ComputedNode, &mut ScrollPosition)>,
+) {
+    for event in wheel_events.read() {
+        for (listview, interaction, node, mut scroll) in &mut listview_query {
+            if *interaction == Interaction::None {
+                continue;
             }
+            let dy = match event.unit {
+                MouseScrollUnit::Line => event.y * (listview.row_height + ROW_GAP),
+                MouseScrollUnit::Pixel => event.y,
+            };
+            scroll.y = (scroll.y - dy).clamp(0., max_scroll(node));
+        }
+    }
+}
 
-            if listview.hovered != hovered_entry {
-                // 如果之前有 hovered，就先重置
-                if let Some(last_hovered) = listview.hovered {
-                    for e in listview.entries[last_hovered].entities.iter() {
-                        let (_, _, marker, mut vis, color)
-                            = item_query.get_mut(*e).unwrap();
-                        if let Some(mut color) = color {
-                            *color = TextColor(listview.text_config.color);
-                        }
-                        if marker.0 == ListItemType::Indicator {
-                            *vis = Visibility::Hidden;
-                        }
-                    }
+/// 过滤或排序条件改变后，重新排列列表项的位置
+pub fn listview_layout_system(
+    mut listview_query: Query<(&mut ListViewMarker, &mut ScrollPosition), Changed<ListViewMarker>>,
+    mut node_query: Query<&mut Node, With<ListItemMarker>>,
+    mut item_query: Query<(&mut ListItemMarker, &mut Visibility, Option<&mut TextColor>)>,
+) {
+    for (mut listview, mut scroll) in &mut listview_query {
+        if !listview.dirty {
+            continue;
+        }
+        listview.dirty = false;
+        highlight_entry(&mut listview, None, &mut item_query);
+        listview.update_order();
+        for entry in &listview.entries {
+            for e in &entry.entities {
+                if let Ok(mut node) = node_query.get_mut(*e) {
+                    node.display = Display::None;
                 }
-
-                // 如果现在有新的 hovered，就设置
-                if let Some(current) = hovered_entry {
-                    for e in listview.entries[current].entities.iter() {
-                        let (_, _, marker, mut vis, color)
-                            = item_query.get_mut(*e).unwrap();
-                        if let Some(mut color) = color {
-                            *color = TextColor(ENTRY_HOVERED_COLOR);
-                        }
-                        if marker.0 == ListItemType::Indicator {
-                            *vis = Visibility::Visible;
-                        }
-                    }
+            }
+        }
+        for (row, index) in listview.order.iter().enumerate() {
+            for (column, e) in listview.entries[*index].entities.iter().enumerate() {
+                if let Ok(mut node) = node_query.get_mut(*e) {
+                    node.display = Display::DEFAULT;
+                    node.grid_row = GridPlacement::start(row as i16 + 1);
+                    node.grid_column = GridPlacement::start(column as i16 + 1);
                 }
-
-                // 更新状态
-                listview.hovered = hovered_entry;
             }
         }
+        scroll.y = 0.;
     }
-}
\ No newline at end of file
+}
+
+/// 滚动列表使第row行完整显示
+fn scroll_to_row(listview: &ListViewMarker, row: usize, node: &ComputedNode, scroll: &mut ScrollPosition) {
+    let pitch = listview.row_height + ROW_GAP;
+    let top = row as f32 * pitch;
+    let visible = node.size.y * node.inverse_scale_factor();
+    if top < scroll.y {
+        scroll.y = top;
+    } else if top + listview.row_height > scroll.y + visible {
+        scroll.y = top + listview.row_height - visible;
+    }
+    scroll.y = scroll.y.max(0.);
+}
+
+/// 列表获得焦点时，用上下方向键、PageUp/PageDown、Home/End移动高亮的列表项并滚动列表，回车键或空格键选中
+pub fn listview_keyboard_system(
+    input_focus: Res<InputFocus>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut listview_query: Query<(Entity, &mut ListViewMarker, &ComputedNode, &mut ScrollPosition)>,
+    mut item_query: Query<(&mut ListItemMarker, &mut Visibility, Option<&mut TextColor>)>,
+    mut writer: MessageWriter<ListViewSelectionChanged>
+) {
+    let Some((entity, mut listview, node, mut scroll))
+        = input_focus.0.and_then(|e| listview_query.get_mut(e).ok()) else {
+        return;
+    };
+    let count = listview.order.len();
+    if count == 0 {
+        return;
+    }
+    // 一页能显示的行数
+    let page = ((node.size.y * node.inverse_scale_factor() / (listview.row_height + ROW_GAP)) as usize).max(1);
+    let current = listview.hovered.and_then(|h| listview.order.iter().position(|i| *i == h));
+    let row = if keyboard.just_pressed(KeyCode::ArrowDown) {
+        current.map_or(0, |i| (i + 1).min(count - 1))
+    } else if keyboard.just_pressed(KeyCode::ArrowUp) {
+        current.map_or(0, |i| i.saturating_sub(1))
+    } else if keyboard.just_pressed(KeyCode::PageDown) {
+        current.map_or(0, |i| (i + page).min(count - 1))
+    } else if keyboard.just_pressed(KeyCode::PageUp) {
+        current.map_or(0, |i| i.saturating_sub(page))
+    } else if keyboard.just_pressed(KeyCode::Home) {
+        0
+    } else if keyboard.just_pressed(KeyCode::End) {
+        count - 1
+    } else {
+        if keyboard.any_just_released([KeyCode::Enter, KeyCode::Space])
+            && let Some(selected) = listview.hovered {
+            writer.write(ListViewSelectionChanged {
+                entity,
+                value: listview.entries[selected].value.clone(),
+            });
+        }
+        return;
+    };
+    let next = listview.order[row];
+    highlight_entry(&mut listview, Some(next), &mut item_query);
+    scroll_to_row(&listview, row, node, &mut scroll);
+}
@@ -0,0 +1,183 @@
+use std::cmp::Ordering;
+use bevy::ecs::system::SystemParam;
+use bevy::input_focus::{InputFocus, InputFocusVisible};
+use bevy::prelude::*;
+use super::{blur_input_box, focus_input_box, ButtonClicked, Enabled, InputBox, InputFocused, ListViewMarker,
+            ModelDialogRoot, Slider};
+
+const FOCUS_RING_COLOR: Color = Color::srgb_u8(96, 211, 255);
+// 纵坐标相差不超过这个距离（物理像素）的控件算作同一行
+const SAME_ROW_DISTANCE: f32 = 8.0;
+
+/// 取消按钮，按Esc键相当于点击
+#[derive(Component)]
+pub struct CancelButton;
+
+/// 显示了焦点框的节点
+#[derive(Component)]
+pub struct FocusRing;
+
+/// 当前可以获得键盘焦点的控件，打开对话框时只包括对话框中的控件
+#[derive(SystemParam)]
+pub struct Focusables<'w, 's> {
+    nodes: Query<'w, 's, (Entity, &'static UiGlobalTransform, &'static ComputedNode, &'static InheritedVisibility,
+                          Option<&'static Enabled>), Or<(With<Interaction>, With<InputBox>)>>,
+    dialogs: Query<'w, 's, (Entity, Option<&'static GlobalZIndex>), With<ModelDialogRoot>>,
+    parents: Query<'w, 's, &'static ChildOf>,
+}
+
+impl Focusables<'_, '_> {
+    /// 最上层的对话框
+    fn scope(&self) -> Option<Entity> {
+        self.dialogs.iter()
+            .max_by_key(|(_, z)| z.map_or(0, |z| z.0))
+            .map(|(entity, _)| entity)
+    }
+
+    fn in_scope(&self, entity: Entity, scope: Option<Entity>) -> bool {
+        match scope {
+            Some(root) => self.parents.iter_ancestors(entity).any(|e| e == root),
+            None => true,
+        }
+    }
+
+    /// 按照从上到下、从左到右的顺序返回所有控件及其中心位置
+    fn ordered(&self) -> Vec<(Entity, Vec2)> {
+        let scope = self.scope();
+        let mut nodes: Vec<(Entity, Vec2)> = self.nodes.iter()
+            .filter(|(_, _, node, visibility, enabled)|
+                visibility.get() && node.size.x > 0. && enabled.is_none_or(|e| e.0))
+            .filter(|(entity, ..)| self.in_scope(*entity, scope))
+            .map(|(entity, transform, ..)| (entity, transform.translation))
+            .collect();
+        nodes.sort_by(|(_, a), (_, b)| {
+            if (a.y - b.y).abs() > SAME_ROW_DISTANCE {
+                a.y.total_cmp(&b.y)
+            } else {
+                a.x.partial_cmp(&b.x).unwrap_or(Ordering::Equal)
+            }
+        });
+        nodes
+    }
+}
+
+/// 在方向键指向的方向上找最近的控件，偏离方向的距离加倍计算
+fn nearest_in_direction(nodes: &[(Entity, Vec2)], from: Vec2, direction: Vec2) -> Option<Entity> {
+    nodes.iter()
+        .filter_map(|(entity, position)| {
+            let offset = *position - from;
+            let along = offset.dot(direction);
+            (along > 0.).then(|| (*entity, along + offset.perp_dot(direction).abs() * 2.))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity)
+}
+
+/// Tab/Shift+Tab按顺序切换焦点，方向键按位置切换焦点，在输入框中按回车键跳到下一个控件
+pub fn keyboard_navigation_system(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut input_focus: ResMut<InputFocus>,
+    mut focus_visible: ResMut<InputFocusVisible>,
+    input_focused: Option<Res<InputFocused>>,
+    focusables: Focusables,
+    widgets: Query<(Has<Slider>, Has<ListViewMarker>, Has<InputBox>)>,
+) {
+    let current = input_focused.map(|f| f.0).or(input_focus.0);
+    // 滑动条自己处理左右键，列表自己处理上下键，输入框的左右键用于移动光标
+    let (slider, list, input) = current.and_then(|e| widgets.get(e).ok()).unwrap_or_default();
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let direction = if keyboard.just_pressed(KeyCode::ArrowUp) && !list {
+        Some(Vec2::NEG_Y)
+    } else if keyboard.just_pressed(KeyCode::ArrowDown) && !list {
+        Some(Vec2::Y)
+    } else if keyboard.just_pressed(KeyCode::ArrowLeft) && !slider && !input {
+        Some(Vec2::NEG_X)
+    } else if keyboard.just_pressed(KeyCode::ArrowRight) && !slider && !input {
+        Some(Vec2::X)
+    } else {
+        None
+    };
+    let tab = keyboard.just_pressed(KeyCode::Tab) || (input && keyboard.just_released(KeyCode::Enter));
+    if direction.is_none() && !tab {
+        return;
+    }
+
+    let nodes = focusables.ordered();
+    if nodes.is_empty() {
+        return;
+    }
+    let index = current.and_then(|c| nodes.iter().position(|(e, _)| *e == c));
+    let next = match (index, direction) {
+        (Some(i), Some(direction)) => nearest_in_direction(&nodes, nodes[i].1, direction),
+        (Some(i), None) if shift => Some(nodes[(i + nodes.len() - 1) % nodes.len()].0),
+        (Some(i), None) => Some(nodes[(i + 1) % nodes.len()].0),
+        (None, _) if shift => nodes.last().map(|(e, _)| *e),
+        (None, _) => nodes.first().map(|(e, _)| *e),
+    };
+    let Some(next) = next else {
+        return;
+    };
+
+    if input && current != Some(next) {
+        commands.queue(blur_input_box);
+    }
+    if widgets.get(next).is_ok_and(|(_, _, input)| input) {
+        commands.queue(move |world: &mut World| focus_input_box(world, next));
+    }
+    input_focus.set(next);
+    focus_visible.0 = true;
+}
+
+/// 按Esc键点击当前界面（或最上层对话框）中的取消按钮
+pub fn cancel_button_system(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    focusables: Focusables,
+    buttons: Query<(Entity, &InheritedVisibility), With<CancelButton>>,
+    mut writer: MessageWriter<ButtonClicked>,
+) {
+    if !keyboard.just_released(KeyCode::Escape) {
+        return;
+    }
+    let scope = focusables.scope();
+    if let Some((entity, _)) = buttons.iter()
+        .find(|(entity, visibility)| visibility.get() && focusables.in_scope(*entity, scope)) {
+        writer.write(ButtonClicked { entity });
+    }
+}
+
+/// 移动鼠标后隐藏焦点框，鼠标悬停不需要焦点框
+pub fn hide_focus_ring_system(mut focus_visible: ResMut<InputFocusVisible>) {
+    if focus_visible.0 {
+        focus_visible.0 = false;
+    }
+}
+
+/// 使用键盘切换焦点时在获得焦点的控件周围显示焦点框，输入框的焦点框显示在边框上
+pub fn focus_ring_system(
+    mut commands: Commands,
+    input_focus: Res<InputFocus>,
+    focus_visible: Res<InputFocusVisible>,
+    rings: Query<Entity, With<FocusRing>>,
+    input_boxes: Query<(), With<InputBox>>,
+    parents: Query<&ChildOf>,
+) {
+    for entity in &rings {
+        commands.entity(entity).remove::<(Outline, FocusRing)>();
+    }
+    let Some(focused) = input_focus.0 else {
+        return;
+    };
+    if !focus_visible.0 {
+        return;
+    }
+    let target = if input_boxes.contains(focused) {
+        parents.get(focused).map_or(focused, |p| p.parent())
+    } else {
+        focused
+    };
+    commands.entity(target).try_insert((
+        Outline::new(Val::Px(2.), Val::Px(2.), FOCUS_RING_COLOR),
+        FocusRing,
+    ));
+}
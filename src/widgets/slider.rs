@@ -0,0 +1,170 @@
+use bevy::input_focus::InputFocus;
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+
+const SLIDER_TRACK_HEIGHT: f32 = 6.0;
+const SLIDER_HANDLE_SIZE: f32 = 18.0;
+const SLIDER_TRACK_COLOR: Color = Color::srgb_u8(76, 69, 113);
+const SLIDER_FILL_COLOR: Color = Color::srgb_u8(147, 51, 234);
+const SLIDER_HANDLE_COLOR: Color = Color::srgb_u8(240, 240, 240);
+const SLIDER_HANDLE_HOVERED_COLOR: Color = Color::srgb_u8(161, 67, 246);
+
+/// 拖动选择数值的滑动条，value在min和max之间，按step取整
+#[derive(Component)]
+pub struct Slider {
+    pub min: f32,
+    pub max: f32,
+    pub step: f32,
+    pub value: f32,
+}
+
+#[derive(Component)]
+pub struct SliderFill;
+
+#[derive(Component)]
+pub struct SliderHandle;
+
+#[derive(Message)]
+pub struct SliderChanged {
+    pub entity: Entity,
+    pub value: f32,
+}
+
+impl Slider {
+    pub fn new<C: Component>(marker: C, min: f32, max: f32, step: f32, value: f32,
+                             width: f32, margin: UiRect) -> impl Bundle {
+        let slider = Slider { min, max, step, value: value.clamp(min, max) };
+        let percent = slider.fraction() * 100.;
+        (
+            Node {
+                width: Val::Px(width),
+                height: Val::Px(SLIDER_HANDLE_SIZE),
+                align_items: AlignItems::Center,
+                margin,
+                ..default()
+            },
+            bevy::ui::prelude::Button,
+            RelativeCursorPosition::default(),
+            slider,
+            marker,
+            children![
+                (
+                    Node {
+                        width: Val::Percent(100.),
+                        height: Val::Px(SLIDER_TRACK_HEIGHT),
+                        border_radius: BorderRadius::MAX,
+                        ..default()
+                    },
+                    BackgroundColor(SLIDER_TRACK_COLOR),
+                    children![(
+                        Node {
+                            width: Val::Percent(percent),
+                            height: Val::Percent(100.),
+                            border_radius: BorderRadius::MAX,
+                            ..default()
+                        },
+                        BackgroundColor(SLIDER_FILL_COLOR),
+                        SliderFill,
+                    )]
+                ),
+                (
+                    Node {
+                        position_type: PositionType::Absolute,
+                        left: Val::Percent(percent),
+                        width: Val::Px(SLIDER_HANDLE_SIZE),
+                        height: Val::Px(SLIDER_HANDLE_SIZE),
+                        margin: UiRect::left(Val::Px(-SLIDER_HANDLE_SIZE / 2.)),
+                        border_radius: BorderRadius::MAX,
+                        ..default()
+                    },
+                    BackgroundColor(SLIDER_HANDLE_COLOR),
+                    SliderHandle,
+                )
+            ],
+        )
+    }
+
+    /// 当前数值在滑动条上的位置，0到1之间
+    pub fn fraction(&self) -> f32 {
+        if self.max > self.min { (self.value - self.min) / (self.max - self.min) } else { 0. }
+    }
+
+    /// 按照滑动条上的位置设置数值，数值有变化时返回true
+    pub fn set_fraction(&mut self, fraction: f32) -> bool {
+        let mut value = self.min + fraction.clamp(0., 1.) * (self.max - self.min);
+        if self.step > 0. {
+            value = self.min + ((value - self.min) / self.step).round() * self.step;
+        }
+        let value = value.clamp(self.min, self.max);
+        if value == self.value {
+            return false;
+        }
+        self.value = value;
+        true
+    }
+}
+
+/// 按住鼠标拖动滑块时更新数值
+pub fn slider_interaction_system(
+    mut sliders: Query<(Entity, &Interaction, &RelativeCursorPosition, &mut Slider)>,
+    mut writer: MessageWriter<SliderChanged>,
+) {
+    for (entity, interaction, cursor, mut slider) in &mut sliders {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        // normalized以节点中心为原点，范围是-0.5到0.5
+        if let Some(position) = cursor.normalized
+            && slider.set_fraction(position.x + 0.5) {
+            writer.write(SliderChanged { entity, value: slider.value });
+        }
+    }
+}
+
+/// 滑动条获得焦点时，用左右方向键按step调整数值
+pub fn slider_keyboard_system(
+    input_focus: Res<InputFocus>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut sliders: Query<&mut Slider>,
+    mut writer: MessageWriter<SliderChanged>,
+) {
+    let Some((entity, mut slider)) = input_focus.0.and_then(|e| sliders.get_mut(e).ok().map(|s| (e, s))) else {
+        return;
+    };
+    let step = if slider.step > 0. { slider.step } else { (slider.max - slider.min) / 20. };
+    let value = if keyboard.just_pressed(KeyCode::ArrowRight) {
+        slider.value + step
+    } else if keyboard.just_pressed(KeyCode::ArrowLeft) {
+        slider.value - step
+    } else {
+        return;
+    };
+    let value = value.clamp(slider.min, slider.max);
+    if value != slider.value {
+        slider.value = value;
+        writer.write(SliderChanged { entity, value });
+    }
+}
+
+pub fn slider_update_system(
+    sliders: Query<(&Slider, &Interaction, &Children), Or<(Changed<Slider>, Changed<Interaction>)>>,
+    children_query: Query<&Children>,
+    mut fills: Query<&mut Node, With<SliderFill>>,
+    mut handles: Query<(&mut Node, &mut BackgroundColor), (With<SliderHandle>, Without<SliderFill>)>,
+) {
+    for (slider, interaction, children) in &sliders {
+        let percent = slider.fraction() * 100.;
+        if let Some(fill) = children_query.get(children[0]).ok().and_then(|c| c.first())
+            && let Ok(mut node) = fills.get_mut(*fill) {
+            node.width = Val::Percent(percent);
+        }
+        if let Ok((mut node, mut color)) = handles.get_mut(children[1]) {
+            node.left = Val::Percent(percent);
+            *color = if *interaction == Interaction::None {
+                SLIDER_HANDLE_COLOR.into()
+            } else {
+                SLIDER_HANDLE_HOVERED_COLOR.into()
+            };
+        }
+    }
+}
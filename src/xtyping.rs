@@ -1,20 +1,36 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 #![doc = include_str!("../README.md")]
 
+mod accessibility;
+mod classroom;
+mod cli;
 mod gaming;
+mod guardian;
+mod heatmap;
+mod keystats;
+mod playtime;
 mod register;
+mod settings;
 mod startup;
+mod texts;
+mod theme;
 mod ui;
 mod widgets;
 
+use bevy::asset::io::AssetSourceBuilder;
 use bevy::input_focus::InputFocus;
 use bevy::prelude::*;
 use bevy::window::WindowPlugin;
-use rand::prelude::SliceRandom;
+use gaming::common::{EnemyKind, FlyingUnitKind};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use std::{fs, io};
+use xtyping_core::letters::LetterPool;
+use xtyping_core::routes::RouteTable;
+use xtyping_core::scoring::ScoringSettings;
 
 const GAME_APP_TITLE: &str = "超级打字练习";
 const GAME_APP_NAME: &str = "xtyping";
@@ -23,8 +39,16 @@ const PLAYERS_DATA_FILE: &str = "players.json";
 
 const WARSHIP_SENTENCES_FILE: &str = "sentences.json";
 
+const CAMPAIGN_FILE: &str = "campaign.json";
+
 fn main() {
-    App::new()
+    // 子命令在这里执行完后直接退出，不会打开游戏窗口
+    let options = cli::parse_args();
+    let themes_dir = theme::themes_dir().display().to_string();
+    let mut app = App::new();
+    app
+        // 主题目录中的资源需要在AssetPlugin之前注册
+        .register_asset_source(theme::THEME_ASSET_SOURCE, AssetSourceBuilder::platform_default(&themes_dir, None))
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 title: GAME_APP_TITLE.to_string(),
@@ -42,15 +66,26 @@ fn main() {
         .init_resource::<GameFonts>()
         .init_resource::<Players>()
         .init_resource::<ExplosionTexture>()
+        .init_resource::<theme::Theme>()
+        .init_resource::<accessibility::Accessibility>()
         .add_systems(OnEnter(GameState::Init), init_resources)
         .add_systems(Startup, setup_camera)
         .add_plugins((
             startup::startup_plugin,
             register::new_player_plugin,
             gaming::play_game_plugin,
+            guardian::guardian_plugin,
+            settings::settings_plugin,
+            heatmap::heatmap_plugin,
             widgets::widgets_plugin,
-        ))
-        .run();
+        ));
+    if options.fullscreen.is_some() {
+        app.insert_resource(settings::DisplayModeOverride(options.fullscreen));
+    }
+    if let Some(quick_start) = options.quick_start {
+        app.insert_resource(quick_start);
+    }
+    app.run();
 }
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
@@ -61,6 +96,9 @@ enum GameState {
     Register,
     Gaming,
     Restart,
+    Guardian,
+    Settings,
+    Heatmap,
 }
 
 /// 玩游戏过程中的可能状态
@@ -76,6 +114,8 @@ enum PlayState {
     Checkpoint, // 游戏过关
     Upgrading,  // 升级祝贺
     Failed,     // 玩家失败
+    LessonDone, // 课程结束
+    Resting,    // 强制休息
 }
 
 #[derive(Resource, Default)]
@@ -93,13 +133,73 @@ struct Player {
     avatar: String,
     score: u32,
     level: u32,
+    // 是否根据玩家表现自动调整难度
+    #[serde(default)]
+    auto_difficulty: bool,
+    // 已经通过的打字课程数量，与闯关的级别分开记录
+    #[serde(default)]
+    lesson: u32,
+    // 玩家选择的主题目录，空字符串表示内置主题
+    #[serde(default)]
+    theme: String,
+    #[serde(default)]
+    accessibility: accessibility::Accessibility,
+    // 玩家所在的班级，从班级名单导入，空字符串表示不属于任何班级
+    #[serde(default)]
+    class: String,
+    #[serde(default)]
+    stats: PracticeStats,
+}
+
+/// 玩家累计的练习数据，用于生成成绩报告
+#[derive(Deserialize, Serialize, Clone, Default, PartialEq)]
+struct PracticeStats {
+    // 击落和漏掉的敌机数量
+    hits: u32,
+    misses: u32,
+    // 按错和按对的字符键数量
+    wrong_keys: u32,
+    correct_keys: u32,
+    // 累计的练习秒数
+    practice_secs: f32,
+}
+
+impl PracticeStats {
+    /// 准确率，按错键和漏掉的敌机都算作错误。还没有玩过时返回None
+    fn accuracy(&self) -> Option<f32> {
+        let total = self.hits + self.misses + self.wrong_keys;
+        (total > 0).then(|| self.hits as f32 / total as f32)
+    }
+
+    /// 每分钟按对的字符数，还没有练习过时返回None
+    fn cpm(&self) -> Option<f32> {
+        (self.practice_secs >= 1.).then(|| self.correct_keys as f32 * 60. / self.practice_secs)
+    }
 }
 
 #[derive(Deserialize, Resource, Default)]
 struct Players(Vec<Player>);
 
-const MAX_PLAYERS_COUNT: usize = 7;
-const MAX_PLAYER_LEVELS: u32 = 5;
+const MAX_PLAYERS_COUNT: usize = 500;
+
+const PLAYER_AVATARS: [&str; 28] = [
+    "whale", "cat", "cool", "donatello", "dragon", "swordsman", "robot",
+    "elephant", "ghost", "hero", "hero-boy", "hero-girl", "hero-girl2", "sun",
+    "kitty", "monkey", "monkey-cool", "panda", "panda-sleep", "assasin", "spierman",
+    "sea-turtle", "snake", "tiger", "angel", "rabbit", "smiling", "dog"];
+// 星级图片的数量，更高的级别只显示一颗星并在旁边标注级别
+const LEVEL_STAR_IMAGES: u32 = 5;
+
+/// 玩家级别对应的星级图片及图片中星星的数量
+fn level_star_image(level: u32) -> (String, u32) {
+    let stars = if level > LEVEL_STAR_IMAGES { 1 } else { level.max(1) };
+    (format!("images/star-{}.png", stars), stars)
+}
+
+/// 超过星级图片数量的级别显示的文字
+fn level_star_label(level: u32) -> String {
+    if level > LEVEL_STAR_IMAGES { format!("{}", level) } else { String::new() }
+}
 
 impl Players {
     pub fn get(&self, name: &str) -> &Player {
@@ -107,22 +207,27 @@ impl Players {
     }
 }
 
-const DEFAULT_ROUTE_HEIGHT: f32 = 40.;
-const MAX_ROUTE_COUNT: usize = 64;
 const GAME_INFO_AREA_HEIGHT: f32 = 70.;
 const GAME_INFO_AREA_MARGIN: f32 = 30.;
 
-#[derive(Clone, Default)]
-struct Route {
-    pub id: i32,
-    pub entities: Vec<Entity>,
+/// 界面缩放后信息栏及其下方空白占用的高度
+fn info_area_height(ui_scale: f32) -> f32 {
+    (GAME_INFO_AREA_HEIGHT + GAME_INFO_AREA_MARGIN) * ui_scale
 }
 
-impl Route {
-    pub fn get_position(&self, window_height: f32) -> f32 {
-        let start = window_height / 2. - GAME_INFO_AREA_HEIGHT - GAME_INFO_AREA_MARGIN;
-        start - self.id as f32 * DEFAULT_ROUTE_HEIGHT - DEFAULT_ROUTE_HEIGHT / 2.
-    }
+/// 第一条航道上边缘的纵坐标，航道从信息栏下方开始排列
+fn routes_top(window_height: f32, ui_scale: f32) -> f32 {
+    window_height / 2. - info_area_height(ui_scale)
+}
+
+/// 游戏模式
+#[derive(Clone, Copy, Default, PartialEq)]
+enum GameMode {
+    // 闯关游戏，按照玩家级别出现字符
+    #[default]
+    Arcade,
+    // 打字课程，只出现已经学过的键
+    Lesson,
 }
 
 #[derive(Resource, Default)]
@@ -130,30 +235,46 @@ struct GamePlayer {
     pub player: Player,
     pub safe_position: f32,
     pub health: u16,
+    pub mode: GameMode,
+    // 命令行用--level跳到了指定级别或课程，本次的进度不保存到玩家数据
+    pub jumped: bool,
 }
 
-#[derive(Resource, Default)]
-struct GameRoutes {
-    pub empty_routes: Vec<Route>,
-    pub used_routes: Vec<Route>,
-}
+/// 敌机的航道，航道上记录的是飞行单元的实体
+#[derive(Resource, Default, Deref, DerefMut)]
+struct GameRoutes(RouteTable<Entity>);
 
-#[derive(Resource, Default)]
-struct GameLetters {
-    pub candidate_letters: Vec<char>,
-    pub choosed_letters: Vec<char>,
-}
+/// 当前关卡敌机使用的字符
+#[derive(Resource, Default, Deref, DerefMut)]
+struct GameLetters(LetterPool);
 
 #[derive(Resource, Default)]
 struct ExplosionTexture {
     pub texture: Handle<Image>,
     pub layout: Handle<TextureAtlasLayout>,
+    // 爆炸动画的帧数
+    pub frames: usize,
 }
 
-fn get_app_data_dir(app_name: &str) -> PathBuf {
-    let mut base_dir = dirs::data_dir().expect("无法获取用户数据目录");
-    base_dir.push(app_name);
+// 命令行指定的数据目录，没有指定时使用系统的用户数据目录
+static APP_DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// 使用指定的目录保存所有数据，需要在读取任何数据之前调用
+fn set_app_data_dir(dir: PathBuf) {
+    APP_DATA_DIR.set(dir).ok();
+}
 
+/// 数据目录的位置，不会创建目录
+fn app_data_dir_path(app_name: &str) -> PathBuf {
+    APP_DATA_DIR.get().cloned().unwrap_or_else(|| {
+        let mut dir = dirs::data_dir().expect("无法获取用户数据目录");
+        dir.push(app_name);
+        dir
+    })
+}
+
+fn get_app_data_dir(app_name: &str) -> PathBuf {
+    let base_dir = app_data_dir_path(app_name);
     fs::create_dir_all(&base_dir).expect("创建应用数据目录失败");
     base_dir
 }
@@ -179,170 +300,465 @@ fn sync_sentences_with_file(data: &mut Vec<Vec<String>>, file_path: &Path) -> io
     Ok(())
 }
 
+/// 一波敌机的数量及出现的时间间隔
+#[derive(Deserialize, Serialize, Clone)]
+struct WaveSettings {
+    pub count: usize,
+    pub interval: (f32, f32),
+    // 这一波开始前额外等待的时间
+    #[serde(default)]
+    pub pause: f32,
+}
+
+/// 一个关卡（玩家级别）的全部设置
+#[derive(Deserialize, Serialize, Clone)]
+struct LevelSettings {
+    // 这一关可能出现的字符，重复的字符出现的概率更高
+    pub letters: String,
+    // 战舰的句子，为空时使用句子资源文件中对应级别的句子
+    #[serde(default)]
+    pub sentences: Vec<String>,
+    // 飞行速度区间
+    pub speed: (f32, f32),
+    // 依次出现的各波敌机
+    pub waves: Vec<WaveSettings>,
+    // 各种敌机出现的权重
+    pub enemy_weights: Vec<(EnemyKind, u32)>,
+    // 各种装备出现的时间间隔，没有设置的装备不会出现
+    pub equipment_intervals: HashMap<FlyingUnitKind, (f32, f32)>,
+    // 战舰的发射时间间隔
+    pub warship_fire_interval: f32,
+    // 战舰单个火炮发射间隔
+    pub warship_gun_interval: f32,
+    // 升级到下一级需要的分数，最后一关不需要
+    #[serde(default)]
+    pub upgrade_score: u32,
+}
+
+impl LevelSettings {
+    /// 这一关所有波次的敌机总数
+    pub fn aircraft_count(&self) -> usize {
+        self.waves.iter().map(|wave| wave.count).sum()
+    }
+
+    /// 已经生成count架敌机后，下一架敌机所在的波次及是否是这一波的第一架
+    pub fn wave_at(&self, count: usize) -> Option<(&WaveSettings, bool)> {
+        let mut start = 0;
+        for wave in &self.waves {
+            if count < start + wave.count {
+                return Some((wave, count == start));
+            }
+            start += wave.count;
+        }
+        None
+    }
+}
+
 #[derive(Resource)]
 struct GameSettings {
-    // 不同用户级别对应的字母
-    pub level_letters: Vec<Vec<char>>,
-    // 不同用户级别对应的战舰句子
-    pub level_sentences: Vec<Vec<String>>,
-    // 不同用户级别对应的飞行速度区间
-    pub level_speeds: Vec<(f32, f32)>,
-    // 不同级别用户战舰的发射时间间隔
-    pub warship_fire_interval: Vec<f32>,
-    // 战舰单个火炮发射间隔
-    pub warship_gun_interval: Vec<f32>,
-    // 升级的分数
-    pub upgrade_scores: Vec<u32>,
-    // 不同用户级别每一关的敌机数量
-    pub aircraft_count: Vec<usize>,
-    // 敌机出现的时间间隔
-    pub aircraft_intervals: Vec<(f32, f32)>,
-    // 炸弹出现的时间间隔
-    pub bomb_intervals: Vec<(f32, f32)>,
-    // 护盾出现的时间间隔
-    pub shield_intervals: Vec<(f32, f32)>,
+    // 所有关卡，玩家的最高级别就是关卡的数量
+    pub levels: Vec<LevelSettings>,
     // 护盾防护的时间
     pub shield_active_time: f32,
-    // 血包出现的时间间隔
-    pub health_pack_intervals: Vec<(f32, f32)>,
+    // 减速持续的时间
+    pub slow_motion_time: f32,
+    // 得分加倍持续的时间
+    pub double_score_time: f32,
+    // 自动瞄准持续的时间
+    pub auto_aim_time: f32,
     // 玩家发射的导弹速度
     pub missile_speed: f32,
     // 战舰发射的火焰速度
     pub flame_speed: f32,
+    // 自动难度调整的参数
+    pub difficulty: DifficultySettings,
+    // 得分及连击的规则
+    pub scoring: ScoringSettings,
+    // 指法课程
+    pub curriculum: CurriculumSettings,
 }
 
-impl Default for GameSettings {
+/// 指法课程中的一课
+#[derive(Clone)]
+struct LessonSettings {
+    pub name: String,
+    // 这一课新学的键
+    pub keys: String,
+}
+
+/// 指法课程，每一课在已经学过的键上增加几个新键
+#[derive(Clone)]
+struct CurriculumSettings {
+    pub lessons: Vec<LessonSettings>,
+    // 每一课的敌机数量
+    pub aircraft_count: usize,
+    // 敌机的飞行速度区间
+    pub speed: (f32, f32),
+    // 敌机出现的时间间隔
+    pub interval: (f32, f32),
+    // 新学的键重复的次数，使其出现得更频繁
+    pub new_key_weight: usize,
+    // 通过一课需要的准确率
+    pub target_accuracy: f32,
+    // 通过一课需要的平均反应时间（秒）
+    pub target_reaction: f32,
+}
+
+impl Default for CurriculumSettings {
     fn default() -> Self {
-        let letters: Vec<Vec<char>> = [
-            "ABCDEFGHIJKLMNOPQRSTUVWXYZ",
-            "1234567890",
-            "+-*/:=%,.;",
-            "?\"{}#!()",
-            "[]<>@_|'",
-        ]
-        .iter()
-        .map(|s| s.chars().collect())
-        .collect();
-
-        let mut level_letters: Vec<Vec<char>> = Vec::with_capacity(MAX_PLAYER_LEVELS as usize);
-        let mut current = Vec::new();
-        let mut rng = rand::rng();
-        for i in 0..MAX_PLAYER_LEVELS as usize {
-            if i < letters.len() {
-                current.extend(&letters[i]);
-                if i == 1 || i == 3 {
-                    current.extend(&letters[0]);
-                }
-                current.shuffle(&mut rng);
-            }
-            level_letters.push(current.clone());
+        let lessons = [
+            ("基准键", "FJ"), ("基准键", "DK"), ("基准键", "SL"), ("基准键", "A;"), ("基准键", "GH"),
+            ("上排键", "RU"), ("上排键", "EI"), ("上排键", "WO"), ("上排键", "QP"), ("上排键", "TY"),
+            ("下排键", "VM"), ("下排键", "C,"), ("下排键", "X."), ("下排键", "Z/"), ("下排键", "BN"),
+            ("Shift键", ":?"), ("Shift键", "<>"),
+            ("数字键", "1234"), ("数字键", "567"), ("数字键", "890"),
+            ("符号键", "-="), ("符号键", "[]"), ("符号键", "'\""), ("符号键", "!@#"), ("符号键", "()*+"),
+        ];
+        CurriculumSettings {
+            lessons: lessons.iter().map(|(name, keys)| LessonSettings {
+                name: name.to_string(),
+                keys: keys.to_string(),
+            }).collect(),
+            aircraft_count: 40,
+            speed: (40., 60.),
+            interval: (2., 3.5),
+            new_key_weight: 3,
+            target_accuracy: 0.9,
+            target_reaction: 3.,
         }
+    }
+}
 
-        let mut level_sentences = vec![
-            vec![
-                "well done".to_owned(),
-                "excuse me".to_owned(),
-                "my best friend".to_owned(),
-                "be quiet".to_owned(),
-                "listen carefully".to_owned(),
-                "Good morning".to_owned(),
-                "Be happy".to_owned(),
-                "Thank you".to_owned(),
-                "Hello bird".to_owned(),
-                "Come here".to_owned(),
-            ],
-            vec![
-                "Knowledge is power".to_owned(),
-                "The sun is bright today".to_owned(),
-                "My cat is under the chair".to_owned(),
-                "We run fast in the park".to_owned(),
-                "Kind words cost nothing".to_owned(),
-                "The stars shine in the sky".to_owned(),
-                "My dog waits at the gate".to_owned(),
-                "We play games after school".to_owned(),
-                "Her smile makes me happy".to_owned(),
-                "Hope is a waking dream".to_owned(),
-            ],
-            vec![
-                "The teacher reads a story to us".to_owned(),
-                "We play football after school today".to_owned(),
-                "He drinks milk every morning".to_owned(),
-                "The dog sleeps on the sofa".to_owned(),
-                "They are singing in the classroom".to_owned(),
-                "The gentle wind moves the green leaves".to_owned(),
-                "We walk together under the blue sky".to_owned(),
-                "My little brother laughs in the garden".to_owned(),
-                "The moon shines softly on the lake".to_owned(),
-                "I write a story about my dream".to_owned(),
-            ],
-            vec![
-                "The little prince lives on a small star".to_owned(),
-                "The little bird sings sweetly in the morning".to_owned(),
-                "A magic flower blooms only under the moon".to_owned(),
-                "The golden sun rises slowly over the ocean".to_owned(),
-                "My mother cooks delicious food for me".to_owned(),
-                "Where there is love, there is life".to_owned(),
-                "Smile, and the world smiles with you".to_owned(),
-                "Honesty is the best policy".to_owned(),
-                "A gentle word can make a heavy heart light".to_owned(),
-                "Stars can’t shine without darkness.".to_owned(),
-            ],
-            vec![
-                "The teacher tells us a funny story".to_owned(),
-                "I like to draw animals and flowers".to_owned(),
-                "The world is brighter when you choose to care".to_owned(),
-                "The shining stars brighten the dark night sky".to_owned(),
-                "A small candle lights the entire dark room".to_owned(),
-                "Light follows the darkest night".to_owned(),
-                "Every flower blooms in its own time".to_owned(),
-                "The morning sun paints the sky with golden light".to_owned(),
-                "Kind hearts are the gardens where love grows".to_owned(),
-                "Dreams are stars that guide us through the night".to_owned(),
-            ],
-        ];
-        let mut sentence_file = get_app_data_dir(GAME_APP_NAME);
-        sentence_file.push(WARSHIP_SENTENCES_FILE);
-        let _ = sync_sentences_with_file(&mut level_sentences, sentence_file.as_path());
+impl CurriculumSettings {
+    /// 玩家当前要学的课程，全部通过后重复最后一课
+    pub fn lesson_index(&self, player: &Player) -> usize {
+        (player.lesson as usize).min(self.lessons.len() - 1)
+    }
+
+    /// 生成一课对应的关卡：只出现已经学过的键，新学的键出现得更频繁
+    pub fn lesson_level(&self, index: usize) -> LevelSettings {
+        let mut letters: String = self.lessons[..index].iter().map(|l| l.keys.as_str()).collect();
+        letters.push_str(&self.lessons[index].keys.repeat(self.new_key_weight.max(1)));
+        LevelSettings {
+            letters,
+            sentences: Vec::new(),
+            speed: self.speed,
+            waves: vec![WaveSettings {
+                count: self.aircraft_count,
+                interval: self.interval,
+                pause: 0.,
+            }],
+            enemy_weights: vec![(EnemyKind::Normal, 1)],
+            equipment_intervals: HashMap::from([(FlyingUnitKind::HealthPack, (120., 180.))]),
+            warship_fire_interval: 4.,
+            warship_gun_interval: 0.4,
+            upgrade_score: 0,
+        }
+    }
+}
+
+impl GameSettings {
+    pub fn max_level(&self) -> u32 {
+        self.levels.len() as u32
+    }
+
+    /// 玩家级别对应的关卡，关卡文件修改后级别可能超出关卡数量，此时使用最后一关
+    pub fn level(&self, level: u32) -> &LevelSettings {
+        let index = (level.max(1) as usize).min(self.levels.len());
+        &self.levels[index - 1]
+    }
+
+    /// 每一关升到下一级需要的分数，用于计算升级进度
+    pub fn upgrade_scores(&self) -> Vec<u32> {
+        self.levels.iter().map(|l| l.upgrade_score).collect()
+    }
+}
+
+/// 自动难度调整的目标及范围
+#[derive(Clone)]
+struct DifficultySettings {
+    // 期望玩家保持的击落比例
+    pub target_success: f32,
+    // 期望的平均反应时间（秒）
+    pub target_reaction: f32,
+    // 反应时间对难度调整的影响权重
+    pub reaction_weight: f32,
+    // 统计最近多少架敌机的结果
+    pub sample_window: usize,
+    // 开始调整前至少需要的样本数量
+    pub min_samples: usize,
+    // 每秒最多调整的速度比例
+    pub adjust_rate: f32,
+    // 敌机速度加速比的范围
+    pub speed_factor_range: (f32, f32),
+    // 敌机出现间隔系数的范围
+    pub interval_factor_range: (f32, f32),
+}
+
+impl Default for DifficultySettings {
+    fn default() -> Self {
+        DifficultySettings {
+            target_success: 0.85,
+            target_reaction: 2.5,
+            reaction_weight: 0.3,
+            sample_window: 20,
+            min_samples: 5,
+            adjust_rate: 0.05,
+            speed_factor_range: (0.6, 2.5),
+            interval_factor_range: (0.4, 1.6),
+        }
+    }
+}
+
+/// 自定义的关卡文件，先查找数据目录，再查找游戏资源目录
+fn campaign_file_path() -> Option<PathBuf> {
+    let mut data_file = app_data_dir_path(GAME_APP_NAME);
+    data_file.push(CAMPAIGN_FILE);
+    let asset_file = PathBuf::from(resolve_assets_path()).join(CAMPAIGN_FILE);
+    [data_file, asset_file].into_iter().find(|p| p.exists())
+}
+
+/// 读取自定义的关卡文件，文件中有无法使用的关卡时返回None，游戏使用内置的关卡
+fn load_campaign_file(level_sentences: &[Vec<String>]) -> Option<Vec<LevelSettings>> {
+    let path = campaign_file_path()?;
+    let mut levels = fs::read_to_string(&path)
+        .and_then(|data| serde_json::from_str::<Vec<LevelSettings>>(&data).map_err(|err| err.into()))
+        .inspect_err(|err| error!("Failed to parse campaign file {}: {}", path.display(), err))
+        .ok()?;
+    if levels.is_empty() {
+        error!("Campaign file {} has no levels", path.display());
+        return None;
+    }
+    fill_level_sentences(&mut levels, level_sentences);
+    let problems = validate_levels(&levels);
+    for problem in &problems {
+        error!("Invalid campaign file {}: {}", path.display(), problem);
+    }
+    problems.is_empty().then_some(levels)
+}
+
+/// 关卡没有设置句子时使用句子资源文件中对应级别的句子
+fn fill_level_sentences(levels: &mut [LevelSettings], level_sentences: &[Vec<String>]) {
+    for (i, level) in levels.iter_mut().enumerate() {
+        if level.sentences.is_empty() {
+            level.sentences = level_sentences.get(i).or(level_sentences.last()).cloned().unwrap_or_default();
+        }
+    }
+}
+
+/// 不能直接用键盘输入的字符，战舰句子中出现这些字符时玩家无法打完句子
+fn untypable_chars(sentence: &str) -> String {
+    sentence.chars().filter(|c| !c.is_ascii_graphic() && *c != ' ').collect()
+}
+
+fn validate_level(index: usize, level: &LevelSettings, problems: &mut Vec<String>) {
+    let name = format!("第{}关", index + 1);
+    if level.letters.is_empty() {
+        problems.push(format!("{}：没有设置字符", name));
+    }
+    let letters = untypable_chars(&level.letters);
+    if !letters.is_empty() {
+        problems.push(format!("{}：字符中有无法输入的“{}”", name, letters));
+    }
+    if level.aircraft_count() == 0 {
+        problems.push(format!("{}：没有敌机", name));
+    }
+    if level.speed.0 > level.speed.1 {
+        problems.push(format!("{}：最小速度大于最大速度", name));
+    }
+    for (i, wave) in level.waves.iter().enumerate() {
+        if wave.interval.0 > wave.interval.1 {
+            problems.push(format!("{}：第{}波敌机的最小间隔大于最大间隔", name, i + 1));
+        }
+    }
+    if level.equipment_intervals.values().any(|(min, max)| min > max) {
+        problems.push(format!("{}：装备的最小间隔大于最大间隔", name));
+    }
+    if level.sentences.is_empty() {
+        problems.push(format!("{}：没有战舰句子", name));
+    }
+    for sentence in &level.sentences {
+        if sentence.trim().is_empty() {
+            problems.push(format!("{}：有空的战舰句子", name));
+            continue;
+        }
+        let chars = untypable_chars(sentence);
+        if !chars.is_empty() {
+            problems.push(format!("{}：句子“{}”中有无法输入的“{}”", name, sentence, chars));
+        }
+    }
+}
+
+/// 检查关卡设置，返回会让游戏无法进行的问题
+fn validate_levels(levels: &[LevelSettings]) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (index, level) in levels.iter().enumerate() {
+        validate_level(index, level, &mut problems);
+    }
+    problems
+}
+
+/// 内置的五个关卡
+fn default_levels() -> Vec<LevelSettings> {
+    let letters = [
+        "ABCDEFGHIJKLMNOPQRSTUVWXYZ",
+        "1234567890",
+        "+-*/:=%,.;",
+        "?\"{}#!()",
+        "[]<>@_|'",
+    ];
+    let mut level_letters: Vec<String> = Vec::with_capacity(letters.len());
+    let mut current = String::new();
+    for (i, chars) in letters.iter().enumerate() {
+        current.push_str(chars);
+        if i == 1 || i == 3 {
+            current.push_str(letters[0]);
+        }
+        level_letters.push(current.clone());
+    }
+
+    let speeds = [(50., 80.), (80., 110.), (120., 150.), (150., 180.), (180., 220.)];
+    let warship_fire_interval = [4., 4., 2.5, 1.5, 0.5];
+    let warship_gun_interval = [0.4, 0.4, 0.3, 0.2, 0.1];
+    let upgrade_scores = [2000, 10000, 28000, 50000, 0];
+    let aircraft_count = [150, 300, 400, 500, 600];
+    let aircraft_intervals = [(3., 5.), (1.5, 3.), (1., 1.5), (0.8, 1.), (0.3, 1.)];
+    let enemy_weights = [
+        vec![(EnemyKind::Normal, 10), (EnemyKind::Scout, 2)],
+        vec![(EnemyKind::Normal, 10), (EnemyKind::Scout, 2), (EnemyKind::Armored, 2), (EnemyKind::ZigZag, 1)],
+        vec![(EnemyKind::Normal, 8), (EnemyKind::Scout, 2), (EnemyKind::Armored, 3), (EnemyKind::ZigZag, 2),
+             (EnemyKind::Splitter, 1)],
+        vec![(EnemyKind::Normal, 6), (EnemyKind::Scout, 3), (EnemyKind::Armored, 3), (EnemyKind::ZigZag, 3),
+             (EnemyKind::Splitter, 2)],
+        vec![(EnemyKind::Normal, 5), (EnemyKind::Scout, 3), (EnemyKind::Armored, 4), (EnemyKind::ZigZag, 3),
+             (EnemyKind::Splitter, 3)],
+    ];
+    // 炸弹、护盾、血包及得分加倍出现的时间间隔
+    let common_intervals = [(150., 200.), (200., 250.), (250., 300.), (300., 400.), (400., 500.)];
+    let slow_motion_intervals = [(120., 180.), (150., 200.), (180., 240.), (200., 300.), (250., 350.)];
+    let auto_aim_intervals = [(180., 240.), (200., 300.), (250., 350.), (300., 400.), (400., 500.)];
+    let extra_life_intervals = [(300., 400.), (400., 500.), (500., 600.), (600., 700.), (700., 800.)];
+
+    (0..letters.len()).map(|i| LevelSettings {
+        letters: level_letters[i].clone(),
+        sentences: Vec::new(),
+        speed: speeds[i],
+        waves: vec![WaveSettings {
+            count: aircraft_count[i],
+            interval: aircraft_intervals[i],
+            pause: 0.,
+        }],
+        enemy_weights: enemy_weights[i].clone(),
+        equipment_intervals: HashMap::from([
+            (FlyingUnitKind::Bomb, common_intervals[i]),
+            (FlyingUnitKind::Shield, common_intervals[i]),
+            (FlyingUnitKind::HealthPack, common_intervals[i]),
+            (FlyingUnitKind::SlowMotion, slow_motion_intervals[i]),
+            (FlyingUnitKind::DoubleScore, common_intervals[i]),
+            (FlyingUnitKind::AutoAim, auto_aim_intervals[i]),
+            (FlyingUnitKind::ExtraLife, extra_life_intervals[i]),
+        ]),
+        warship_fire_interval: warship_fire_interval[i],
+        warship_gun_interval: warship_gun_interval[i],
+        upgrade_score: upgrade_scores[i],
+    }).collect()
+}
+
+/// 内置的战舰句子，每个级别一组
+fn builtin_warship_sentences() -> Vec<Vec<String>> {
+    vec![
+        vec![
+            "well done".to_owned(),
+            "excuse me".to_owned(),
+            "my best friend".to_owned(),
+            "be quiet".to_owned(),
+            "listen carefully".to_owned(),
+            "Good morning".to_owned(),
+            "Be happy".to_owned(),
+            "Thank you".to_owned(),
+            "Hello bird".to_owned(),
+            "Come here".to_owned(),
+        ],
+        vec![
+            "Knowledge is power".to_owned(),
+            "The sun is bright today".to_owned(),
+            "My cat is under the chair".to_owned(),
+            "We run fast in the park".to_owned(),
+            "Kind words cost nothing".to_owned(),
+            "The stars shine in the sky".to_owned(),
+            "My dog waits at the gate".to_owned(),
+            "We play games after school".to_owned(),
+            "Her smile makes me happy".to_owned(),
+            "Hope is a waking dream".to_owned(),
+        ],
+        vec![
+            "The teacher reads a story to us".to_owned(),
+            "We play football after school today".to_owned(),
+            "He drinks milk every morning".to_owned(),
+            "The dog sleeps on the sofa".to_owned(),
+            "They are singing in the classroom".to_owned(),
+            "The gentle wind moves the green leaves".to_owned(),
+            "We walk together under the blue sky".to_owned(),
+            "My little brother laughs in the garden".to_owned(),
+            "The moon shines softly on the lake".to_owned(),
+            "I write a story about my dream".to_owned(),
+        ],
+        vec![
+            "The little prince lives on a small star".to_owned(),
+            "The little bird sings sweetly in the morning".to_owned(),
+            "A magic flower blooms only under the moon".to_owned(),
+            "The golden sun rises slowly over the ocean".to_owned(),
+            "My mother cooks delicious food for me".to_owned(),
+            "Where there is love, there is life".to_owned(),
+            "Smile, and the world smiles with you".to_owned(),
+            "Honesty is the best policy".to_owned(),
+            "A gentle word can make a heavy heart light".to_owned(),
+            "Stars can't shine without darkness.".to_owned(),
+        ],
+        vec![
+            "The teacher tells us a funny story".to_owned(),
+            "I like to draw animals and flowers".to_owned(),
+            "The world is brighter when you choose to care".to_owned(),
+            "The shining stars brighten the dark night sky".to_owned(),
+            "A small candle lights the entire dark room".to_owned(),
+            "Light follows the darkest night".to_owned(),
+            "Every flower blooms in its own time".to_owned(),
+            "The morning sun paints the sky with golden light".to_owned(),
+            "Kind hearts are the gardens where love grows".to_owned(),
+            "Dreams are stars that guide us through the night".to_owned(),
+        ],
+    ]
+}
+
+/// 读取句子资源文件，文件不存在时用内置的句子创建
+fn warship_sentences() -> Vec<Vec<String>> {
+    let mut level_sentences = builtin_warship_sentences();
+    let mut sentence_file = get_app_data_dir(GAME_APP_NAME);
+    sentence_file.push(WARSHIP_SENTENCES_FILE);
+    let _ = sync_sentences_with_file(&mut level_sentences, sentence_file.as_path());
+    level_sentences
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        let level_sentences = warship_sentences();
+        let levels = load_campaign_file(&level_sentences).unwrap_or_else(|| {
+            let mut levels = default_levels();
+            fill_level_sentences(&mut levels, &level_sentences);
+            levels
+        });
 
         GameSettings {
-            level_letters,
-            level_sentences,
-            level_speeds: vec![
-                (50., 80.),
-                (80., 110.),
-                (120., 150.),
-                (150., 180.),
-                (180., 220.),
-            ],
-            warship_fire_interval: vec![4., 4., 2.5, 1.5, 0.5],
-            warship_gun_interval: vec![0.4, 0.4, 0.3, 0.2, 0.1],
-            upgrade_scores: vec![2000, 10000, 28000, 50000],
-            aircraft_count: vec![150, 300, 400, 500, 600],
-            aircraft_intervals: vec![(3., 5.), (1.5, 3.), (1., 1.5), (0.8, 1.), (0.3, 1.)],
-            bomb_intervals: vec![
-                (150., 200.),
-                (200., 250.),
-                (250., 300.),
-                (300., 400.),
-                (400., 500.),
-            ],
-            shield_intervals: vec![
-                (150., 200.),
-                (200., 250.),
-                (250., 300.),
-                (300., 400.),
-                (400., 500.),
-            ],
-            health_pack_intervals: vec![
-                (150., 200.),
-                (200., 250.),
-                (250., 300.),
-                (300., 400.),
-                (400., 500.),
-            ],
+            levels,
             shield_active_time: 30.,
+            slow_motion_time: 10.,
+            double_score_time: 20.,
+            auto_aim_time: 8.,
             missile_speed: 1000.,
             flame_speed: 500.,
+            difficulty: DifficultySettings::default(),
+            scoring: ScoringSettings::default(),
+            curriculum: CurriculumSettings::default(),
         }
     }
 }
@@ -353,6 +769,7 @@ fn setup_camera(mut commands: Commands) {
 
 /// 初始化全局的字体、用户以及图片资源
 fn init_resources(
+    mut commands: Commands,
     mut players: ResMut<Players>,
     mut fonts: ResMut<GameFonts>,
     mut next: ResMut<NextState<GameState>>,
@@ -369,28 +786,32 @@ fn init_resources(
     widgets::UI_BUTTON_FONT.set(fonts.ui_font.clone()).ok();
 
     texture.texture = asset_server.load("images/explosion.png");
-    texture.layout = texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
-        UVec2::new(150, 129),
-        3,
-        3,
-        None,
-        None,
-    ));
+    let grid = theme::ExplosionGrid::default();
+    texture.layout = texture_atlas_layouts.add(grid.layout());
+    texture.frames = grid.frames();
 
-    let mut data_file = get_app_data_dir(GAME_APP_NAME);
-    data_file.push(PLAYERS_DATA_FILE);
-    if Path::new(&data_file).exists() {
-        fs::read_to_string(&data_file)
-            .and_then(|data| serde_json::from_str::<Vec<Player>>(&data).map_err(|err| err.into()))
-            .map(|data| players.0.extend(data))
-            .unwrap_or_else(|err| error!("Failed to parse player data: {}", err));
-    } else {
-        info!("No player data file found in current directory");
+    match load_game_users() {
+        Ok(data) => players.0.extend(data),
+        Err(err) => error!("Failed to parse player data: {}", err),
     }
+    commands.insert_resource(playtime::PlayTime::load());
 
     next.set(GameState::Startup);
 }
 
+/// 读取数据目录中保存的所有玩家，还没有玩家数据文件时返回空列表
+fn load_game_users() -> Result<Vec<Player>, String> {
+    let mut data_file = get_app_data_dir(GAME_APP_NAME);
+    data_file.push(PLAYERS_DATA_FILE);
+    if !data_file.exists() {
+        info!("No player data file found in {}", data_file.display());
+        return Ok(Vec::new());
+    }
+    fs::read_to_string(&data_file)
+        .map_err(|err| err.to_string())
+        .and_then(|data| serde_json::from_str::<Vec<Player>>(&data).map_err(|err| err.to_string()))
+}
+
 fn save_game_users(players: &Players) {
     if let Ok(json) = serde_json::to_string_pretty(&players.0) {
         let mut data_file = get_app_data_dir(GAME_APP_NAME);
@@ -0,0 +1,123 @@
+//! 与玩家自己的最好成绩赛跑。每局游戏按秒记录累计的进度，
+//! 每个关卡保留最好的一局，下一局游戏时与它在同一时刻的进度比较
+
+use serde::{Deserialize, Serialize};
+
+/// 某一秒结束时的累计进度
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct GhostSample {
+    // 击落和漏掉的敌机数量
+    pub hits: u32,
+    pub misses: u32,
+    // 击中的战舰句子字符数量
+    pub boss_letters: u32,
+}
+
+impl GhostSample {
+    /// 用于比较的进度，击落的敌机和击中的战舰字符都算
+    pub fn progress(&self) -> u32 {
+        self.hits + self.boss_letters
+    }
+}
+
+/// 一局游戏的进度曲线，第i个记录是第i+1秒结束时的进度
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct GhostRun {
+    pub samples: Vec<GhostSample>,
+}
+
+/// 一局游戏结束时与最好成绩的差距，正数表示击中更多或者用时更长
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GhostDelta {
+    pub progress: i32,
+    pub secs: i32,
+}
+
+impl GhostRun {
+    /// 记录到第secs秒为止的进度，中间缺少的秒数使用同样的进度
+    pub fn record(&mut self, secs: u32, sample: GhostSample) {
+        while (self.samples.len() as u32) < secs {
+            self.samples.push(sample);
+        }
+    }
+
+    /// 这一局的秒数
+    pub fn duration(&self) -> u32 {
+        self.samples.len() as u32
+    }
+
+    /// 第secs秒结束时的进度，超过这一局的时长时是最后的进度
+    pub fn at(&self, secs: u32) -> GhostSample {
+        match secs {
+            0 => GhostSample::default(),
+            n => self.samples.get(n as usize - 1).or(self.samples.last()).copied().unwrap_or_default(),
+        }
+    }
+
+    /// 最后的进度
+    pub fn last(&self) -> GhostSample {
+        self.samples.last().copied().unwrap_or_default()
+    }
+
+    /// 在第secs秒有sample的进度时领先最好成绩的数量，负数表示落后
+    pub fn lead(&self, secs: u32, sample: &GhostSample) -> i32 {
+        sample.progress() as i32 - self.at(secs).progress() as i32
+    }
+
+    /// 这一局是否比best更好：击中的更多，其次是用时更短，再次是漏掉的更少
+    pub fn beats(&self, best: &GhostRun) -> bool {
+        let (mine, theirs) = (self.last(), best.last());
+        (std::cmp::Reverse(mine.progress()), self.duration(), mine.misses)
+            < (std::cmp::Reverse(theirs.progress()), best.duration(), theirs.misses)
+    }
+
+    /// 这一局结束时与best的差距
+    pub fn delta(&self, best: &GhostRun) -> GhostDelta {
+        GhostDelta {
+            progress: self.last().progress() as i32 - best.last().progress() as i32,
+            secs: self.duration() as i32 - best.duration() as i32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(hits: u32, misses: u32) -> GhostSample {
+        GhostSample { hits, misses, boss_letters: 0 }
+    }
+
+    fn run(samples: &[GhostSample]) -> GhostRun {
+        GhostRun { samples: samples.to_vec() }
+    }
+
+    #[test]
+    fn record_fills_missing_seconds() {
+        let mut ghost = GhostRun::default();
+        ghost.record(1, sample(1, 0));
+        ghost.record(3, sample(2, 0));
+        assert_eq!(ghost.samples, vec![sample(1, 0), sample(2, 0), sample(2, 0)]);
+        ghost.record(2, sample(5, 0));
+        assert_eq!(ghost.duration(), 3);
+    }
+
+    #[test]
+    fn at_clamps_to_run_length() {
+        let ghost = run(&[sample(1, 0), sample(2, 1)]);
+        assert_eq!(ghost.at(0), GhostSample::default());
+        assert_eq!(ghost.at(1), sample(1, 0));
+        assert_eq!(ghost.at(10), sample(2, 1));
+        assert_eq!(GhostRun::default().at(3), GhostSample::default());
+    }
+
+    #[test]
+    fn beats_prefers_progress_then_time_then_misses() {
+        let best = run(&[sample(1, 1), sample(2, 1)]);
+        assert!(run(&[sample(3, 1)]).beats(&best));
+        assert!(!run(&[sample(1, 0), sample(1, 0)]).beats(&best));
+        assert!(run(&[sample(2, 5)]).beats(&best));
+        assert!(run(&[sample(0, 0), sample(2, 0)]).beats(&best));
+        assert!(!best.beats(&best));
+    }
+}
@@ -0,0 +1,164 @@
+use rand::Rng;
+
+/// 敌机字符的候选池。屏幕上出现的字符从候选中取出，敌机消失后再放回，
+/// 只要候选字符足够，同时出现在屏幕上的字符就不会重复
+#[derive(Clone, Debug, Default)]
+pub struct LetterPool {
+    candidate_letters: Vec<char>,
+    // 已经出现过的字符，候选字符用完时从这里随机选择
+    choosed_letters: Vec<char>,
+    // 正在屏幕上的字符，同一个字符可能出现多次
+    on_screen: Vec<char>,
+}
+
+/// 大小写字母是同一个键
+fn same_key(a: char, b: char) -> bool {
+    a.eq_ignore_ascii_case(&b)
+}
+
+impl LetterPool {
+    /// 换成新关卡的字符，重复的字符出现的概率更高
+    pub fn reset(&mut self, letters: &str) {
+        self.candidate_letters = letters.chars().collect();
+    }
+
+    /// 清空所有字符，开始新的一局
+    pub fn clear(&mut self) {
+        self.candidate_letters.clear();
+        self.choosed_letters.clear();
+        self.on_screen.clear();
+    }
+
+    fn is_on_screen(&self, letter: char) -> bool {
+        self.on_screen.iter().any(|c| same_key(*c, letter))
+    }
+
+    /// 从letters中随机选择一个，尽量选择屏幕上没有的字符
+    fn choose(&self, letters: &[char], rng: &mut impl Rng) -> Option<usize> {
+        let unique: Vec<usize> = (0..letters.len()).filter(|i| !self.is_on_screen(letters[*i])).collect();
+        if !unique.is_empty() {
+            Some(unique[rng.random_range(0..unique.len())])
+        } else if !letters.is_empty() {
+            Some(rng.random_range(0..letters.len()))
+        } else {
+            None
+        }
+    }
+
+    /// 随机取出一个字符。优先使用屏幕上没有的候选字符，其次是屏幕上没有的已经出现过的字符，
+    /// 所有字符都已经在屏幕上时才会出现重复的字符
+    pub fn random_letter(&mut self, rng: &mut impl Rng) -> char {
+        let fresh = self.candidate_letters.iter().any(|c| !self.is_on_screen(*c));
+        let reused = self.choosed_letters.iter().any(|c| !self.is_on_screen(*c));
+        let letter = if fresh || (!reused && !self.candidate_letters.is_empty()) {
+            let index = self.choose(&self.candidate_letters, rng).unwrap();
+            let letter = self.candidate_letters.swap_remove(index);
+            if !self.choosed_letters.contains(&letter) {
+                self.choosed_letters.push(letter);
+            }
+            letter
+        } else {
+            let index = self.choose(&self.choosed_letters, rng).expect("关卡没有任何字符");
+            self.choosed_letters[index]
+        };
+        self.on_screen.push(letter);
+        letter
+    }
+
+    /// 从候选字符中取出指定的字符，用于恢复保存的飞行单元
+    pub fn take_letter(&mut self, letter: char) {
+        if let Some(index) = self.candidate_letters.iter().position(|c| *c == letter) {
+            self.candidate_letters.swap_remove(index);
+        }
+        if !self.choosed_letters.contains(&letter) {
+            self.choosed_letters.push(letter);
+        }
+        self.on_screen.push(letter);
+    }
+
+    /// 飞行单元消失后把它的字符放回候选池
+    pub fn put_back(&mut self, letters: impl IntoIterator<Item = char>) {
+        for letter in letters {
+            if let Some(index) = self.on_screen.iter().position(|c| *c == letter) {
+                self.on_screen.swap_remove(index);
+            }
+            self.candidate_letters.push(letter);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use super::*;
+
+    #[test]
+    fn random_letter_comes_from_level_letters() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut pool = LetterPool::default();
+        pool.reset("ABC");
+        for _ in 0..20 {
+            let letter = pool.random_letter(&mut rng);
+            assert!("ABC".contains(letter));
+            pool.put_back([letter]);
+        }
+    }
+
+    #[test]
+    fn reset_switches_to_new_letters() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut pool = LetterPool::default();
+        pool.reset("AB");
+        pool.reset("Z");
+        assert_eq!(pool.random_letter(&mut rng), 'Z');
+    }
+
+    #[test]
+    fn taken_letter_is_not_drawn_again() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut pool = LetterPool::default();
+        pool.reset("AB");
+        pool.take_letter('A');
+        assert_eq!(pool.random_letter(&mut rng), 'B');
+    }
+
+    #[test]
+    fn on_screen_letters_are_unique() {
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut pool = LetterPool::default();
+            pool.reset("ABCDEAB");
+            let mut letters: Vec<char> = (0..5).map(|_| pool.random_letter(&mut rng)).collect();
+            letters.sort();
+            assert_eq!(letters, vec!['A', 'B', 'C', 'D', 'E']);
+        }
+    }
+
+    #[test]
+    fn exhausted_pool_repeats_letters() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut pool = LetterPool::default();
+        pool.reset("AB");
+        pool.random_letter(&mut rng);
+        pool.random_letter(&mut rng);
+        for _ in 0..10 {
+            assert!("AB".contains(pool.random_letter(&mut rng)));
+        }
+    }
+
+    #[test]
+    fn put_back_letter_is_drawn_first() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut pool = LetterPool::default();
+        pool.reset("AB");
+        let first = pool.random_letter(&mut rng);
+        let second = pool.random_letter(&mut rng);
+        assert_ne!(first, second);
+        pool.put_back([first]);
+        for _ in 0..5 {
+            assert_eq!(pool.random_letter(&mut rng), first);
+            pool.put_back([first]);
+        }
+    }
+}
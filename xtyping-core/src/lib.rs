@@ -0,0 +1,10 @@
+//! 超级打字的核心游戏规则：敌机字符的选择、航道的分配、级别的升级、得分和连击以及与最好成绩的比较。
+//!
+//! 这里的类型和函数不依赖任何游戏引擎，游戏中的Bevy系统只负责把它们和实体、界面连接起来，
+//! 其它前端（例如终端中的打字练习）也可以使用同样的规则。
+
+pub mod ghost;
+pub mod letters;
+pub mod progress;
+pub mod routes;
+pub mod scoring;
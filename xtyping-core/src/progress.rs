@@ -0,0 +1,70 @@
+//! 级别的升级规则。upgrade_scores依次是每一关升到下一级需要的分数，
+//! 关卡的数量就是最高级别，最后一关的分数不会用到
+
+/// 最高级别
+pub fn max_level(upgrade_scores: &[u32]) -> u32 {
+    upgrade_scores.len() as u32
+}
+
+/// 级别对应关卡的升级分数，超出关卡数量的级别使用最后一关
+fn level_upgrade_score(upgrade_scores: &[u32], level: u32) -> u32 {
+    let index = (level.max(1) as usize).min(upgrade_scores.len());
+    upgrade_scores.get(index.wrapping_sub(1)).copied().unwrap_or_default()
+}
+
+/// 从第一级升到level的下一级需要的累计分数
+pub fn upgrade_base_score(upgrade_scores: &[u32], level: u32) -> u32 {
+    upgrade_scores.iter().take(level as usize).sum()
+}
+
+/// 玩家在level级有score分时应该升到的新级别，还不能升级或者已经是最高级别时返回None
+pub fn next_level(upgrade_scores: &[u32], level: u32, score: u32) -> Option<u32> {
+    (level < max_level(upgrade_scores) && score >= upgrade_base_score(upgrade_scores, level)).then_some(level + 1)
+}
+
+/// 当前级别的升级进度，0到100之间，最高级别总是100
+pub fn upgrade_percent(upgrade_scores: &[u32], level: u32, score: u32) -> f32 {
+    if level >= max_level(upgrade_scores) {
+        return 100.;
+    }
+    let mut scores = score;
+    for i in 0..level {
+        let upgrade_score = level_upgrade_score(upgrade_scores, i + 1);
+        if scores >= upgrade_score {
+            scores -= upgrade_score;
+        } else {
+            break;
+        }
+    }
+    100. * scores as f32 / level_upgrade_score(upgrade_scores, level).max(1) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCORES: [u32; 3] = [10, 20, 30];
+
+    #[test]
+    fn base_score_is_cumulative() {
+        assert_eq!(max_level(&SCORES), 3);
+        assert_eq!(upgrade_base_score(&SCORES, 1), 10);
+        assert_eq!(upgrade_base_score(&SCORES, 2), 30);
+    }
+
+    #[test]
+    fn next_level_needs_enough_score() {
+        assert_eq!(next_level(&SCORES, 1, 9), None);
+        assert_eq!(next_level(&SCORES, 1, 10), Some(2));
+        assert_eq!(next_level(&SCORES, 2, 30), Some(3));
+        assert_eq!(next_level(&SCORES, 3, 1000), None);
+        assert_eq!(next_level(&[], 1, 1000), None);
+    }
+
+    #[test]
+    fn percent_counts_from_current_level() {
+        assert_eq!(upgrade_percent(&SCORES, 1, 5), 50.);
+        assert_eq!(upgrade_percent(&SCORES, 2, 20), 50.);
+        assert_eq!(upgrade_percent(&SCORES, 3, 0), 100.);
+    }
+}
@@ -0,0 +1,290 @@
+use rand::Rng;
+
+/// 每条航道的高度
+pub const ROUTE_HEIGHT: f32 = 40.;
+/// 航道数量的上限
+pub const MAX_ROUTE_COUNT: usize = 64;
+
+/// 指定高度的区域中能够容纳的航道数量，窗口再小也至少保留一条航道
+pub fn route_count(height: f32) -> usize {
+    ((height.max(0.) / ROUTE_HEIGHT) as usize).clamp(1, MAX_ROUTE_COUNT)
+}
+
+/// 第id条航道中心的纵坐标，top是第一条航道上边缘的纵坐标，纵坐标向上为正
+pub fn route_position(id: i32, top: f32) -> f32 {
+    top - id as f32 * ROUTE_HEIGHT - ROUTE_HEIGHT / 2.
+}
+
+/// 敌机飞行的一条航道，T是前端用来标识飞行单元的类型
+#[derive(Clone, Debug)]
+pub struct Route<T> {
+    pub id: i32,
+    // 航道上的飞行单元
+    pub units: Vec<T>,
+}
+
+impl<T> Route<T> {
+    /// 航道中心的纵坐标
+    pub fn position(&self, top: f32) -> f32 {
+        route_position(self.id, top)
+    }
+}
+
+/// 全部航道，分为没有飞行单元和已经有飞行单元的两组
+#[derive(Clone, Debug)]
+pub struct RouteTable<T> {
+    empty_routes: Vec<Route<T>>,
+    used_routes: Vec<Route<T>>,
+}
+
+impl<T> Default for RouteTable<T> {
+    fn default() -> Self {
+        Self { empty_routes: Vec::new(), used_routes: Vec::new() }
+    }
+}
+
+impl<T: PartialEq> RouteTable<T> {
+    /// 重新创建count条空航道
+    pub fn reset(&mut self, count: usize) {
+        self.used_routes.clear();
+        self.empty_routes = (0..count as i32).map(|id| Route { id, units: Vec::new() }).collect();
+    }
+
+    /// 航道数量改为count，已有的航道保持不变。减少航道时，被删除航道上的飞行单元按照
+    /// allocate的规则移到其它航道，返回这些飞行单元及其新的航道编号。gap返回两个飞行单元
+    /// 之间的水平距离，所有航道都太拥挤时新编号为None，这个单元应该移除后稍后再出现
+    pub fn resize(&mut self, count: usize, rng: &mut impl Rng, min_spacing: f32,
+                  gap: impl Fn(&T, &T) -> f32) -> Vec<(T, Option<i32>)> where T: Clone {
+        let count = count.max(1);
+        for id in self.len()..count {
+            self.empty_routes.push(Route { id: id as i32, units: Vec::new() });
+        }
+        self.empty_routes.retain(|r| (r.id as usize) < count);
+        let (removed, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.used_routes)
+            .into_iter()
+            .partition(|r| r.id as usize >= count);
+        self.used_routes = kept;
+        let mut moved = Vec::new();
+        for unit in removed.into_iter().flat_map(|r| r.units) {
+            let id = self.allocate(rng, min_spacing, |other| gap(other, &unit)).map(|route| {
+                route.units.push(unit.clone());
+                route.id
+            });
+            moved.push((unit, id));
+        }
+        moved
+    }
+
+    /// 航道的总数
+    pub fn len(&self) -> usize {
+        self.empty_routes.len() + self.used_routes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 随机选择一条航道，优先选择空航道
+    fn pick(&mut self, rng: &mut impl Rng) -> &mut Route<T> {
+        if self.empty_routes.is_empty() {
+            let index = rng.random_range(0..self.used_routes.len());
+            &mut self.used_routes[index]
+        } else {
+            let index = rng.random_range(0..self.empty_routes.len());
+            let route = self.empty_routes.swap_remove(index);
+            self.used_routes.push(route);
+            self.used_routes.last_mut().unwrap()
+        }
+    }
+
+    /// 航道上的飞行单元与新单元的水平距离都不小于min_spacing时，新单元才能放到这条航道上。
+    /// gap返回已有的飞行单元与新单元之间的水平距离
+    fn is_route_clear(route: &Route<T>, min_spacing: f32, gap: &impl Fn(&T) -> f32) -> bool {
+        route.units.iter().all(|unit| gap(unit) >= min_spacing)
+    }
+
+    /// 指定编号的航道能否放入新的飞行单元
+    pub fn is_clear(&self, id: i32, min_spacing: f32, gap: impl Fn(&T) -> f32) -> bool {
+        self.empty_routes.iter().any(|r| r.id == id)
+            || self.used_routes.iter().any(|r| r.id == id && Self::is_route_clear(r, min_spacing, &gap))
+    }
+
+    /// 为新的飞行单元随机分配一条航道，优先选择空航道，其次是与已有单元保持足够距离的航道，
+    /// 所有航道都太拥挤时返回None，新单元应该稍后再出现
+    pub fn allocate(&mut self, rng: &mut impl Rng, min_spacing: f32, gap: impl Fn(&T) -> f32) -> Option<&mut Route<T>> {
+        if !self.empty_routes.is_empty() {
+            return Some(self.pick(rng));
+        }
+        let clear: Vec<usize> = (0..self.used_routes.len())
+            .filter(|i| Self::is_route_clear(&self.used_routes[*i], min_spacing, &gap))
+            .collect();
+        let index = *clear.get(rng.random_range(0..clear.len().max(1)))?;
+        Some(&mut self.used_routes[index])
+    }
+
+    /// 占用指定编号的航道，用于恢复保存的飞行单元或者切换到相邻航道
+    pub fn occupy(&mut self, id: i32) -> Option<&mut Route<T>> {
+        if let Some(index) = self.empty_routes.iter().position(|r| r.id == id) {
+            let route = self.empty_routes.swap_remove(index);
+            self.used_routes.push(route);
+        }
+        self.used_routes.iter_mut().find(|r| r.id == id)
+    }
+
+    /// 飞行单元离开航道，航道上没有其它单元时转移到空航道
+    pub fn release(&mut self, id: i32, unit: &T) {
+        if let Some(pos) = self.used_routes.iter().position(|r| r.id == id) {
+            let route = &mut self.used_routes[pos];
+            route.units.retain(|u| u != unit);
+            if route.units.is_empty() {
+                let route = self.used_routes.swap_remove(pos);
+                self.empty_routes.push(route);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use super::*;
+
+    fn routes(count: usize) -> RouteTable<u32> {
+        let mut table = RouteTable::default();
+        table.reset(count);
+        table
+    }
+
+    #[test]
+    fn route_count_is_clamped() {
+        assert_eq!(route_count(0.), 1);
+        assert_eq!(route_count(ROUTE_HEIGHT * 10.), 10);
+        assert_eq!(route_count(ROUTE_HEIGHT * 1000.), MAX_ROUTE_COUNT);
+    }
+
+    #[test]
+    fn occupy_and_release_route() {
+        let mut table = routes(3);
+        table.occupy(1).unwrap().units.push(7);
+        assert!(!table.is_clear(1, 10., |_| 0.));
+        assert!(table.is_clear(0, 10., |_| 0.));
+        table.release(1, &7);
+        assert!(table.is_clear(1, 10., |_| 0.));
+        assert_eq!(table.len(), 3);
+    }
+
+    #[test]
+    fn occupy_unknown_route() {
+        assert!(routes(3).occupy(5).is_none());
+    }
+
+    #[test]
+    fn release_keeps_route_with_other_units() {
+        let mut table = routes(2);
+        table.occupy(0).unwrap().units.extend([7, 8]);
+        table.release(0, &7);
+        assert!(!table.is_clear(0, 10., |_| 0.));
+        table.release(0, &8);
+        assert!(table.is_clear(0, 10., |_| 0.));
+    }
+
+    // 测试中飞行单元的值就是它与新单元的水平距离
+    fn gap(unit: &u32) -> f32 {
+        *unit as f32
+    }
+
+    #[test]
+    fn allocate_prefers_empty_route() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut table = routes(2);
+        table.occupy(0).unwrap().units.push(0);
+        assert_eq!(table.allocate(&mut rng, 100., gap).unwrap().id, 1);
+    }
+
+    #[test]
+    fn allocate_keeps_min_spacing() {
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut table = routes(3);
+            table.occupy(0).unwrap().units.push(5);
+            table.occupy(1).unwrap().units.push(50);
+            table.occupy(2).unwrap().units.extend([60, 10]);
+            assert_eq!(table.allocate(&mut rng, 20., gap).unwrap().id, 1);
+        }
+    }
+
+    #[test]
+    fn allocate_fails_when_all_routes_are_crowded() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut table = routes(2);
+        table.occupy(0).unwrap().units.push(5);
+        table.occupy(1).unwrap().units.push(50);
+        assert!(table.allocate(&mut rng, 100., gap).is_none());
+    }
+
+    // 测试中两个飞行单元之间的水平距离就是它们的值之差
+    fn distance(a: &u32, b: &u32) -> f32 {
+        (*a as f32 - *b as f32).abs()
+    }
+
+    #[test]
+    fn resize_moves_units_off_removed_routes() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut table = routes(4);
+        table.occupy(0).unwrap().units.push(1);
+        table.occupy(3).unwrap().units.push(9);
+        let moved = table.resize(2, &mut rng, 0., distance);
+        assert_eq!(table.len(), 2);
+        assert!(table.occupy(3).is_none());
+        assert_eq!(moved.len(), 1);
+        let (unit, id) = moved[0];
+        assert_eq!(unit, 9);
+        let id = id.unwrap();
+        assert!(id < 2);
+        assert!(table.occupy(id).unwrap().units.contains(&9));
+        assert!(table.occupy(0).unwrap().units.contains(&1));
+    }
+
+    #[test]
+    fn resize_keeps_min_spacing() {
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut table = routes(4);
+            table.occupy(0).unwrap().units.push(100);
+            table.occupy(1).unwrap().units.push(300);
+            table.occupy(2).unwrap().units.push(110);
+            table.occupy(3).unwrap().units.push(290);
+            let moved = table.resize(2, &mut rng, 50., distance);
+            // 110离航道0上的100太近，290离航道1上的300太近，只能互相交换航道
+            assert_eq!(moved, vec![(110, Some(1)), (290, Some(0))]);
+            for id in 0..2 {
+                let units = &table.occupy(id).unwrap().units;
+                for (i, a) in units.iter().enumerate() {
+                    assert!(units[i + 1..].iter().all(|b| distance(a, b) >= 50.));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn resize_defers_units_when_routes_are_crowded() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut table = routes(3);
+        table.occupy(0).unwrap().units.push(100);
+        table.occupy(1).unwrap().units.push(100);
+        table.occupy(2).unwrap().units.push(110);
+        assert_eq!(table.resize(2, &mut rng, 50., distance), vec![(110, None)]);
+        assert_eq!(table.occupy(0).unwrap().units, vec![100]);
+        assert_eq!(table.occupy(1).unwrap().units, vec![100]);
+    }
+
+    #[test]
+    fn resize_keeps_at_least_one_route() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut table = routes(3);
+        table.occupy(2).unwrap().units.push(7);
+        assert_eq!(table.resize(0, &mut rng, 10., distance), vec![(7, Some(0))]);
+        assert_eq!(table.len(), 1);
+    }
+}
@@ -0,0 +1,105 @@
+/// 得分规则，连续击中会提升得分倍数，漏掉敌机或者按错键中断连击
+#[derive(Clone, Debug)]
+pub struct ScoringSettings {
+    // 击落一架敌机（或击中装甲的一个字符）的基础分
+    pub aircraft_score: u32,
+    // 击中战舰句子中一个字符的基础分，默认不得分，只计入连击
+    pub warship_letter_score: u32,
+    // 击毁战舰的基础分
+    pub warship_score: u32,
+    // 每连续击中多少次倍数加一
+    pub streak_step: u32,
+    // 连击倍数的上限
+    pub max_multiplier: u32,
+}
+
+impl Default for ScoringSettings {
+    fn default() -> Self {
+        ScoringSettings {
+            aircraft_score: 1,
+            warship_letter_score: 0,
+            warship_score: 50,
+            streak_step: 10,
+            max_multiplier: 4,
+        }
+    }
+}
+
+/// 按错键时从score中扣除的分数，wrong_key_penalty是设置的扣分，分数不会扣成负数
+pub fn penalty(wrong_key_penalty: u32, score: u32) -> u32 {
+    wrong_key_penalty.min(score)
+}
+
+/// 玩家当前的连击次数
+#[derive(Clone, Debug, Default)]
+pub struct Streak {
+    pub hits: u32,
+    // 本局最高的连击次数
+    pub best: u32,
+}
+
+impl Streak {
+    /// 当前连击对应的得分倍数
+    pub fn multiplier(&self, scoring: &ScoringSettings) -> u32 {
+        (1 + self.hits / scoring.streak_step.max(1)).min(scoring.max_multiplier.max(1))
+    }
+
+    /// 击中一次，连击次数加一，返回得到的分数及总的倍数，bonus是装备效果等额外的倍数
+    pub fn hit(&mut self, scoring: &ScoringSettings, base: u32, bonus: u32) -> (u32, u32) {
+        self.hits += 1;
+        self.best = self.best.max(self.hits);
+        let multiplier = self.multiplier(scoring) * bonus;
+        (base * multiplier, multiplier)
+    }
+
+    /// 连击中断
+    pub fn reset(&mut self) {
+        self.hits = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multiplier_grows_with_streak_and_is_capped() {
+        let scoring = ScoringSettings::default();
+        let mut streak = Streak::default();
+        assert_eq!(streak.hit(&scoring, 1, 1), (1, 1));
+        for _ in 0..8 {
+            streak.hit(&scoring, 1, 1);
+        }
+        assert_eq!(streak.hit(&scoring, 1, 1), (2, 2));
+        assert_eq!(streak.hit(&scoring, 3, 2), (12, 4));
+        for _ in 0..100 {
+            streak.hit(&scoring, 1, 1);
+        }
+        assert_eq!(streak.multiplier(&scoring), scoring.max_multiplier);
+    }
+
+    #[test]
+    fn reset_keeps_best_streak() {
+        let scoring = ScoringSettings::default();
+        let mut streak = Streak::default();
+        streak.hit(&scoring, 1, 1);
+        streak.hit(&scoring, 1, 1);
+        streak.reset();
+        assert_eq!(streak.hits, 0);
+        assert_eq!(streak.best, 2);
+        assert_eq!(streak.multiplier(&scoring), 1);
+    }
+
+    #[test]
+    fn zero_streak_step_does_not_panic() {
+        let scoring = ScoringSettings { streak_step: 0, ..Default::default() };
+        assert_eq!(Streak { hits: 3, best: 3 }.multiplier(&scoring), scoring.max_multiplier);
+    }
+
+    #[test]
+    fn penalty_never_goes_below_zero() {
+        assert_eq!(penalty(1, 0), 0);
+        assert_eq!(penalty(1, 5), 1);
+        assert_eq!(penalty(0, 5), 0);
+    }
+}